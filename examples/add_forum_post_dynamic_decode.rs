@@ -13,8 +13,8 @@ use frame_support::{
 use mycelium::{
     sp_core::crypto::AccountId32,
     Api,
+    Signer,
 };
-use sp_core::sr25519::Pair;
 use sp_keyring::AccountKeyring;
 use std::{
     thread,
@@ -77,7 +77,7 @@ async fn main() -> Result<(), mycelium::Error> {
     sleep(DELAY);
 
     let inserted_post: Option<Vec<u8>> = api
-        .fetch_opaque_storage_map("ForumModule", "AllPosts", last_post_id)
+        .fetch_opaque_storage_map("ForumModule", "AllPosts", last_post_id, None)
         .await?;
 
     println!("inserted-post: {:#?}", inserted_post);
@@ -121,7 +121,7 @@ async fn main() -> Result<(), mycelium::Error> {
     sleep(DELAY);
 
     if let Some(post_comments) = api
-        .fetch_opaque_storage_map("ForumModule", "Kids", last_post_id)
+        .fetch_opaque_storage_map("ForumModule", "Kids", last_post_id, None)
         .await?
     {
         let post_comments: BoundedVec<u32, MaxComments> =
@@ -139,7 +139,13 @@ async fn main() -> Result<(), mycelium::Error> {
     dbg!(all_posts);
 
     let kids: Option<Vec<Vec<u8>>> = api
-        .fetch_opaque_storage_map_paged("ForumModule", "Kids", 10, None::<u32>)
+        .fetch_opaque_storage_map_paged(
+            "ForumModule",
+            "Kids",
+            10,
+            None::<u32>,
+            None,
+        )
         .await?;
     dbg!(&kids);
     if let Some(kids) = kids {
@@ -156,7 +162,7 @@ async fn main() -> Result<(), mycelium::Error> {
 async fn add_post(
     api: &Api,
     post: &str,
-    author: &Pair,
+    author: &dyn Signer,
 ) -> Result<u32, mycelium::Error> {
     let pallet = api.metadata().pallet("ForumModule")?;
     let call_index = pallet
@@ -182,7 +188,7 @@ async fn add_post(
 /// this is executing in between the function calls in the following intended extrinsics
 async fn get_current_item(api: &Api) -> Result<u32, mycelium::Error> {
     let current_item: Option<Vec<u8>> = api
-        .fetch_opaque_storage_value("ForumModule", "ItemCounter")
+        .fetch_opaque_storage_value("ForumModule", "ItemCounter", None)
         .await?;
 
     if let Some(current_item) = current_item {
@@ -199,7 +205,7 @@ async fn add_comment_to(
     api: &Api,
     parent_item: u32,
     comment: &str,
-    author: &Pair,
+    author: &dyn Signer,
 ) -> Result<u32, mycelium::Error> {
     let pallet = api.metadata().pallet("ForumModule")?;
     let call_index = pallet.calls.get("comment_on").unwrap();
@@ -238,7 +244,13 @@ async fn get_all_posts(api: &Api) -> Result<Vec<Post>, mycelium::Error> {
     println!("---->Getting all the post_id...");
     let next_to: Option<u32> = None;
     let storage_values: Option<Vec<Vec<u8>>> = api
-        .fetch_opaque_storage_map_paged("ForumModule", "AllPosts", 10, next_to)
+        .fetch_opaque_storage_map_paged(
+            "ForumModule",
+            "AllPosts",
+            10,
+            next_to,
+            None,
+        )
         .await?;
     if let Some(storage_values) = storage_values {
         for bytes in storage_values.into_iter() {
@@ -254,7 +266,7 @@ async fn get_post(
     post_id: u32,
 ) -> Result<Option<Post>, mycelium::Error> {
     if let Some(post) = api
-        .fetch_opaque_storage_map("ForumModule", "AllPosts", post_id)
+        .fetch_opaque_storage_map("ForumModule", "AllPosts", post_id, None)
         .await?
     {
         let post: Post = Post::decode(&mut post.as_slice())?;
@@ -269,7 +281,7 @@ async fn get_kids(
     item_id: u32,
 ) -> Result<Option<BoundedVec<u32, MaxComments>>, mycelium::Error> {
     if let Some(kids) = api
-        .fetch_opaque_storage_map("ForumModule", "Kids", item_id)
+        .fetch_opaque_storage_map("ForumModule", "Kids", item_id, None)
         .await?
     {
         let kids: BoundedVec<u32, MaxComments> =
@@ -311,7 +323,12 @@ async fn get_comment(
 ) -> Result<Option<Comment>, mycelium::Error> {
     println!("getting comment {}", comment_id);
     if let Some(comment) = api
-        .fetch_opaque_storage_map("ForumModule", "AllComments", comment_id)
+        .fetch_opaque_storage_map(
+            "ForumModule",
+            "AllComments",
+            comment_id,
+            None,
+        )
         .await?
     {
         let comment: Comment = Decode::decode(&mut comment.as_slice())?;
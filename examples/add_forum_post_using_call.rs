@@ -2,17 +2,19 @@
 #![deny(warnings)]
 use frame_support::BoundedVec;
 use mycelium::{
+    types::extrinsic_params::{
+        PlainTipExtrinsicParams,
+        PlainTipExtrinsicParamsBuilder,
+    },
     Api,
 };
 use node_template_runtime::{
     pallet_forum,
     Call,
-    Header,
     Runtime,
 };
 use pallet_forum::Post;
 use sp_keyring::AccountKeyring;
-use sp_runtime::generic::Era;
 
 #[tokio::main]
 async fn main() -> Result<(), mycelium::Error> {
@@ -20,30 +22,22 @@ async fn main() -> Result<(), mycelium::Error> {
 
     let api = Api::new("http://localhost:9933").await?;
 
-
-    let head_hash = api
-        .chain_get_finalized_head()
-        .await?
-        .expect("must have a finalized head");
-    let header: Header = api
-        .chain_get_header(head_hash)
-        .await?
-        .expect("must have a header");
-
     let call: Call = Call::ForumModule(pallet_forum::Call::post_content {
         content: BoundedVec::try_from(b"Hello world post using Call!".to_vec())
             .unwrap(),
     });
 
+    // the era is computed from the latest finalized block
     let period = 5;
-    let era = Era::mortal(period, header.number.into());
-
+    let params = PlainTipExtrinsicParamsBuilder::new().mortal(period).tip(10);
     let extrinsic = api
-        .sign_extrinsic_with_era(&from, call, Some(era), Some(head_hash), Some(10))
+        .sign_extrinsic_with_params::<PlainTipExtrinsicParams, _>(
+            &from, call, params,
+        )
         .await?;
 
     let current_item: Option<u32> = api
-        .fetch_storage_value("ForumModule", "ItemCounter")
+        .fetch_storage_value("ForumModule", "ItemCounter", None)
         .await?;
     println!("current item: {:?}", current_item);
 
@@ -54,7 +48,7 @@ async fn main() -> Result<(), mycelium::Error> {
     let current_item = current_item.unwrap_or(0);
 
     let inserted_post: Option<Post<Runtime>> = api
-        .fetch_storage_map("ForumModule", "AllPosts", current_item)
+        .fetch_storage_map("ForumModule", "AllPosts", current_item, None)
         .await?;
     println!("inserted-post: {:#?}", inserted_post);
     if let Some(inserted_post) = inserted_post {
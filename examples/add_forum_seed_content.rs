@@ -5,8 +5,10 @@ use frame_support::{
     pallet_prelude::ConstU32,
     BoundedVec,
 };
-use mycelium::Api;
-use sp_core::sr25519::Pair;
+use mycelium::{
+    Api,
+    Signer,
+};
 use sp_keyring::AccountKeyring;
 
 const DELAY: u64 = 1500; // in ms
@@ -135,8 +137,8 @@ async fn main() -> anyhow::Result<()> {
 
 async fn more_seed(
     api: &Api,
-    alice: &Pair,
-    bob: &Pair,
+    alice: &dyn Signer,
+    bob: &dyn Signer,
 ) -> Result<(), mycelium::Error> {
 
 
@@ -194,7 +196,7 @@ fn sleep(ms: u64) {
 async fn add_post(
     api: &Api,
     post: &str,
-    author: &Pair,
+    author: &dyn Signer,
 ) -> Result<u32, mycelium::Error> {
     println!("post len: {}", post.len());
     let pallet = api.metadata().pallet("ForumModule")?;
@@ -222,7 +224,7 @@ async fn add_post(
 /// this is executing in between the function calls in the following intended extrinsics
 async fn get_current_item(api: &Api) -> Result<u32, mycelium::Error> {
     let current_item: Option<Vec<u8>> = api
-        .fetch_opaque_storage_value("ForumModule", "ItemCounter")
+        .fetch_opaque_storage_value("ForumModule", "ItemCounter", None)
         .await?;
 
     if let Some(current_item) = current_item {
@@ -239,7 +241,7 @@ async fn add_comment_to(
     api: &Api,
     parent_item: u32,
     comment: &str,
-    author: &Pair,
+    author: &dyn Signer,
 ) -> Result<u32, mycelium::Error> {
     println!("comment len: {}", comment.len());
     let pallet = api.metadata().pallet("ForumModule")?;
@@ -31,7 +31,7 @@ async fn main() -> Result<(), mycelium::Error> {
     println!("account info: {:#?}", info);
 
     let result = api
-        .balance_transfer(from, to, 42_000_000_000_000_u128, None)
+        .balance_transfer(&from, to, 42_000_000_000_000_u128, None)
         .await?;
     println!("result: {:?}", result);
     Ok(())
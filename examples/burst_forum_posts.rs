@@ -0,0 +1,38 @@
+//! This example submits several forum posts from the same account at once,
+//! the nonces are handed out by the nonce manager of the api
+#![deny(warnings)]
+use frame_support::{
+    pallet_prelude::ConstU32,
+    BoundedVec,
+};
+use mycelium::Api;
+use sp_keyring::AccountKeyring;
+
+type MaxContentLength = ConstU32<280>;
+
+#[tokio::main]
+async fn main() -> Result<(), mycelium::Error> {
+    let api = Api::new("http://localhost:9933").await?;
+    let pallet = api.metadata().pallet("ForumModule")?;
+    let call_index = [pallet.index, pallet.calls["post_content"]];
+
+    let mut handles = vec![];
+    for i in 0..5 {
+        let api = api.clone();
+        handles.push(tokio::spawn(async move {
+            let alice: sp_core::sr25519::Pair = AccountKeyring::Alice.pair();
+            let content: BoundedVec<u8, MaxContentLength> =
+                BoundedVec::try_from(format!("Burst post #{}", i).into_bytes())
+                    .expect("content is too long");
+            let extrinsic = api
+                .sign_extrinsic(&alice, (call_index, content), None)
+                .await?;
+            api.submit_extrinsic(extrinsic).await
+        }));
+    }
+    for handle in handles {
+        let result = handle.await.expect("task must not panic");
+        println!("result: {:?}", result);
+    }
+    Ok(())
+}
@@ -0,0 +1,37 @@
+//! This example reads the forum posts twice at the same block with the read cache enabled,
+//! the values of the second pass are served from the cache, only the keys are fetched again
+#![deny(warnings)]
+use futures::TryStreamExt;
+use mycelium::{
+    Api,
+    CacheConfig,
+};
+use node_template_runtime::Runtime;
+use pallet_forum::Post;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut api = Api::new("http://localhost:9933").await?;
+    api.enable_cache(CacheConfig::default());
+
+    let finalized = api
+        .chain_get_finalized_head()
+        .await?
+        .expect("must have a finalized block");
+    for pass in 1..=2 {
+        let posts: Vec<(u32, Post<Runtime>)> = api
+            .iter_storage_map("ForumModule", "AllPosts", Some(finalized))
+            .try_collect()
+            .await?;
+        let stats = api.cache().expect("cache is enabled").stats();
+        println!(
+            "pass {}: {} posts, {} hits, {} misses, hit rate: {:.2}",
+            pass,
+            posts.len(),
+            stats.hits,
+            stats.misses,
+            stats.hit_rate()
+        );
+    }
+    Ok(())
+}
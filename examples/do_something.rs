@@ -28,8 +28,9 @@ async fn main() -> Result<(), mycelium::Error> {
     //wait for a little bit
     std::thread::sleep(std::time::Duration::from_millis(1000));
 
-    let something: Result<Option<u32>, _> =
-        api.fetch_storage_value("TemplateModule", "Something").await;
+    let something: Result<Option<u32>, _> = api
+        .fetch_storage_value("TemplateModule", "Something", None)
+        .await;
     println!("something: {:?}", something);
 
     assert_eq!(something.ok().flatten(), Some(value));
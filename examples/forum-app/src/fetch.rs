@@ -11,51 +11,112 @@ use frame_support::{
     traits::Get,
     BoundedVec,
 };
+use futures::TryStreamExt;
 use mycelium::{
     sp_core::{
         crypto::AccountId32,
         H256,
     },
     types::extrinsics::GenericAddress,
+    utils,
     Api,
 };
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    time::Duration,
+};
 
 const FORUM_MODULE: &str = "ForumModule";
 const ALL_POSTS: &str = "AllPosts";
 const ALL_COMMENTS: &str = "AllComments";
 const KIDS: &str = "Kids";
+const ITEM_COUNTER: &str = "ItemCounter";
+const SYSTEM_MODULE: &str = "System";
+const BLOCK_HASH: &str = "BlockHash";
+/// The waits before watching the forum again after the subscription stopped
+const WATCH_MIN_BACKOFF: Duration = Duration::from_secs(1);
+const WATCH_MAX_BACKOFF: Duration = Duration::from_secs(60);
 
 pub async fn get_post_list(api: &Api) -> Result<Vec<PostDetail>, Error> {
-    let mut all_post = Vec::with_capacity(10);
-    log::info!("---->Getting all the post_id...");
-    let next_to: Option<u32> = None;
-    let storage_values: Option<Vec<Vec<u8>>> = api
-        .fetch_opaque_storage_map_paged(FORUM_MODULE, ALL_POSTS, 10, next_to)
+    log::info!("---->Getting all the posts...");
+    let posts: Vec<Post> = api
+        .iter_storage_map::<u32, Post>(FORUM_MODULE, ALL_POSTS, None)
+        .map_ok(|(_post_id, post)| post)
+        .try_collect()
         .await?;
-    log::info!("here..");
-    if let Some(storage_values) = storage_values {
-        for (i, bytes) in storage_values.into_iter().enumerate() {
-            log::info!("At post: {}", i);
-            let post: Option<Post> = Post::decode(&mut bytes.as_slice()).ok();
-            if let Some(post) = post {
-                let reply_count = get_reply_count(api, post.post_id).await?;
-                let block_hash = get_block_hash(api, post.block_number)
-                    .await?
-                    .expect("must have a block hash");
-                all_post.push(PostDetail {
-                    post,
-                    reply_count,
-                    comments: vec![],
-                    block_hash,
-                });
-            }
-        }
+    let mut all_post = Vec::with_capacity(posts.len());
+    // fetch the replies of all the posts in one request
+    let kids_keys = posts
+        .iter()
+        .map(|post| {
+            api.metadata()
+                .storage_map_key(FORUM_MODULE, KIDS, post.post_id)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(mycelium::Error::from)?;
+    let all_kids: Vec<Option<BoundedVec<u32, MaxComments>>> =
+        api.fetch_storage_many(&kids_keys, None).await?;
+    let block_numbers: Vec<u32> =
+        posts.iter().map(|post| post.block_number).collect();
+    let block_hashes = get_block_hashes(api, &block_numbers).await?;
+    for (post, kids) in posts.into_iter().zip(all_kids) {
+        let reply_count = kids.map(|kids| kids.len()).unwrap_or(0);
+        let block_hash = block_hashes[&post.block_number].clone();
+        all_post.push(PostDetail {
+            post,
+            reply_count,
+            comments: vec![],
+            block_hash,
+        });
     }
     log::info!("done get_post_list..: {:#?}", all_post);
     all_post.sort_unstable_by_key(|item| item.post.post_id);
     Ok(all_post)
 }
 
+/// Same as [`watch_item_counter`] for as long as the app runs, the subscription is
+/// restarted with an exponential backoff when it fails or is closed by the node
+pub async fn keep_watching_item_counter(api: &Api, on_change: impl Fn()) {
+    let mut backoff = WATCH_MIN_BACKOFF;
+    loop {
+        let started = utils::now();
+        match watch_item_counter(api, &on_change).await {
+            Ok(()) => log::warn!("The node closed the forum subscription"),
+            Err(e) => log::error!("Stopped watching the forum: {}", e),
+        }
+        // the subscription was working for a while, the node is reachable again
+        if utils::now().saturating_sub(started) > WATCH_MAX_BACKOFF {
+            backoff = WATCH_MIN_BACKOFF;
+        }
+        log::info!("Watching the forum again in {:?}", backoff);
+        utils::sleep(backoff).await;
+        backoff = (backoff * 2).min(WATCH_MAX_BACKOFF);
+        // items may have been added while the forum was not watched
+        on_change();
+    }
+}
+
+/// Calls `on_change` every time a post or a comment is added to the forum
+pub async fn watch_item_counter(
+    api: &Api,
+    on_change: impl Fn(),
+) -> Result<(), Error> {
+    let counter_key = api
+        .metadata()
+        .storage_value_key(FORUM_MODULE, ITEM_COUNTER)
+        .map_err(mycelium::Error::from)?;
+    let mut changes = api.subscribe_storage::<u32>(&[counter_key]).await?;
+    // the first change set is the current value
+    changes.try_next().await?;
+    while changes.try_next().await?.is_some() {
+        on_change();
+    }
+    Ok(())
+}
+
 pub async fn get_reply_count(api: &Api, post_id: u32) -> Result<usize, Error> {
     let reply_count = get_kids(api, post_id)
         .await?
@@ -73,9 +134,7 @@ pub async fn get_post_details(
     if let Some(post) = post {
         let comment_replies = get_comment_replies(api, post_id).await?;
         let reply_count = get_reply_count(api, post_id).await?;
-        let block_hash = get_block_hash(api, post.block_number)
-            .await?
-            .expect("must have a block hash");
+        let block_hash = get_block_hash(api, post.block_number).await?;
         Ok(Some(PostDetail {
             post,
             comments: comment_replies,
@@ -89,7 +148,7 @@ pub async fn get_post_details(
 
 pub async fn get_post(api: &Api, post_id: u32) -> Result<Option<Post>, Error> {
     if let Some(post) = api
-        .fetch_opaque_storage_map(FORUM_MODULE, ALL_POSTS, post_id)
+        .fetch_opaque_storage_map(FORUM_MODULE, ALL_POSTS, post_id, None)
         .await?
     {
         let post: Option<Post> = Post::decode(&mut post.as_slice()).ok();
@@ -104,7 +163,7 @@ async fn get_kids(
     item_id: u32,
 ) -> Result<Option<BoundedVec<u32, MaxComments>>, Error> {
     if let Some(kids) = api
-        .fetch_opaque_storage_map(FORUM_MODULE, KIDS, item_id)
+        .fetch_opaque_storage_map(FORUM_MODULE, KIDS, item_id, None)
         .await?
     {
         let kids: Option<BoundedVec<u32, MaxComments>> =
@@ -140,9 +199,7 @@ pub async fn get_comment_detail(
 ) -> Result<Option<CommentDetail>, Error> {
     if let Some(comment) = get_comment(api, comment_id).await? {
         let kid_comments = get_comment_replies(api, comment_id).await?;
-        let block_hash = get_block_hash(api, comment.block_number)
-            .await?
-            .expect("must have a block hash");
+        let block_hash = get_block_hash(api, comment.block_number).await?;
 
         Ok(Some(CommentDetail {
             comment,
@@ -160,7 +217,7 @@ pub async fn get_comment(
 ) -> Result<Option<Comment>, Error> {
     log::debug!("getting comment_id: {}", comment_id);
     if let Some(comment) = api
-        .fetch_opaque_storage_map(FORUM_MODULE, ALL_COMMENTS, comment_id)
+        .fetch_opaque_storage_map(FORUM_MODULE, ALL_COMMENTS, comment_id, None)
         .await?
     {
         let comment: Option<Comment> =
@@ -175,9 +232,50 @@ pub async fn get_comment(
 pub async fn get_block_hash(
     api: &Api,
     block_number: u32,
-) -> Result<Option<String>, Error> {
-    let block_hash = api.fetch_block_hash(block_number).await?;
-    Ok(block_hash.map(|hash| format!("{:#x}", hash)))
+) -> Result<String, Error> {
+    let block_hash =
+        api.fetch_block_hash(block_number).await?.ok_or_else(|| {
+            mycelium::Error::EmptyResponse(format!(
+                "chain_getBlockHash({})",
+                block_number
+            ))
+        })?;
+    Ok(format!("{:#x}", block_hash))
+}
+
+/// The hashes of the blocks `block_numbers`, the recent blocks are read together
+/// from `System.BlockHash` and the blocks which are too old to be kept there
+/// are asked one by one to the node
+pub async fn get_block_hashes(
+    api: &Api,
+    block_numbers: &[u32],
+) -> Result<BTreeMap<u32, String>, Error> {
+    // many posts are added in the same block
+    let block_numbers: BTreeSet<u32> = block_numbers.iter().copied().collect();
+    let block_hash_keys = block_numbers
+        .iter()
+        .map(|block_number| {
+            api.metadata().storage_map_key(
+                SYSTEM_MODULE,
+                BLOCK_HASH,
+                block_number,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(mycelium::Error::from)?;
+    let stored_hashes: Vec<Option<H256>> =
+        api.fetch_storage_many(&block_hash_keys, None).await?;
+    let mut block_hashes = BTreeMap::new();
+    for (block_number, block_hash) in
+        block_numbers.into_iter().zip(stored_hashes)
+    {
+        let block_hash = match block_hash {
+            Some(block_hash) => format!("{:#x}", block_hash),
+            None => get_block_hash(api, block_number).await?,
+        };
+        block_hashes.insert(block_number, block_hash);
+    }
+    Ok(block_hashes)
 }
 
 pub async fn add_post(api: &Api, post: &str) -> Result<Option<H256>, Error> {
@@ -230,6 +328,22 @@ pub async fn send_reward(
         Compact(amount),
     );
 
+    // show the fee before asking the wallet for the signature
+    let fee = api
+        .estimate_fee(&api.unsigned_extrinsic(balance_transfer_call.clone()))
+        .await?;
+    let confirmed = sauron::window()
+        .confirm_with_message(&format!(
+            "Sending a reward of {} costs {} in fees, continue?",
+            amount,
+            fee.total()
+        ))
+        .unwrap_or(false);
+    if !confirmed {
+        log::info!("Reward cancelled");
+        return Ok(None);
+    }
+
     let tx_hash =
         crate::sign_and_submit_call(api, balance_transfer_call).await?;
     log::debug!("Sent some coins to with a tx_hash: {:?}", tx_hash);
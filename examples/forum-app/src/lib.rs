@@ -3,17 +3,21 @@ use crate::sp_core::H256;
 use codec::Encode;
 use content::*;
 use mycelium::{
+    signer::JsSigner,
     sp_core,
-    sp_core::{
-        crypto::AccountId32,
+    sp_core::crypto::AccountId32,
+    types::validity::{
+        DispatchFailure,
+        DryRunResult,
     },
     Api,
+    Signer,
 };
 use sauron::prelude::*;
 use sp_keyring::AccountKeyring;
 use std::fmt;
+use wasm_bindgen::JsValue;
 use wasm_bindgen_futures::spawn_local;
-use mycelium::sp_core::Pair;
 
 const URL: &str = "http://localhost:9933";
 const BLOCK_EXPLORER: &str =
@@ -41,6 +45,8 @@ pub enum Msg {
     Errored(Error),
     /// Initiating the Api
     InitApi(Api),
+    /// A post or a comment was added to the forum
+    ItemsChanged,
     UrlChanged(String),
     /// The user clicks on the `submit` button, the program will then show the form for submitting
     /// a new post
@@ -58,6 +64,8 @@ pub enum Msg {
     /// The program will use the `new_post` field of App and use it as input for new post
     /// submission
     SubmitPost,
+    /// The new post is submitted, the posts are shown once it is added
+    PostSubmitted,
     /// The user clicks on the `reward` button next to the author id
     /// The program will then use the fix reward amount to send tokens from the user to the post
     /// author's account
@@ -78,6 +86,8 @@ pub enum Error {
     MyCeliumError(#[from] mycelium::Error),
     #[error("Content too long: {0}, max: {1}")]
     ContentTooLong(usize, u32),
+    #[error("The extrinsic would fail with {0}::{1}")]
+    DispatchError(String, String),
 }
 
 struct App {
@@ -85,6 +95,8 @@ struct App {
     new_comment: Option<String>,
     new_post: Option<String>,
     api: Option<Api>,
+    /// a new post was submitted, show the posts when it is added
+    pending_post: bool,
 }
 
 impl Default for App {
@@ -94,6 +106,7 @@ impl Default for App {
             new_comment: None,
             new_post: None,
             api: None,
+            pending_post: false,
         }
     }
 }
@@ -107,7 +120,12 @@ impl App {
                     match Api::new(URL).await {
                         Ok(api) => {
                             log::info!("got some api..");
-                            program.dispatch(Msg::InitApi(api));
+                            program.dispatch(Msg::InitApi(api.clone()));
+                            // keeps running for as long as the app
+                            fetch::keep_watching_item_counter(&api, || {
+                                program.dispatch(Msg::ItemsChanged)
+                            })
+                            .await;
                         }
                         Err(e) => {
                             program.dispatch(Msg::Errored(
@@ -163,7 +181,7 @@ impl App {
                             "Posting a new content successful with tx_hash {:?}",
                             tx_hash
                         );
-                            program.dispatch_with_delay(Msg::FetchPosts, 1000);
+                            program.dispatch(Msg::PostSubmitted);
                         }
                         Err(e) => {
                             log::error!(
@@ -256,18 +274,18 @@ impl App {
                     let api = api.unwrap();
                     match fetch::send_reward(&api, author, reward_amount).await
                     {
-                        Ok(tx_hash) => {
+                        Ok(Some(tx_hash)) => {
                             log::info!(
                                 "Author rewarded with a tx_hash {:?}",
                                 tx_hash
                             );
                             program.dispatch_with_delay(
-                                Msg::RewardFinish(
-                                    tx_hash.expect("must have the hash"),
-                                ),
+                                Msg::RewardFinish(tx_hash),
                                 2000,
                             );
                         }
+                        // the user declined the fee
+                        Ok(None) => (),
                         Err(e) => {
                             log::error!(
                                 "Something is wrong when submitting post: {}",
@@ -393,6 +411,21 @@ impl Application<Msg> for App {
                 self.fetch_posts()
             }
             Msg::FetchPosts => self.fetch_posts(),
+            Msg::ItemsChanged => {
+                // refresh the list, without leaving the page the user is reading
+                if self.pending_post
+                    || matches!(self.content, Some(Content::Posts(_)))
+                {
+                    self.pending_post = false;
+                    self.fetch_posts()
+                } else {
+                    Cmd::none()
+                }
+            }
+            Msg::PostSubmitted => {
+                self.pending_post = true;
+                Cmd::none()
+            }
             Msg::PostsReceived(posts) => {
                 log::debug!("posts: {:#?}", posts);
                 self.content = Some(Content::from(posts));
@@ -490,7 +523,15 @@ impl Application<Msg> for App {
     }
 }
 
-/// TODO: This should be hookup to the browser extension
+#[wasm_bindgen(inline_js = "
+export function wallet_signer() { return globalThis.walletSigner; }")]
+extern "C" {
+    /// the account and signer of the browser wallet set in `index.html`
+    fn wallet_signer() -> JsValue;
+}
+
+/// Sign the call with the account of the browser wallet,
+/// or with alice when there is no browser wallet
 pub async fn sign_and_submit_call<Call>(
     api: &Api,
     call: Call,
@@ -498,15 +539,37 @@ pub async fn sign_and_submit_call<Call>(
 where
     Call: Encode + Clone + fmt::Debug,
 {
-    // we use alice for now, for simplicity
-    let signer: sp_core::sr25519::Pair = AccountKeyring::Alice.pair();
-    let signer_account = AccountId32::from(signer.public());
-    let nonce = api.get_nonce_for_account(&signer_account).await?;
-
-    let (payload, extra) = api.compose_opaque_payload_and_extra(nonce, call.clone(), None, None, None).await?;
-    let signature = signer.sign(&payload);
-
-    let tx_hash = api.submit_signed_call(call, &signer_account, signature.into(), extra).await?;
+    let wallet = wallet_signer();
+    let (extrinsic, account) = if wallet.is_undefined() {
+        let signer: sp_core::sr25519::Pair = AccountKeyring::Alice.pair();
+        let extrinsic = api.sign_extrinsic(&signer, call, None).await?;
+        (extrinsic, signer.account_id())
+    } else {
+        let signer = JsSigner::from_injected_account(&wallet, wallet.clone())?;
+        let extrinsic = api.sign_extrinsic(&signer, call, None).await?;
+        (extrinsic, signer.account_id())
+    };
+    // reject the extrinsics which would fail, ie: `ForumModule::ContentTooLong`
+    // before paying for them
+    let rejection = match api.dry_run(&extrinsic).await {
+        Ok(DryRunResult::Success) => None,
+        Ok(DryRunResult::Failed(DispatchFailure::Pallet(error))) => {
+            Some(Error::DispatchError(error.pallet, error.error))
+        }
+        Ok(result) => Some(Error::RequestError(format!("{:?}", result))),
+        // the node may not allow the unsafe `system_dryRun`
+        Err(e) => {
+            log::warn!("Unable to dry run the extrinsic: {}", e);
+            None
+        }
+    };
+    if let Some(error) = rejection {
+        // the nonce of the rejected extrinsic is never used, so the next
+        // extrinsic needs to fetch it again from the node
+        api.nonce_manager().resync(&account);
+        return Err(error);
+    }
+    let tx_hash = api.submit_extrinsic(extrinsic).await?;
     Ok(tx_hash)
 }
 
@@ -0,0 +1,31 @@
+//! This example shows what changed in the forum storage between two blocks,
+//! pass the block numbers as arguments, ie: `forum_storage_diff 10 20`
+#![deny(warnings)]
+use mycelium::Api;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<u32> = std::env::args()
+        .skip(1)
+        .map(|arg| arg.parse())
+        .collect::<Result<_, _>>()?;
+    let (from, to) = match args.as_slice() {
+        [from, to] => (*from, *to),
+        _ => anyhow::bail!("usage: forum_storage_diff <from block> <to block>"),
+    };
+
+    let api = Api::new("http://localhost:9933").await?;
+    let from = api
+        .fetch_block_hash(from)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("no block #{}", from))?;
+    let to = api
+        .fetch_block_hash(to)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("no block #{}", to))?;
+
+    let diff = api.storage_diff("ForumModule", from, to).await?;
+    println!("{}", diff);
+    println!("{:#}", diff.to_json());
+    Ok(())
+}
@@ -1,6 +1,11 @@
 //! An example using an offline extrinsic, using the types of the instantiated chain
 #![deny(warnings)]
 use mycelium::{
+    types::extrinsic_params::{
+        AssetTip,
+        AssetTipExtrinsicParams,
+        AssetTipExtrinsicParamsBuilder,
+    },
     Api,
 };
 use node_template_runtime::{
@@ -40,13 +45,14 @@ async fn main() -> Result<(), mycelium::Error> {
 
 let era = Era::mortal(period, header.number.into());
 
+    // the tip is paid in the native currency since no asset is specified,
+    // use `AssetTip::new(10).of_asset(asset_id)` to pay it in an asset
+    let params = AssetTipExtrinsicParamsBuilder::new()
+        .era(era, head_hash)
+        .tip(AssetTip::new(10));
     let xt = api
-        .sign_extrinsic_with_era(
-            &from,
-            call,
-            Some(era),
-            Some(head_hash),
-            Some(10),
+        .sign_extrinsic_with_params::<AssetTipExtrinsicParams, _>(
+            &from, call, params,
         )
         .await?;
 
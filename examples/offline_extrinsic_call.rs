@@ -1,6 +1,11 @@
 //! An example using an offline extrinsic, using the types of the instantiated chain
 #![deny(warnings)]
 use mycelium::{
+    types::extrinsic_params::{
+        ExtrinsicParams,
+        PlainTipExtrinsicParams,
+        PlainTipExtrinsicParamsBuilder,
+    },
     Api,
 };
 use node_template_runtime::{
@@ -50,7 +55,16 @@ async fn main() -> Result<(), mycelium::Error> {
 
     let signer_account = AccountId32::from(from.public());
 
-    let (payload, extra) = api.compose_payload_and_extra(&signer_account, call.clone(), Some(era), Some(head_hash),  Some(10)).await?;
+    let nonce = api.get_nonce_for_account(&signer_account).await?;
+
+    let params = PlainTipExtrinsicParams::new(
+        nonce,
+        PlainTipExtrinsicParamsBuilder::new()
+            .era(era, head_hash)
+            .tip(10),
+    );
+
+    let (payload, extra) = api.compose_payload_and_extra(call.clone(), &params).await?;
 
     let signature = payload.using_encoded(|payload|from.sign(payload));
     let multi_signature = MultiSignature::from(signature);
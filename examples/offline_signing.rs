@@ -0,0 +1,78 @@
+//! This example creates a transaction document, signs it as an offline machine would,
+//! then submits the signed document.
+//! The metadata is carried to the offline machine along with the document, so the call
+//! description can be checked against the encoded call before signing.
+#![deny(warnings)]
+use codec::{
+    Compact,
+    Decode,
+    Encode,
+};
+use mycelium::{
+    frame_metadata::RuntimeMetadataPrefixed,
+    types::{
+        extrinsic_params::{
+            PlainTipExtrinsicParams,
+            PlainTipExtrinsicParamsBuilder,
+        },
+        extrinsics::GenericAddress,
+        signed_extensions::SignedExtensions,
+        unsigned_transaction::{
+            SignedTransaction,
+            UnsignedTransaction,
+        },
+    },
+    Api,
+    BaseApi,
+    Metadata,
+};
+use sp_keyring::AccountKeyring;
+use std::convert::TryFrom;
+
+#[tokio::main]
+async fn main() -> Result<(), mycelium::Error> {
+    let api = Api::new("http://localhost:9933").await?;
+
+    // online: create the document and carry it to the offline machine
+    let alice = AccountKeyring::Alice.to_account_id();
+    let to = AccountKeyring::Charlie.to_account_id();
+    let call_index = api.pallet_call_index("Balances", "transfer")?;
+    let call = (call_index, GenericAddress::Id(to), Compact(42_000_u128));
+    let document = api
+        .unsigned_transaction::<PlainTipExtrinsicParams, _>(
+            call,
+            &alice,
+            PlainTipExtrinsicParamsBuilder::new(),
+        )
+        .await?;
+    let json = document.to_json()?;
+    println!("unsigned document: {}", json);
+    let metadata_bytes = BaseApi::new("http://localhost:9933")
+        .fetch_runtime_metadata_at(None)
+        .await?
+        .expect("must have a metadata")
+        .encode();
+
+    // offline: review and sign the document, the call description is checked
+    // against the encoded call with the metadata
+    let metadata = Metadata::try_from(RuntimeMetadataPrefixed::decode(
+        &mut metadata_bytes.as_slice(),
+    )?)?;
+    let document = UnsignedTransaction::from_json(&json)?;
+    println!(
+        "signing {}.{}",
+        document.call_description.pallet, document.call_description.call
+    );
+    let signer: sp_core::sr25519::Pair = AccountKeyring::Alice.pair();
+    let signed = document
+        .sign(&signer, &SignedExtensions::default(), &metadata)
+        .await?;
+    let bytes = signed.to_bytes();
+    println!("signed document is {} bytes", bytes.len());
+
+    // online: submit the signed document
+    let signed = SignedTransaction::from_bytes(&bytes)?;
+    let tx_hash = api.submit_signed_document(&signed).await?;
+    println!("tx_hash: {:?}", tx_hash);
+    Ok(())
+}
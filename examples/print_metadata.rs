@@ -8,7 +8,17 @@ use mycelium::{
 async fn main() -> Result<(), mycelium::Error> {
     let api = Api::new("http://localhost:9933").await?;
     let metadata: &Metadata = api.metadata();
-    println!("metadata: {:#?}", metadata);
+    let explorer = metadata.explorer();
+    println!("{}", explorer.to_text());
     println!("runtime version: {:#?}", api.runtime_version());
+
+    println!("ForumModule: {}", explorer.pallet("ForumModule")?);
+
+    for found in explorer.search("posts").iter().take(5) {
+        println!(
+            "found {:?} {}.{} (score: {})",
+            found.kind, found.pallet, found.name, found.score
+        );
+    }
     Ok(())
 }
@@ -0,0 +1,51 @@
+//! This example lists the storage of a contract deployed with `pallet_contracts`,
+//! pass the ss58 address of the contract as the first argument
+#![deny(warnings)]
+use futures::TryStreamExt;
+use mycelium::{
+    sp_core::{
+        crypto::{
+            AccountId32,
+            Ss58Codec,
+        },
+        storage::StorageKey,
+    },
+    Api,
+};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .expect("usage: show_contract_storage <contract address>");
+    let contract = AccountId32::from_ss58check(&address)
+        .map_err(|e| anyhow::anyhow!("invalid address: {:?}", e))?;
+
+    let api = Api::new("http://localhost:9933").await?;
+    let child_info =
+        match api.fetch_contract_child_info(&contract, None).await? {
+            Some(child_info) => child_info,
+            None => {
+                println!("There is no contract at {}", address);
+                return Ok(());
+            }
+        };
+
+    let entries: Vec<(StorageKey, Vec<u8>)> = api
+        .iter_child_storage(&child_info, StorageKey(vec![]), None)
+        .try_collect()
+        .await?;
+    println!("The contract has {} storage entries", entries.len());
+    for (key, value) in entries {
+        let size = api
+            .fetch_child_storage_size(&child_info, key.clone(), None)
+            .await?;
+        println!(
+            "0x{}: 0x{} ({:?} bytes)",
+            hex::encode(&key.0),
+            hex::encode(&value),
+            size
+        );
+    }
+    Ok(())
+}
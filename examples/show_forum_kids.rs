@@ -0,0 +1,37 @@
+//! This example lists the replies of every forum item,
+//! the parent item id is decoded back out of the storage key of `Kids`
+#![deny(warnings)]
+use futures::TryStreamExt;
+use mycelium::Api;
+
+#[tokio::main]
+async fn main() -> Result<(), mycelium::Error> {
+    let api = Api::new("http://localhost:9933").await?;
+
+    let all_kids: Vec<(u32, Vec<u32>)> = api
+        .iter_storage_map("ForumModule", "Kids", None)
+        .try_collect()
+        .await?;
+    for (parent_item, kids) in all_kids {
+        println!("item {} has the replies: {:?}", parent_item, kids);
+    }
+
+    // without knowing the key type
+    let kids_prefix = api
+        .metadata()
+        .storage_map_key_prefix("ForumModule", "Kids")?;
+    let kids_keys = api
+        .fetch_storage_keys_paged_by_prefix(kids_prefix, 10, None, None)
+        .await?
+        .unwrap_or_default();
+    for kids_key in kids_keys {
+        let keys = api.metadata().decode_storage_key_dynamic(
+            "ForumModule",
+            "Kids",
+            &kids_key,
+        )?;
+        let keys: Vec<_> = keys.iter().map(|key| key.to_json()).collect();
+        println!("Kids key: {:?}", keys);
+    }
+    Ok(())
+}
@@ -0,0 +1,25 @@
+//! This example calls the runtime apis of the node template
+#![deny(warnings)]
+use mycelium::Api;
+use sp_keyring::AccountKeyring;
+
+#[tokio::main]
+async fn main() -> Result<(), mycelium::Error> {
+    let api = Api::new("http://localhost:9933").await?;
+
+    let version = api.runtime_api_version(None).await?;
+    println!("runtime api version: {:?}", version);
+
+    let alice = AccountKeyring::Alice.to_account_id();
+    let nonce = api.runtime_api_account_nonce(&alice, None).await?;
+    println!("alice nonce: {:?}", nonce);
+
+    // any runtime api can be called with the method name and the argument types
+    let slot_duration: Option<u64> =
+        api.runtime_call("AuraApi_slot_duration", (), None).await?;
+    println!("slot duration: {:?}", slot_duration);
+
+    let authorities = api.runtime_api_grandpa_authorities(None).await?;
+    println!("grandpa authorities: {:?}", authorities);
+    Ok(())
+}
@@ -0,0 +1,31 @@
+//! This example reads the balance of an account as it was at an older block
+#![deny(warnings)]
+use mycelium::{
+    Api,
+    StorageBackend,
+};
+use sp_keyring::AccountKeyring;
+
+#[tokio::main]
+async fn main() -> Result<(), mycelium::Error> {
+    let api = Api::new("http://localhost:9933").await?;
+    let alice = AccountKeyring::Alice.to_account_id();
+
+    let block_hash = api.fetch_block_hash(1).await?.expect("must have block 1");
+    let at_block = api.at(block_hash).await?;
+    println!(
+        "spec version at block 1: {}",
+        at_block.runtime_version().spec_version
+    );
+
+    let then = at_block.get_account_info(&alice).await?;
+    let now = api.get_account_info(&alice).await?;
+    println!("alice at block 1: {:#?}", then);
+    println!("alice now: {:#?}", now);
+
+    let total_issuance: Option<u128> = api
+        .fetch_storage_value("Balances", "TotalIssuance", Some(block_hash))
+        .await?;
+    println!("total issuance at block 1: {:?}", total_issuance);
+    Ok(())
+}
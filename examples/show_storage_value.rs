@@ -11,22 +11,25 @@ use sp_keyring::AccountKeyring;
 #[tokio::main]
 async fn main() -> Result<(), mycelium::Error> {
     let api = Api::new("http://localhost:9933").await?;
-    let something: Result<Option<u32>, _> =
-        api.fetch_storage_value("TemplateModule", "Something").await;
+    let something: Result<Option<u32>, _> = api
+        .fetch_storage_value("TemplateModule", "Something", None)
+        .await;
     println!("something: {:?}", something);
 
-    let total_issuance: Result<Option<u128>, _> =
-        api.fetch_storage_value("Balances", "TotalIssuance").await;
+    let total_issuance: Result<Option<u128>, _> = api
+        .fetch_storage_value("Balances", "TotalIssuance", None)
+        .await;
     println!("total issuance: {:?}", total_issuance);
 
     dbg!(
-        api.fetch_opaque_storage_value("Balances", "TotalIssuance")
+        api.fetch_opaque_storage_value("Balances", "TotalIssuance", None)
             .await?
     );
 
     let account_id: AccountId32 = AccountKeyring::Alice.to_account_id();
-    let account_info: Result<Option<AccountInfo>, _> =
-        api.fetch_storage_map("System", "Account", account_id).await;
+    let account_info: Result<Option<AccountInfo>, _> = api
+        .fetch_storage_map("System", "Account", account_id, None)
+        .await;
 
     println!("account_info: {:#?}", account_info);
 
@@ -64,6 +67,7 @@ async fn main() -> Result<(), mycelium::Error> {
             "Reserves",
             10,
             None::<AccountId32>,
+            None,
         )
         .await;
     println!("paged: {:?}", paged);
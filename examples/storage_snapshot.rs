@@ -0,0 +1,35 @@
+//! This example saves the storage of the forum and the accounts into a snapshot file,
+//! then reads the forum posts back from the file without the node
+#![deny(warnings)]
+use mycelium::{
+    types::snapshot::SnapshotStorage,
+    Api,
+    StorageBackend,
+};
+
+#[tokio::main]
+async fn main() -> Result<(), mycelium::Error> {
+    let api = Api::new("http://localhost:9933").await?;
+    let snapshot = api
+        .export_snapshot(&["ForumModule", "System"], None)
+        .await?;
+    println!(
+        "exported {} entries at block #{} {:?}",
+        snapshot.entries.len(),
+        snapshot.header.number,
+        snapshot.block_hash
+    );
+    snapshot.save("forum.snapshot")?;
+
+    let storage = SnapshotStorage::load("forum.snapshot")?;
+    let item_counter: Option<u32> = storage
+        .fetch_storage_value("ForumModule", "ItemCounter")
+        .await?;
+    println!("item counter: {:?}", item_counter);
+    let all_kids: Vec<(u32, Vec<u32>)> =
+        storage.iter_storage_map("ForumModule", "Kids")?;
+    for (item_id, kids) in all_kids {
+        println!("item {} has the replies: {:?}", item_id, kids);
+    }
+    Ok(())
+}
@@ -0,0 +1,47 @@
+//! This example shows how the balance of alice and the forum item counter changed
+//! from the genesis block up to the best block
+#![deny(warnings)]
+use mycelium::{
+    types::account_info::AccountInfo,
+    Api,
+};
+use sp_keyring::AccountKeyring;
+
+#[tokio::main]
+async fn main() -> Result<(), mycelium::Error> {
+    let api = Api::new("http://localhost:9933").await?;
+    let genesis = api.genesis_hash();
+
+    let alice = AccountKeyring::Alice.to_account_id();
+    let account_key =
+        api.metadata().storage_map_key("System", "Account", alice)?;
+    let changes = api
+        .query_storage::<AccountInfo>(&[account_key], genesis, None)
+        .await?;
+    for change in changes {
+        println!(
+            "{:?}: alice free balance: {:?}",
+            change.block_hash,
+            change.value.map(|info| info.data.free)
+        );
+    }
+
+    // decode the values without knowing their type
+    let counter_key = api
+        .metadata()
+        .storage_value_key("ForumModule", "ItemCounter")?;
+    let counter_type = api
+        .metadata()
+        .storage_value_type_id("ForumModule", "ItemCounter")?;
+    let changes = api
+        .query_storage_dynamic(&[(counter_key, counter_type)], genesis, None)
+        .await?;
+    for change in changes {
+        println!(
+            "{:?}: item counter: {:?}",
+            change.block_hash,
+            change.value.map(|value| value.to_json())
+        );
+    }
+    Ok(())
+}
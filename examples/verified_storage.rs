@@ -0,0 +1,40 @@
+//! This example reads the forum posts and the balance of alice along with a storage proof,
+//! which is verified against the state root of the finalized block
+#![deny(warnings)]
+use mycelium::{
+    types::account_info::AccountInfo,
+    Api,
+};
+use sp_keyring::AccountKeyring;
+
+#[tokio::main]
+async fn main() -> Result<(), mycelium::Error> {
+    let api = Api::new("http://localhost:9933").await?;
+    let finalized = api.chain_get_finalized_head().await?;
+
+    let alice = AccountKeyring::Alice.to_account_id();
+    let account_key =
+        api.metadata().storage_map_key("System", "Account", alice)?;
+    let account_info: Vec<Option<AccountInfo>> = api
+        .fetch_storage_with_proof(&[account_key], finalized)
+        .await?;
+    println!("verified alice account: {:#?}", account_info);
+
+    let post_keys = (0..3_u32)
+        .map(|post_id| {
+            api.metadata()
+                .storage_map_key("ForumModule", "AllPosts", post_id)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let posts = api
+        .fetch_opaque_storage_with_proof(&post_keys, finalized)
+        .await?;
+    for (post_id, post) in posts.iter().enumerate() {
+        println!(
+            "post {}: {} bytes verified",
+            post_id,
+            post.as_ref().map(|bytes| bytes.len()).unwrap_or(0)
+        );
+    }
+    Ok(())
+}
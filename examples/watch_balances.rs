@@ -0,0 +1,40 @@
+//! This example prints the balances of alice and bob every time they change,
+//! the changes are notified by the node over a WebSocket subscription
+#![deny(warnings)]
+use futures::TryStreamExt;
+use mycelium::{
+    types::account_info::AccountInfo,
+    Api,
+};
+use sp_keyring::AccountKeyring;
+
+#[tokio::main]
+async fn main() -> Result<(), mycelium::Error> {
+    let mut api = Api::new("http://localhost:9933").await?;
+    // without this, the finalized blocks are polled over http
+    api.set_ws_url("ws://localhost:9944");
+
+    let account_keys = [AccountKeyring::Alice, AccountKeyring::Bob]
+        .iter()
+        .map(|account| {
+            api.metadata().storage_map_key(
+                "System",
+                "Account",
+                account.to_account_id(),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut changes =
+        api.subscribe_storage::<AccountInfo>(&account_keys).await?;
+    while let Some(change_set) = changes.try_next().await? {
+        for change in change_set {
+            println!(
+                "{:?}: 0x{} free balance: {:?}",
+                change.block_hash,
+                hex::encode(&change.key.0[change.key.0.len() - 32..]),
+                change.value.map(|info| info.data.free)
+            );
+        }
+    }
+    Ok(())
+}
@@ -1,19 +1,51 @@
 use crate::{
+    types::signed_extensions::{
+        ExtensionEncoder,
+        SignedExtensions,
+    },
     Error,
     Metadata,
 };
+pub use at_block::AtBlock;
 pub use base_api::BaseApi;
 use delegate::delegate;
+pub use nonce_manager::NonceManager;
+pub use read_cache::{
+    CacheConfig,
+    CacheStats,
+    ReadCache,
+};
 use serde::de::DeserializeOwned;
 use sp_core::H256;
-use sp_runtime::traits::Header;
+use sp_runtime::{
+    generic::SignedBlock,
+    traits::{
+        Block,
+        Header,
+    },
+};
 use sp_version::RuntimeVersion;
+pub use storage_backend::StorageBackend;
 
+mod at_block;
 mod balance_api;
 mod base_api;
+mod child_storage_api;
 mod constant_api;
 mod extrinsic_api;
+mod fee_api;
+mod iter_storage_api;
+mod nonce_manager;
+mod query_storage_api;
+mod read_cache;
+mod runtime_api;
+mod snapshot_api;
 mod storage_api;
+mod storage_backend;
+mod storage_diff_api;
+mod storage_proof_api;
+mod subscription_api;
+mod validate_api;
 
 /// A more complex Api which requires prefetching some fields such as Metadata, genesis_hash and
 /// runtime version
@@ -23,6 +55,14 @@ pub struct Api {
     metadata: Metadata,
     genesis_hash: H256,
     runtime_version: RuntimeVersion,
+    /// the encoders of the signed extensions listed in the metadata
+    signed_extensions: SignedExtensions,
+    /// shared by the clones of this api
+    nonce_manager: NonceManager,
+    /// the WebSocket endpoint of the node, used for the subscriptions
+    ws_url: Option<String>,
+    /// shared by the clones of this api, reads are not cached when it is `None`
+    cache: Option<ReadCache>,
 }
 
 impl Api {
@@ -33,10 +73,6 @@ impl Api {
             #[call(fetch_finalized_head)]
             pub async fn chain_get_finalized_head(&self) -> Result<Option<H256>, Error>;
 
-            #[call(fetch_header)]
-            pub async fn chain_get_header<H>(&self, hash: H256) -> Result<Option<H>,Error>
-                where H:Header + DeserializeOwned;
-
             pub async fn author_submit_extrinsic(
                 &self,
                 hex_extrinsic: String,
@@ -50,6 +86,127 @@ impl Api {
         }
     }
 
+    /// The header of the block `hash`, from the cache when it is enabled
+    pub async fn chain_get_header<H>(
+        &self,
+        hash: H256,
+    ) -> Result<Option<H>, Error>
+    where
+        H: Header + DeserializeOwned,
+    {
+        let cached = self.cache.as_ref().and_then(|cache| cache.header(hash));
+        let value = match cached {
+            Some(value) => Some(value),
+            None => {
+                let value = self
+                    .base_api
+                    .json_request_value("chain_getHeader", vec![hash])
+                    .await?;
+                if let (Some(cache), Some(value)) = (&self.cache, &value) {
+                    cache.put_header(hash, value.clone());
+                }
+                value
+            }
+        };
+        Ok(value.map(serde_json::from_value).transpose()?)
+    }
+
+    /// The block `hash`, from the cache when it is enabled
+    pub async fn fetch_signed_block_by_hash<B>(
+        &self,
+        hash: H256,
+    ) -> Result<Option<SignedBlock<B>>, Error>
+    where
+        B: Block + DeserializeOwned,
+    {
+        let cached = self.cache.as_ref().and_then(|cache| cache.block(hash));
+        let value = match cached {
+            Some(value) => Some(value),
+            None => {
+                let value = self
+                    .base_api
+                    .json_request_value("chain_getBlock", vec![hash])
+                    .await?;
+                if let (Some(cache), Some(value)) = (&self.cache, &value) {
+                    cache.put_block(hash, value.clone());
+                }
+                value
+            }
+        };
+        Ok(value.map(serde_json::from_value).transpose()?)
+    }
+
+    /// The runtime version in effect at block `hash`, from the cache when it is enabled
+    pub async fn fetch_runtime_version_at(
+        &self,
+        hash: H256,
+    ) -> Result<Option<RuntimeVersion>, Error> {
+        if let Some(runtime_version) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.runtime_version(hash))
+        {
+            return Ok(Some(runtime_version));
+        }
+        let runtime_version =
+            self.base_api.fetch_runtime_version_at(Some(hash)).await?;
+        if let (Some(cache), Some(runtime_version)) =
+            (&self.cache, &runtime_version)
+        {
+            cache.put_runtime_version(hash, runtime_version.clone());
+        }
+        Ok(runtime_version)
+    }
+
+    /// The block number `n`, from the cache when it is enabled
+    pub async fn fetch_signed_block<B>(
+        &self,
+        n: u32,
+    ) -> Result<Option<SignedBlock<B>>, Error>
+    where
+        B: Block + DeserializeOwned,
+    {
+        match self.fetch_block_hash(n).await? {
+            Some(hash) => self.fetch_signed_block_by_hash(hash).await,
+            None => Ok(None),
+        }
+    }
+
+    /// The block number `n` without its justifications, from the cache when it is enabled
+    pub async fn fetch_block<B>(&self, n: u32) -> Result<Option<B>, Error>
+    where
+        B: Block + DeserializeOwned,
+    {
+        let signed_block = self.fetch_signed_block(n).await?;
+        Ok(signed_block.map(|signed_block| signed_block.block))
+    }
+
+    /// The metadata of the `runtime_version` in effect at block `hash`,
+    /// from the cache when it is enabled
+    pub(crate) async fn fetch_metadata_of(
+        &self,
+        runtime_version: &RuntimeVersion,
+        hash: H256,
+    ) -> Result<Metadata, Error> {
+        let spec_version = runtime_version.spec_version;
+        if let Some(metadata) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.metadata(spec_version))
+        {
+            return Ok(metadata);
+        }
+        let metadata = self
+            .base_api
+            .fetch_metadata_at(Some(hash))
+            .await?
+            .ok_or(Error::NoMetadata)?;
+        if let Some(cache) = &self.cache {
+            cache.put_metadata(spec_version, metadata.clone());
+        }
+        Ok(metadata)
+    }
+
     pub async fn new(url: &str) -> Result<Self, Error> {
         let base_api = BaseApi::new(url);
 
@@ -72,6 +229,10 @@ impl Api {
             metadata,
             genesis_hash,
             runtime_version,
+            signed_extensions: SignedExtensions::default(),
+            nonce_manager: NonceManager::new(),
+            ws_url: None,
+            cache: None,
         })
     }
 
@@ -86,4 +247,36 @@ impl Api {
     pub fn genesis_hash(&self) -> H256 {
         self.genesis_hash
     }
+
+    pub fn nonce_manager(&self) -> &NonceManager {
+        &self.nonce_manager
+    }
+
+    pub fn signed_extensions(&self) -> &SignedExtensions {
+        &self.signed_extensions
+    }
+
+    /// Use the WebSocket endpoint `ws_url` of the node for the subscriptions,
+    /// ie: `ws://localhost:9944`, otherwise the subscriptions poll the http endpoint
+    pub fn set_ws_url(&mut self, ws_url: &str) {
+        self.ws_url = Some(ws_url.to_string());
+    }
+
+    /// Cache the storage values, headers, blocks and runtime versions read at a block hash,
+    /// the reads at the best block are cached for `config.latest_ttl` only
+    pub fn enable_cache(&mut self, config: CacheConfig) {
+        self.cache = Some(ReadCache::new(config));
+    }
+
+    pub fn cache(&self) -> Option<&ReadCache> {
+        self.cache.as_ref()
+    }
+
+    /// Register the encoder of a custom signed extension used in the chain
+    pub fn register_signed_extension<E>(&mut self, identifier: &str, encoder: E)
+    where
+        E: ExtensionEncoder + 'static,
+    {
+        self.signed_extensions.register(identifier, encoder);
+    }
 }
@@ -0,0 +1,129 @@
+//! A view of the chain at a specific block
+use crate::{
+    api::{
+        Api,
+        StorageBackend,
+    },
+    error::Error,
+    Metadata,
+};
+use async_trait::async_trait;
+use sp_core::{
+    storage::StorageKey,
+    H256,
+};
+use sp_version::RuntimeVersion;
+use std::borrow::Cow;
+
+/// Reads the storage and constants of the chain as they were at one block,
+/// the storage reads are the methods of [`StorageBackend`].
+///
+/// The storage keys are computed using the metadata of the runtime which was in effect at
+/// that block, so the reads are consistent even if the runtime has been upgraded since.
+pub struct AtBlock<'a> {
+    api: &'a Api,
+    block_hash: H256,
+    runtime_version: RuntimeVersion,
+    metadata: Cow<'a, Metadata>,
+}
+
+impl Api {
+    /// Scope the reads to the block `block_hash`,
+    /// the metadata is only fetched when the runtime differs from the current one
+    /// and it is not in the cache yet.
+    pub async fn at(&self, block_hash: H256) -> Result<AtBlock<'_>, Error> {
+        let runtime_version = self
+            .fetch_runtime_version_at(block_hash)
+            .await?
+            .ok_or(Error::NoRuntimeVersion)?;
+        let metadata = if runtime_version.spec_version
+            == self.runtime_version.spec_version
+        {
+            Cow::Borrowed(&self.metadata)
+        } else {
+            Cow::Owned(
+                self.fetch_metadata_of(&runtime_version, block_hash).await?,
+            )
+        };
+        Ok(AtBlock {
+            api: self,
+            block_hash,
+            runtime_version,
+            metadata,
+        })
+    }
+}
+
+impl<'a> AtBlock<'a> {
+    pub fn block_hash(&self) -> H256 {
+        self.block_hash
+    }
+
+    pub fn runtime_version(&self) -> &RuntimeVersion {
+        &self.runtime_version
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// The value of the constant in the runtime at this block
+    pub fn fetch_constant_opaque_value(
+        &self,
+        module: &str,
+        constant_name: &str,
+    ) -> Result<Vec<u8>, Error> {
+        Ok(self
+            .metadata
+            .pallet(module)?
+            .constant(constant_name)?
+            .value
+            .clone())
+    }
+}
+
+/// Reads at the block of the view, the storage keys are computed with the metadata of this block
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl StorageBackend for AtBlock<'_> {
+    fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    async fn fetch_opaque_storage_by_key_hash(
+        &self,
+        storage_key: StorageKey,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.api
+            .fetch_opaque_storage_by_key_hash(
+                storage_key,
+                Some(self.block_hash),
+            )
+            .await
+    }
+
+    async fn fetch_opaque_storage_many(
+        &self,
+        storage_keys: &[StorageKey],
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        self.api
+            .fetch_opaque_storage_many(storage_keys, Some(self.block_hash))
+            .await
+    }
+
+    async fn fetch_storage_keys_paged_by_prefix(
+        &self,
+        prefix: StorageKey,
+        count: u32,
+        start_key: Option<StorageKey>,
+    ) -> Result<Option<Vec<StorageKey>>, Error> {
+        self.api
+            .fetch_storage_keys_paged_by_prefix(
+                prefix,
+                count,
+                start_key,
+                Some(self.block_hash),
+            )
+            .await
+    }
+}
@@ -1,6 +1,12 @@
 use crate::{
     error::Error,
-    types::metadata::Metadata,
+    types::{
+        fee::{
+            FeeDetails,
+            RuntimeDispatchInfo,
+        },
+        metadata::Metadata,
+    },
     utils::FromHexStr,
 };
 use frame_metadata::RuntimeMetadataPrefixed;
@@ -10,6 +16,10 @@ use serde::{
     Serialize,
 };
 use sp_core::{
+    crypto::{
+        AccountId32,
+        Ss58Codec,
+    },
     Decode,
     H256,
 };
@@ -61,7 +71,15 @@ impl BaseApi {
     pub async fn fetch_runtime_metadata(
         &self,
     ) -> Result<Option<RuntimeMetadataPrefixed>, Error> {
-        let value = self.json_request_value("state_getMetadata", ()).await?;
+        self.fetch_runtime_metadata_at(None).await
+    }
+
+    /// Get the runtime metadata which was in effect at block `at`
+    pub async fn fetch_runtime_metadata_at(
+        &self,
+        at: Option<H256>,
+    ) -> Result<Option<RuntimeMetadataPrefixed>, Error> {
+        let value = self.json_request_value("state_getMetadata", [at]).await?;
         match value {
             Some(value) => {
                 let value_str = value
@@ -78,7 +96,15 @@ impl BaseApi {
 
     /// Get the metadata of the substrate chain
     pub async fn fetch_metadata(&self) -> Result<Option<Metadata>, Error> {
-        let rt_metadata = self.fetch_runtime_metadata().await?;
+        self.fetch_metadata_at(None).await
+    }
+
+    /// Get the metadata of the substrate chain at block `at`
+    pub async fn fetch_metadata_at(
+        &self,
+        at: Option<H256>,
+    ) -> Result<Option<Metadata>, Error> {
+        let rt_metadata = self.fetch_runtime_metadata_at(at).await?;
         match rt_metadata {
             Some(rt_metadata) => {
                 let metadata = Metadata::try_from(rt_metadata)?;
@@ -196,9 +222,17 @@ impl BaseApi {
 
     pub async fn fetch_runtime_version(
         &self,
+    ) -> Result<Option<RuntimeVersion>, Error> {
+        self.fetch_runtime_version_at(None).await
+    }
+
+    /// Get the runtime version which was in effect at block `at`
+    pub async fn fetch_runtime_version_at(
+        &self,
+        at: Option<H256>,
     ) -> Result<Option<RuntimeVersion>, Error> {
         let version = self
-            .json_request_value("state_getRuntimeVersion", ())
+            .json_request_value("state_getRuntimeVersion", [at])
             .await?;
         match version {
             Some(version) => {
@@ -226,6 +260,99 @@ impl BaseApi {
         }
     }
 
+    /// Fetch the next nonce of the `account`,
+    /// this includes the transactions of the account which are still in the transaction pool
+    pub async fn fetch_account_next_index(
+        &self,
+        account: &AccountId32,
+    ) -> Result<Option<u32>, Error> {
+        let value = self
+            .json_request_value(
+                "system_accountNextIndex",
+                vec![account.to_ss58check()],
+            )
+            .await?;
+        match value {
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Fetch the weight, class and fee of an encoded extrinsic, at the best block if `at` is `None`
+    pub async fn fetch_payment_query_info(
+        &self,
+        hex_extrinsic: &str,
+        at: Option<H256>,
+    ) -> Result<Option<RuntimeDispatchInfo>, Error> {
+        let value = self
+            .json_request_value("payment_queryInfo", (hex_extrinsic, at))
+            .await?;
+        value
+            .map(|value| RuntimeDispatchInfo::from_json(&value))
+            .transpose()
+    }
+
+    /// Fetch the breakdown of the fee of an encoded extrinsic, at the best block if `at` is `None`
+    pub async fn fetch_payment_query_fee_details(
+        &self,
+        hex_extrinsic: &str,
+        at: Option<H256>,
+    ) -> Result<Option<FeeDetails>, Error> {
+        let value = self
+            .json_request_value("payment_queryFeeDetails", (hex_extrinsic, at))
+            .await?;
+        value.map(|value| FeeDetails::from_json(&value)).transpose()
+    }
+
+    /// Call the runtime api `method`, ie: `AccountNonceApi_account_nonce` with the scale encoded
+    /// `data` as arguments at block `at`, or at the best block if `at` is `None`.
+    /// Returns the scale encoded result.
+    pub async fn state_call(
+        &self,
+        method: &str,
+        data: &[u8],
+        at: Option<H256>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let value = self
+            .json_request_value(
+                "state_call",
+                (method, format!("0x{}", hex::encode(data)), at),
+            )
+            .await?;
+        value
+            .map(|value| parse_hex_bytes("state_call", &value))
+            .transpose()
+    }
+
+    /// return the block hash of the best block, which may not be finalized yet
+    pub async fn fetch_best_block_hash(&self) -> Result<Option<H256>, Error> {
+        let value = self.json_request_value("chain_getBlockHash", ()).await?;
+        match value {
+            Some(value) => {
+                let hash = value.as_str().map(H256::from_hex).transpose()?;
+                Ok(hash)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Apply the extrinsic on top of block `at`, or the best block if `at` is `None`,
+    /// without committing it and return the scale encoded `ApplyExtrinsicResult`.
+    /// This is an unsafe rpc method, the node needs to be started with `--rpc-methods unsafe`
+    /// unless it is a local node.
+    pub async fn system_dry_run(
+        &self,
+        hex_extrinsic: &str,
+        at: Option<H256>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let value = self
+            .json_request_value("system_dryRun", (hex_extrinsic, at))
+            .await?;
+        value
+            .map(|value| parse_hex_bytes("system_dryRun", &value))
+            .transpose()
+    }
+
     /// Make a rpc request and return the result.result if it has value
     pub(crate) async fn json_request_value<P: Serialize>(
         &self,
@@ -274,11 +401,40 @@ impl BaseApi {
     }
 }
 
+/// decode the hex string `value` returned by the rpc `method`
+fn parse_hex_bytes(
+    method: &str,
+    value: &serde_json::Value,
+) -> Result<Vec<u8>, Error> {
+    let value_str = value.as_str().ok_or_else(|| {
+        Error::UnexpectedResponse(method.to_string(), value.clone())
+    })?;
+    Ok(Vec::from_hex(value_str)?)
+}
+
 #[cfg(test)]
 mod tests {
     #![cfg(not(target_arch = "wasm32"))]
     use super::*;
 
+    #[test]
+    fn parse_hex_bytes_of_string() {
+        let value = serde_json::json!("0x0001ff");
+        let bytes = parse_hex_bytes("system_dryRun", &value).unwrap();
+        assert_eq!(bytes, vec![0x00, 0x01, 0xff]);
+    }
+
+    #[test]
+    fn parse_hex_bytes_of_non_string() {
+        let value = serde_json::json!({ "ok": true });
+        let result = parse_hex_bytes("system_dryRun", &value);
+        assert!(matches!(
+            result,
+            Err(Error::UnexpectedResponse(method, response))
+                if method == "system_dryRun" && response == value
+        ));
+    }
+
     #[tokio::test]
     async fn test1() {
         println!("fetching metada...");
@@ -0,0 +1,360 @@
+//! Read the storage of the child tries with the `childstate_*` rpc methods
+use crate::{
+    api::Api,
+    error::Error,
+    types::child_storage::ContractTrieId,
+    utils::FromHexStr,
+};
+use codec::Decode;
+use futures::{
+    stream,
+    Stream,
+};
+use sp_core::{
+    crypto::AccountId32,
+    storage::{
+        ChildInfo,
+        StorageKey,
+    },
+    H256,
+};
+use std::collections::VecDeque;
+
+/// The number of keys fetched in a single `childstate_getKeysPaged` request
+const CHILD_PAGE_SIZE: u32 = 256;
+
+/// The position of a child trie iteration
+struct ChildIter {
+    /// the block all the pages are read from, set with the first page
+    block_hash: Option<H256>,
+    /// the last key of the previous page
+    start_key: Option<StorageKey>,
+    entries: VecDeque<(StorageKey, Vec<u8>)>,
+    finished: bool,
+}
+
+impl Api {
+    /// The child trie holding the storage of the contract at `contract`,
+    /// `None` if there is no contract at this account
+    pub async fn fetch_contract_child_info(
+        &self,
+        contract: &AccountId32,
+        at: Option<H256>,
+    ) -> Result<Option<ChildInfo>, Error> {
+        let storage_key = self.metadata.storage_map_key(
+            "Contracts",
+            "ContractInfoOf",
+            contract,
+        )?;
+        let trie_id: Option<ContractTrieId> =
+            self.fetch_raw(storage_key, at).await?;
+        Ok(trie_id.map(|trie_id| trie_id.child_info()))
+    }
+
+    pub async fn fetch_child_storage<V>(
+        &self,
+        child_info: &ChildInfo,
+        storage_key: StorageKey,
+        at: Option<H256>,
+    ) -> Result<Option<V>, Error>
+    where
+        V: Decode,
+    {
+        match self
+            .fetch_opaque_child_storage(child_info, storage_key, at)
+            .await?
+        {
+            Some(bytes) => Ok(Some(V::decode(&mut bytes.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// the value at `storage_key` in the child trie, with `childstate_getStorage`
+    pub async fn fetch_opaque_child_storage(
+        &self,
+        child_info: &ChildInfo,
+        storage_key: StorageKey,
+        at: Option<H256>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let value = self
+            .base_api
+            .json_request_value(
+                "childstate_getStorage",
+                (child_info.prefixed_storage_key(), storage_key, at),
+            )
+            .await?;
+        match value {
+            Some(value) => {
+                let value_str = value.as_str().ok_or_else(|| {
+                    Error::UnexpectedResponse(
+                        "childstate_getStorage".to_string(),
+                        value.clone(),
+                    )
+                })?;
+                Ok(Some(Vec::from_hex(value_str)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// the values at `storage_keys` in the child trie in a single
+    /// `childstate_getStorageEntries` request, in the same order as the keys
+    pub async fn fetch_opaque_child_storage_many(
+        &self,
+        child_info: &ChildInfo,
+        storage_keys: &[StorageKey],
+        at: Option<H256>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        if storage_keys.is_empty() {
+            return Ok(vec![]);
+        }
+        let value = self
+            .base_api
+            .json_request_value(
+                "childstate_getStorageEntries",
+                (child_info.prefixed_storage_key(), storage_keys, at),
+            )
+            .await?
+            .ok_or_else(|| {
+                Error::EmptyResponse("childstate_getStorageEntries".to_string())
+            })?;
+        let invalid = || {
+            Error::UnexpectedResponse(
+                "childstate_getStorageEntries".to_string(),
+                value.clone(),
+            )
+        };
+        let values = value.as_array().ok_or_else(invalid)?;
+        if values.len() != storage_keys.len() {
+            return Err(invalid());
+        }
+        values
+            .iter()
+            .map(|value| {
+                value
+                    .as_str()
+                    .map(Vec::from_hex)
+                    .transpose()
+                    .map_err(Error::from)
+            })
+            .collect()
+    }
+
+    /// the hash of the value at `storage_key` in the child trie
+    pub async fn fetch_child_storage_hash(
+        &self,
+        child_info: &ChildInfo,
+        storage_key: StorageKey,
+        at: Option<H256>,
+    ) -> Result<Option<H256>, Error> {
+        let value = self
+            .base_api
+            .json_request_value(
+                "childstate_getStorageHash",
+                (child_info.prefixed_storage_key(), storage_key, at),
+            )
+            .await?;
+        match value {
+            Some(value) => {
+                let value_str = value.as_str().ok_or_else(|| {
+                    Error::UnexpectedResponse(
+                        "childstate_getStorageHash".to_string(),
+                        value.clone(),
+                    )
+                })?;
+                Ok(Some(H256::from_hex(value_str)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// the size in bytes of the value at `storage_key` in the child trie
+    pub async fn fetch_child_storage_size(
+        &self,
+        child_info: &ChildInfo,
+        storage_key: StorageKey,
+        at: Option<H256>,
+    ) -> Result<Option<u64>, Error> {
+        let value = self
+            .base_api
+            .json_request_value(
+                "childstate_getStorageSize",
+                (child_info.prefixed_storage_key(), storage_key, at),
+            )
+            .await?;
+        value
+            .map(|value| {
+                value.as_u64().ok_or_else(|| {
+                    Error::UnexpectedResponse(
+                        "childstate_getStorageSize".to_string(),
+                        value.clone(),
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    /// fetch `count` keys of the child trie which starts with `prefix`, after the `start_key`
+    pub async fn fetch_child_storage_keys_paged(
+        &self,
+        child_info: &ChildInfo,
+        prefix: StorageKey,
+        count: u32,
+        start_key: Option<StorageKey>,
+        at: Option<H256>,
+    ) -> Result<Vec<StorageKey>, Error> {
+        let value = self
+            .base_api
+            .json_request_value(
+                "childstate_getKeysPaged",
+                (
+                    child_info.prefixed_storage_key(),
+                    prefix,
+                    count,
+                    start_key,
+                    at,
+                ),
+            )
+            .await?;
+        match value {
+            Some(value) => {
+                let invalid = || {
+                    Error::UnexpectedResponse(
+                        "childstate_getKeysPaged".to_string(),
+                        value.clone(),
+                    )
+                };
+                value
+                    .as_array()
+                    .ok_or_else(invalid)?
+                    .iter()
+                    .map(|v| {
+                        let value_str = v.as_str().ok_or_else(invalid)?;
+                        Ok(StorageKey(Vec::from_hex(value_str)?))
+                    })
+                    .collect()
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Iterate over the entries of the child trie whose keys start with `prefix`,
+    /// use an empty prefix for all the entries.
+    ///
+    /// All the entries are read from the block `at`, or from the best block at the time
+    /// of the first page if `at` is `None`.
+    /// The keys are fetched by pages of 256, and the values of each page in a single request.
+    pub fn iter_child_storage<'a>(
+        &'a self,
+        child_info: &'a ChildInfo,
+        prefix: StorageKey,
+        at: Option<H256>,
+    ) -> impl Stream<Item = Result<(StorageKey, Vec<u8>), Error>> + 'a {
+        let state = ChildIter {
+            block_hash: at,
+            start_key: None,
+            entries: VecDeque::new(),
+            finished: false,
+        };
+        stream::try_unfold(state, move |mut state| {
+            let prefix = prefix.clone();
+            async move {
+                loop {
+                    if let Some(entry) = state.entries.pop_front() {
+                        return Ok(Some((entry, state)));
+                    }
+                    if state.finished {
+                        return Ok(None);
+                    }
+                    self.fetch_child_storage_page(
+                        child_info,
+                        prefix.clone(),
+                        &mut state,
+                    )
+                    .await?;
+                }
+            }
+        })
+    }
+
+    /// fetch the next page of keys and their values into the `state`
+    async fn fetch_child_storage_page(
+        &self,
+        child_info: &ChildInfo,
+        prefix: StorageKey,
+        state: &mut ChildIter,
+    ) -> Result<(), Error> {
+        let block_hash = match state.block_hash {
+            Some(block_hash) => block_hash,
+            None => {
+                let block_hash = self
+                    .base_api
+                    .fetch_best_block_hash()
+                    .await?
+                    .ok_or(Error::NoBestBlock)?;
+                state.block_hash = Some(block_hash);
+                block_hash
+            }
+        };
+        let storage_keys = self
+            .fetch_child_storage_keys_paged(
+                child_info,
+                prefix,
+                CHILD_PAGE_SIZE,
+                state.start_key.take(),
+                Some(block_hash),
+            )
+            .await?;
+        state.start_key = next_page_start(&storage_keys, CHILD_PAGE_SIZE);
+        state.finished = state.start_key.is_none();
+
+        let values = self
+            .fetch_opaque_child_storage_many(
+                child_info,
+                &storage_keys,
+                Some(block_hash),
+            )
+            .await?;
+        for (storage_key, value) in storage_keys.into_iter().zip(values) {
+            if let Some(value) = value {
+                state.entries.push_back((storage_key, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The key the page after `page` starts after,
+/// `None` when the node returned fewer keys than `page_size` so `page` is the last one
+fn next_page_start(page: &[StorageKey], page_size: u32) -> Option<StorageKey> {
+    if page.len() < page_size as usize {
+        None
+    } else {
+        page.last().cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(len: u8) -> Vec<StorageKey> {
+        (0..len).map(|i| StorageKey(vec![i])).collect()
+    }
+
+    #[test]
+    fn test_full_page_continues_after_its_last_key() {
+        assert_eq!(next_page_start(&page(4), 4), Some(StorageKey(vec![3])));
+    }
+
+    #[test]
+    fn test_short_page_is_the_last() {
+        assert_eq!(next_page_start(&page(3), 4), None);
+    }
+
+    #[test]
+    fn test_empty_page_is_the_last() {
+        assert_eq!(next_page_start(&page(0), 4), None);
+        assert_eq!(next_page_start(&page(0), 0), None);
+    }
+}
@@ -3,7 +3,7 @@ use crate::{
     api::Api,
     Error,
 };
-use frame_metadata::PalletConstantMetadata;
+use frame_metadata::v14::PalletConstantMetadata;
 use scale_info::{
     form::PortableForm,
     Type,
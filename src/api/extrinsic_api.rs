@@ -1,42 +1,56 @@
 use crate::{
     api::Api,
     error::Error,
+    signer::{
+        Signer,
+        SignerPayload,
+    },
     types::{
         account_info::AccountInfo,
         extrinsic_params::{
-            GenericExtra,
+            ExtrinsicParams,
+            Mortality,
+            PlainTipExtrinsicParams,
+            PlainTipExtrinsicParamsBuilder,
             SignedPayload,
         },
         extrinsics::{
             GenericAddress,
             UncheckedExtrinsicV4,
         },
+        metadata::Encoded,
+        signed_extensions::ExtensionParams,
+        unsigned_transaction::{
+            CallDescription,
+            SignedTransaction,
+            UnsignedTransaction,
+            DOCUMENT_VERSION,
+        },
     },
 };
-use codec::Encode;
-use codec::Decode;
-use codec::Compact;
-use sp_core::{
-    crypto::Pair,
-    H256,
+use codec::{
+    Decode,
+    Encode,
 };
+use sp_core::H256;
 use sp_runtime::{
-    traits::IdentifyAccount,
+    generic::{
+        self,
+        Era,
+    },
+    traits::BlakeTwo256,
     AccountId32,
     MultiSignature,
-    MultiSigner,
 };
 use std::fmt;
-use sp_runtime::generic::Era;
+
+/// The number of blocks a transaction is valid for when the period is not specified,
+/// this is the same default as the polkadot-js api
+pub(crate) const DEFAULT_MORTAL_PERIOD: u64 = 64;
 
 impl Api {
-    pub fn signer_account<P>(signer: &P) -> AccountId32
-    where
-        P: Pair,
-        MultiSigner: From<P::Public>,
-    {
-        let multi_signer = MultiSigner::from(signer.public());
-        multi_signer.into_account()
+    pub fn signer_account(signer: &dyn Signer) -> AccountId32 {
+        signer.account_id()
     }
 
     pub async fn get_nonce_for_account(
@@ -50,12 +64,17 @@ impl Api {
         }
     }
 
-    pub async fn get_nonce<P>(&self, signer: &P) -> Result<u32, Error>
-    where
-        P: Pair,
-        MultiSigner: From<P::Public>,
-    {
-        let signer_account = Self::signer_account(signer);
+    /// The next nonce of the `account` from the [`NonceManager`](crate::NonceManager),
+    /// use this when submitting several transactions of the same account back to back
+    pub async fn next_nonce(
+        &self,
+        account: &AccountId32,
+    ) -> Result<u32, Error> {
+        self.nonce_manager.next_nonce(&self.base_api, account).await
+    }
+
+    pub async fn get_nonce(&self, signer: &dyn Signer) -> Result<u32, Error> {
+        let signer_account = signer.account_id();
         self.get_nonce_for_account(&signer_account).await
     }
 
@@ -63,7 +82,7 @@ impl Api {
         &self,
         account_id: &AccountId32,
     ) -> Result<Option<AccountInfo>, Error> {
-        self.fetch_storage_map("System", "Account", account_id)
+        self.fetch_storage_map("System", "Account", account_id, None)
             .await
     }
 
@@ -85,140 +104,462 @@ impl Api {
         UncheckedExtrinsicV4::new_unsigned(call)
     }
 
+    /// The values used by the signed extensions, with the nonce, era and tip
+    /// filled in by the extrinsic `params`.
+    /// A [`Mortality::Mortal`] era is left immortal, use [`Api::resolve_extension_params`]
+    /// to compute it.
+    pub fn extension_params<Params>(&self, params: &Params) -> ExtensionParams
+    where
+        Params: ExtrinsicParams,
+    {
+        let mut extension_params = ExtensionParams {
+            spec_version: self.runtime_version.spec_version,
+            transaction_version: self.runtime_version.transaction_version,
+            genesis_hash: self.genesis_hash,
+            mortality_checkpoint: self.genesis_hash,
+            era: Era::immortal(),
+            nonce: 0,
+            tip: 0,
+            tip_asset: None,
+            metadata_hash: None,
+        };
+        params.fill_extension_params(&mut extension_params);
+        extension_params
+    }
 
-    pub fn compose_payload<Call>(
+    /// Same as [`Api::extension_params`], with the era of a [`Mortality::Mortal`] transaction
+    /// computed from the latest finalized block
+    pub async fn resolve_extension_params<Params>(
         &self,
-        call: Call,
-        extra: GenericExtra,
-        head_hash: Option<H256>,
-    ) -> Result<SignedPayload<Call>, Error>
+        params: &Params,
+    ) -> Result<ExtensionParams, Error>
     where
-        Call: Encode + Clone + fmt::Debug,
+        Params: ExtrinsicParams,
     {
-        let raw_payload: SignedPayload<Call> = SignedPayload::from_raw(
-            call,
-            extra,
-            (
-                self.runtime_version.spec_version,
-                self.runtime_version.transaction_version,
-                self.genesis_hash,
-                head_hash.unwrap_or(self.genesis_hash),
-                (),
-                (),
-                (),
-            ),
-        );
-        Ok(raw_payload)
+        let (extension_params, _checkpoint_number) =
+            self.resolve_mortality(params).await?;
+        Ok(extension_params)
+    }
+
+    /// the extension params along with the block number of their mortality checkpoint,
+    /// which is shown by the wallets
+    async fn resolve_mortality<Params>(
+        &self,
+        params: &Params,
+    ) -> Result<(ExtensionParams, u64), Error>
+    where
+        Params: ExtrinsicParams,
+    {
+        let mut extension_params = self.extension_params(params);
+        let checkpoint_number = match params.mortality() {
+            Mortality::Mortal(period) => {
+                let (era, checkpoint, checkpoint_number) =
+                    self.finalized_mortal_era(period).await?;
+                extension_params.era = era;
+                extension_params.mortality_checkpoint = checkpoint;
+                checkpoint_number
+            }
+            Mortality::Era { checkpoint, .. } => {
+                self.fetch_header(checkpoint).await?.number
+            }
+            // checkpointed at the genesis block
+            Mortality::Immortal => 0,
+        };
+        Ok((extension_params, checkpoint_number))
     }
 
+    /// A mortal era starting at the latest finalized block along with the hash of that block,
+    /// the `period` is capped by `System.BlockHashCount` since the node can only check
+    /// the transactions which started at one of the last `BlockHashCount` blocks.
+    /// The era is immortal, checkpointed at the genesis block, when `BlockHashCount` is
+    /// shorter than the shortest mortal era.
+    pub async fn mortal_era(
+        &self,
+        period: Option<u64>,
+    ) -> Result<(Era, H256), Error> {
+        let (era, checkpoint, _checkpoint_number) =
+            self.finalized_mortal_era(period).await?;
+        Ok((era, checkpoint))
+    }
+
+    /// the mortal era starting at the latest finalized block, with the hash and the number
+    /// of that block
+    async fn finalized_mortal_era(
+        &self,
+        period: Option<u64>,
+    ) -> Result<(Era, H256, u64), Error> {
+        let period = match mortal_period(
+            period.unwrap_or(DEFAULT_MORTAL_PERIOD),
+            self.block_hash_count().unwrap_or(DEFAULT_MORTAL_PERIOD),
+        ) {
+            Some(period) => period,
+            None => return Ok((Era::Immortal, self.genesis_hash, 0)),
+        };
+        let checkpoint = self
+            .chain_get_finalized_head()
+            .await?
+            .ok_or(Error::NoFinalizedHead)?;
+        let header = self.fetch_header(checkpoint).await?;
+        Ok((
+            Era::mortal(period, header.number),
+            checkpoint,
+            header.number,
+        ))
+    }
 
+    /// the header of the block `hash`, which must exist
+    async fn fetch_header(
+        &self,
+        hash: H256,
+    ) -> Result<generic::Header<u64, BlakeTwo256>, Error> {
+        self.chain_get_header(hash).await?.ok_or_else(|| {
+            Error::EmptyResponse(format!("chain_getHeader({:?})", hash))
+        })
+    }
 
-    /// sign a bytes with the specified signer
-    /// TODO: This should call an external API for the runtime
-    /// otherwise, this api acts as if it is a wallet
-    pub fn sign_message<P>(signer: &P, payload: &[u8]) -> P::Signature
+    /// The number of block hashes kept by the chain, `System.BlockHashCount`
+    pub fn block_hash_count(&self) -> Result<u64, Error> {
+        let value =
+            self.fetch_constant_opaque_value("System", "BlockHashCount")?;
+        // the block number is either a u32 or a u64
+        let count = match value.len() {
+            4 => u32::decode(&mut value.as_slice())? as u64,
+            _ => u64::decode(&mut value.as_slice())?,
+        };
+        Ok(count)
+    }
+
+    /// The identifiers of the signed extensions which are encoded into the transactions,
+    /// in the order they are declared, see [`SignedExtensions::identifiers`]
+    ///
+    /// [`SignedExtensions::identifiers`]: crate::types::signed_extensions::SignedExtensions::identifiers
+    pub fn signed_extension_identifiers(&self) -> Result<Vec<String>, Error> {
+        Ok(self.signed_extensions.identifiers(&self.metadata)?)
+    }
+
+    /// Compose the payload to be signed and the signed extra,
+    /// encoded according to the signed extensions listed in the metadata
+    pub fn compose_payload<Call>(
+        &self,
+        call: Call,
+        params: &ExtensionParams,
+    ) -> Result<(SignedPayload<Call, Encoded, Encoded>, Encoded), Error>
     where
-        P: Pair,
+        Call: Encode + Clone + fmt::Debug,
     {
-        signer.sign(payload)
+        let (extra, additional_signed) =
+            self.signed_extensions.encode(&self.metadata, params)?;
+        let raw_payload =
+            SignedPayload::from_raw(call, extra.clone(), additional_signed);
+        Ok((raw_payload, extra))
     }
 
+    /// sign a bytes with the specified signer,
+    /// the signer may be a remote service or a wallet holding the private key
+    pub async fn sign_message(
+        signer: &dyn Signer,
+        payload: &[u8],
+    ) -> Result<MultiSignature, Error> {
+        signer.sign(payload).await
+    }
 
-
-    /// submit the extrinsic into the node
-    pub async fn submit_extrinsic<Call>(
+    /// submit the extrinsic into the node,
+    /// the nonce of the signer is resynced when the node rejects the nonce of the extrinsic
+    pub async fn submit_extrinsic<Call, Extra>(
         &self,
-        xt: UncheckedExtrinsicV4<Call>,
+        xt: UncheckedExtrinsicV4<Call, Extra>,
     ) -> Result<Option<H256>, Error>
     where
         Call: Clone + fmt::Debug + Encode,
+        Extra: Encode,
     {
         let encoded = xt.hex_encode();
-        Ok(self.author_submit_extrinsic(encoded).await?)
+        let result = self.author_submit_extrinsic(encoded).await;
+        if let Err(e) = &result {
+            self.nonce_manager.resync_on_nonce_error(&xt, e);
+        }
+        result
     }
 
-    /// if Era uses some period and block number, the head_hash must be the head_has of the
-    /// block_number used in the era
-    pub async fn compose_payload_and_extra<Call>(&self,
-        nonce: u32,
+    /// Compose the payload to be signed and the signed extra of the `call`
+    /// using the extrinsic `params`, which carries the nonce, era and tip.
+    pub async fn compose_payload_and_extra<Params, Call>(
+        &self,
         call: Call,
-        era: Option<Era>,
-        head_hash: Option<H256>,
-        tip: Option<u128>,
-        )-> Result<(SignedPayload<Call>, GenericExtra), Error>
+        params: &Params,
+    ) -> Result<(SignedPayload<Call, Encoded, Encoded>, Encoded), Error>
     where
+        Params: ExtrinsicParams,
         Call: Clone + fmt::Debug + Encode,
     {
-
-        let tip = tip.unwrap_or(0);
-        let era = era.unwrap_or(Era::immortal());
-        let extra = GenericExtra(era, Compact(nonce), Compact(tip));
-
-        let raw_payload: SignedPayload<Call> =
-            self.compose_payload(call.clone(), extra.clone(), head_hash)?;
-
-        Ok((raw_payload,extra))
+        let extension_params = self.resolve_extension_params(params).await?;
+        self.compose_payload(call, &extension_params)
     }
 
-
-
-    pub async fn sign_extrinsic_with_era<P, Call>(&self, signer: &P, call: Call, era: Option<Era>, head_hash: Option<H256>, tip: Option<u128>) -> Result<UncheckedExtrinsicV4<Call>, Error>
-        where
-        P: sp_core::crypto::Pair,
-        AccountId32: From<P::Public>,
-        MultiSigner: From<P::Public>,
-        MultiSignature: From<P::Signature>,
+    /// Sign the `call` using the next nonce of the signer and the `other_params`
+    /// such as the era and the tip, ie: [`AssetTipExtrinsicParamsBuilder`] when the tip
+    /// is paid in an asset.
+    /// The nonce is resynced when the extrinsic can not be signed, so it is not skipped.
+    ///
+    /// [`AssetTipExtrinsicParamsBuilder`]: crate::types::extrinsic_params::AssetTipExtrinsicParamsBuilder
+    pub async fn sign_extrinsic_with_params<Params, Call>(
+        &self,
+        signer: &dyn Signer,
+        call: Call,
+        other_params: Params::OtherParams,
+    ) -> Result<UncheckedExtrinsicV4<Call, Encoded>, Error>
+    where
+        Params: ExtrinsicParams,
         Call: Clone + fmt::Debug + Encode,
     {
+        let signer_account = signer.account_id();
+        let nonce = self.next_nonce(&signer_account).await?;
+        let params = Params::new(nonce, other_params);
+        let extrinsic =
+            self.sign_extrinsic_with_nonce(signer, call, &params).await;
+        if extrinsic.is_err() {
+            // the nonce was not used, leaving a gap in the nonces of the account
+            self.nonce_manager.resync(&signer_account);
+        }
+        extrinsic
+    }
 
-        let signer_account = AccountId32::from(signer.public());
-        let nonce = self.get_nonce_for_account(&signer_account).await?;
-        let (payload, extra) = self.compose_payload_and_extra(nonce, call.clone(), era, head_hash,  tip).await?;
-
+    /// sign the `call` with the nonce already taken in the `params`
+    async fn sign_extrinsic_with_nonce<Params, Call>(
+        &self,
+        signer: &dyn Signer,
+        call: Call,
+        params: &Params,
+    ) -> Result<UncheckedExtrinsicV4<Call, Encoded>, Error>
+    where
+        Params: ExtrinsicParams,
+        Call: Clone + fmt::Debug + Encode,
+    {
+        let signer_account = signer.account_id();
+        let (extension_params, checkpoint_number) =
+            self.resolve_mortality(params).await?;
+        let (payload, extra) =
+            self.compose_payload(call.clone(), &extension_params)?;
 
-        let signature = payload.using_encoded(|payload|signer.sign(payload));
-        let multi_signature = MultiSignature::from(signature);
+        let signer_payload = SignerPayload {
+            address: signer_account.clone(),
+            method: call.encode(),
+            params: extension_params,
+            block_number: checkpoint_number as u32,
+            signed_extensions: self.signed_extension_identifiers()?,
+            version: 4,
+            encoded: payload.using_encoded(|payload| payload.to_vec()),
+        };
+        let multi_signature = signer.sign_payload(&signer_payload).await?;
 
-        let extrinsic = UncheckedExtrinsicV4::new_signed(call, GenericAddress::from(signer_account), multi_signature, extra);
+        let extrinsic = UncheckedExtrinsicV4::new_signed(
+            call,
+            GenericAddress::from(signer_account),
+            multi_signature,
+            extra,
+        );
         Ok(extrinsic)
     }
 
-    pub async fn sign_extrinsic<P, Call>(&self, signer: &P, call: Call, tip: Option<u128>) -> Result<UncheckedExtrinsicV4<Call>, Error>
-        where
-        P: sp_core::crypto::Pair,
-        AccountId32: From<P::Public>,
-        MultiSigner: From<P::Public>,
-        MultiSignature: From<P::Signature>,
+    /// Sign a mortal extrinsic valid for 64 blocks from the latest finalized block,
+    /// with the tip paid in the native currency
+    pub async fn sign_extrinsic<Call>(
+        &self,
+        signer: &dyn Signer,
+        call: Call,
+        tip: Option<u128>,
+    ) -> Result<UncheckedExtrinsicV4<Call, Encoded>, Error>
+    where
         Call: Clone + fmt::Debug + Encode,
     {
-        self.sign_extrinsic_with_era(signer, call, None, None, tip).await
+        let other_params =
+            PlainTipExtrinsicParamsBuilder::new().tip(tip.unwrap_or(0));
+        self.sign_extrinsic_with_params::<PlainTipExtrinsicParams, _>(
+            signer,
+            call,
+            other_params,
+        )
+        .await
     }
 
     /// create a payload ready for signing and the extra in opaque bytes
-    pub async fn compose_opaque_payload_and_extra<Call>(&self, nonce: u32, call:Call, era: Option<Era>, head_hash: Option<H256>, tip: Option<u128>) -> Result<(Vec<u8>, Vec<u8>), Error>
-        where Call: Clone + fmt::Debug + Encode
+    pub async fn compose_opaque_payload_and_extra<Params, Call>(
+        &self,
+        call: Call,
+        params: &Params,
+    ) -> Result<(Vec<u8>, Vec<u8>), Error>
+    where
+        Params: ExtrinsicParams,
+        Call: Clone + fmt::Debug + Encode,
     {
-        let (payload, extra) = self.compose_payload_and_extra(nonce, call, era, head_hash, tip).await?;
-        let payload_encoded = payload.encode();
-        let payload_for_signing  = if payload_encoded.len() > 256 {
-            sp_core::blake2_256(&payload_encoded).to_vec()
-        }else{
-            payload_encoded
-        };
-        Ok((payload_for_signing,extra.encode()))
+        let (payload, extra) =
+            self.compose_payload_and_extra(call, params).await?;
+        let payload_for_signing = payload.using_encoded(|p| p.to_vec());
+        Ok((payload_for_signing, extra.0))
     }
 
     /// submit the signed call with signature and extra
-    pub async fn submit_signed_call<Call>(&self, call: Call, signer_account: &AccountId32, multi_signature: MultiSignature, extra: Vec<u8>) -> Result<Option<H256>, Error>
-        where Call: Clone + fmt::Debug + Encode
+    pub async fn submit_signed_call<Call>(
+        &self,
+        call: Call,
+        signer_account: &AccountId32,
+        multi_signature: MultiSignature,
+        extra: Vec<u8>,
+    ) -> Result<Option<H256>, Error>
+    where
+        Call: Clone + fmt::Debug + Encode,
+    {
+        let extrinsic = UncheckedExtrinsicV4::new_signed(
+            call,
+            GenericAddress::from(signer_account.clone()),
+            multi_signature,
+            Encoded(extra),
+        );
+        self.submit_extrinsic(extrinsic).await
+    }
+
+    /// Create a transaction document to be signed offline,
+    /// using the next nonce of the `signer_account` and the `other_params`
+    pub async fn unsigned_transaction<Params, Call>(
+        &self,
+        call: Call,
+        signer_account: &AccountId32,
+        other_params: Params::OtherParams,
+    ) -> Result<UnsignedTransaction, Error>
+    where
+        Params: ExtrinsicParams,
+        Call: Encode,
     {
+        let nonce = self.next_nonce(signer_account).await?;
+        let params = Params::new(nonce, other_params);
+        let document = self
+            .transaction_document(call.encode(), signer_account, &params)
+            .await;
+        if document.is_err() {
+            // the nonce was not used, leaving a gap in the nonces of the account
+            self.nonce_manager.resync(signer_account);
+        }
+        document
+    }
 
-        let extra = GenericExtra::decode(&mut extra.as_slice())?;
-        let extrinsic = UncheckedExtrinsicV4::new_signed(call, GenericAddress::from(signer_account.clone()), multi_signature, extra);
-        let encoded = extrinsic.hex_encode();
-        let tx_hash = self.author_submit_extrinsic(encoded).await?;
-        Ok(tx_hash)
+    /// the transaction document of the `call`, with the nonce already taken in the `params`
+    async fn transaction_document<Params>(
+        &self,
+        call: Vec<u8>,
+        signer_account: &AccountId32,
+        params: &Params,
+    ) -> Result<UnsignedTransaction, Error>
+    where
+        Params: ExtrinsicParams,
+    {
+        let params = self.resolve_extension_params(params).await?;
+        Ok(UnsignedTransaction {
+            version: DOCUMENT_VERSION,
+            call_description: CallDescription::from_call(
+                &self.metadata,
+                &call,
+            )?,
+            call,
+            signer: signer_account.clone(),
+            nonce: params.nonce,
+            era: params.era,
+            mortality_checkpoint: params.mortality_checkpoint,
+            tip: params.tip,
+            tip_asset: params.tip_asset,
+            spec_version: params.spec_version,
+            transaction_version: params.transaction_version,
+            genesis_hash: params.genesis_hash,
+            signed_extensions: self.signed_extension_identifiers()?,
+        })
     }
 
+    /// Assemble the extrinsic of a transaction document signed offline and submit it.
+    /// The document must have been created for the current runtime of this chain.
+    pub async fn submit_signed_document(
+        &self,
+        signed: &SignedTransaction,
+    ) -> Result<Option<H256>, Error> {
+        let tx = &signed.transaction;
+        tx.verify(&self.metadata)?;
+        if tx.genesis_hash != self.genesis_hash {
+            return Err(Error::InvalidTransactionDocument(
+                "the document is for a different chain".to_string(),
+            ));
+        }
+        if tx.spec_version != self.runtime_version.spec_version
+            || tx.transaction_version
+                != self.runtime_version.transaction_version
+        {
+            return Err(Error::InvalidTransactionDocument(
+                "the runtime has been upgraded since the document was created"
+                    .to_string(),
+            ));
+        }
+        if tx.signed_extensions != self.signed_extension_identifiers()? {
+            return Err(Error::InvalidTransactionDocument(
+                "the signed extensions of the chain has changed".to_string(),
+            ));
+        }
+        let (extra, _additional_signed) =
+            tx.encode_extensions(&self.signed_extensions)?;
+        let extrinsic = UncheckedExtrinsicV4::new_signed(
+            Encoded(tx.call.clone()),
+            GenericAddress::from(tx.signer.clone()),
+            signed.signature.clone(),
+            extra,
+        );
+        self.submit_extrinsic(extrinsic).await
+    }
+}
 
+/// The shortest period of a mortal era, [`Era::mortal`] clamps the shorter periods up to it
+const MIN_MORTAL_PERIOD: u64 = 4;
+
+/// The period of a mortal era, capped by the `block_hash_count`.
+/// [`Era::mortal`] rounds the period up to a power of two, so it is rounded down here
+/// when rounding up would exceed the `block_hash_count`.
+/// Returns `None` when the `block_hash_count` is shorter than the shortest mortal era,
+/// the transaction has to be immortal then.
+fn mortal_period(period: u64, block_hash_count: u64) -> Option<u64> {
+    if block_hash_count < MIN_MORTAL_PERIOD {
+        return None;
+    }
+    let period = period.min(block_hash_count).next_power_of_two();
+    let period = if period > block_hash_count {
+        period / 2
+    } else {
+        period
+    };
+    Some(period.max(MIN_MORTAL_PERIOD))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mortal_period() {
+        assert_eq!(mortal_period(64, 2400), Some(64));
+        assert_eq!(mortal_period(100, 2400), Some(128));
+        assert_eq!(mortal_period(2400, 2400), Some(2048));
+        assert_eq!(mortal_period(10_000, 4096), Some(4096));
+        assert_eq!(mortal_period(64, 50), Some(32));
+        assert_eq!(mortal_period(1, 2400), Some(4));
+        assert_eq!(mortal_period(64, 5), Some(4));
+        assert_eq!(mortal_period(64, 4), Some(4));
+        assert_eq!(mortal_period(64, 3), None);
+        assert_eq!(mortal_period(64, 1), None);
+        assert_eq!(mortal_period(64, 0), None);
+        for (period, block_hash_count) in
+            [(2400, 2400), (3000, 250), (1, 2400), (64, 5), (64, 4)]
+        {
+            let period = mortal_period(period, block_hash_count).unwrap();
+            match Era::mortal(period, 1_000_000) {
+                Era::Mortal(era_period, _phase) => {
+                    assert!(era_period <= block_hash_count)
+                }
+                Era::Immortal => panic!("must be mortal"),
+            }
+        }
+    }
 }
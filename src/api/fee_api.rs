@@ -0,0 +1,187 @@
+//! Estimate the fee of an extrinsic before submitting it
+use super::extrinsic_api::DEFAULT_MORTAL_PERIOD;
+use crate::{
+    error::Error,
+    types::{
+        extrinsic_params::{
+            ExtrinsicParams,
+            PlainTipExtrinsicParams,
+            PlainTipExtrinsicParamsBuilder,
+        },
+        extrinsics::{
+            GenericAddress,
+            UncheckedExtrinsicV4,
+        },
+        fee::FeeEstimate,
+        metadata::Encoded,
+        signed_extensions::{
+            ExtensionParams,
+            SignedExtensions,
+        },
+    },
+    Api,
+};
+use codec::Encode;
+use sp_core::{
+    sr25519,
+    H256,
+};
+use sp_runtime::{
+    generic::Era,
+    AccountId32,
+    MultiSignature,
+};
+use std::fmt;
+
+impl Api {
+    /// Estimate the weight and the fee of the extrinsic at the best block.
+    ///
+    /// An unsigned extrinsic is estimated as if it was signed, with a placeholder signer,
+    /// signature and signed extra, so the fee can be shown to the user before asking for the
+    /// signature. The fee of a signed extrinsic includes its tip in [`FeeDetails::tip`].
+    ///
+    /// [`FeeDetails::tip`]: crate::types::fee::FeeDetails::tip
+    pub async fn estimate_fee<Call, Extra>(
+        &self,
+        xt: &UncheckedExtrinsicV4<Call, Extra>,
+    ) -> Result<FeeEstimate, Error>
+    where
+        Call: Encode + Clone + fmt::Debug,
+        Extra: Encode,
+    {
+        let hex_extrinsic = match xt.signature {
+            Some(_) => xt.hex_encode(),
+            None => self.placeholder_signed(xt.function.clone())?.hex_encode(),
+        };
+        self.estimate_fee_hex(&hex_extrinsic, None).await
+    }
+
+    /// Estimate the weight and the fee of a hex encoded extrinsic at block `at`,
+    /// or at the best block if `at` is `None`
+    pub async fn estimate_fee_hex(
+        &self,
+        hex_extrinsic: &str,
+        at: Option<H256>,
+    ) -> Result<FeeEstimate, Error> {
+        let info = self
+            .base_api
+            .fetch_payment_query_info(hex_extrinsic, at)
+            .await?
+            .ok_or(Error::NoFeeInfo)?;
+        let details = self
+            .base_api
+            .fetch_payment_query_fee_details(hex_extrinsic, at)
+            .await?
+            .ok_or(Error::NoFeeInfo)?;
+        Ok(FeeEstimate { info, details })
+    }
+
+    /// wrap the `call` in an extrinsic with the same length as a signed one,
+    /// the fee doesn't depend on the validity of the signature
+    fn placeholder_signed<Call>(
+        &self,
+        call: Call,
+    ) -> Result<UncheckedExtrinsicV4<Call, Encoded>, Error>
+    where
+        Call: Encode + Clone + fmt::Debug,
+    {
+        let params = self.extension_params(&PlainTipExtrinsicParams::new(
+            0,
+            PlainTipExtrinsicParamsBuilder::new(),
+        ));
+        let identifiers = self.signed_extension_identifiers()?;
+        sign_with_placeholder(
+            call,
+            &self.signed_extensions,
+            &identifiers,
+            params,
+        )
+    }
+}
+
+/// sign the `call` with a zero account and signature, and the signed extra of the
+/// signed extensions `identifiers` encoded from the `params`
+fn sign_with_placeholder<Call>(
+    call: Call,
+    registry: &SignedExtensions,
+    identifiers: &[String],
+    mut params: ExtensionParams,
+) -> Result<UncheckedExtrinsicV4<Call, Encoded>, Error>
+where
+    Call: Encode,
+{
+    // a mortal era is encoded in 2 bytes, the same as the default era of a transaction
+    params.era = Era::mortal(DEFAULT_MORTAL_PERIOD, 0);
+    let (extra, _additional_signed) =
+        registry.encode_identifiers(identifiers, &params)?;
+    Ok(UncheckedExtrinsicV4::new_signed(
+        call,
+        GenericAddress::Id(AccountId32::new([0; 32])),
+        MultiSignature::Sr25519(sr25519::Signature::from_raw([0; 64])),
+        extra,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::{
+        Compact,
+        Decode,
+    };
+
+    #[test]
+    fn test_placeholder_signed_layout() {
+        let identifiers: Vec<String> = [
+            "CheckSpecVersion",
+            "CheckGenesis",
+            "CheckMortality",
+            "CheckNonce",
+            "ChargeTransactionPayment",
+        ]
+        .iter()
+        .map(|id| id.to_string())
+        .collect();
+        let params = ExtensionParams {
+            spec_version: 100,
+            transaction_version: 1,
+            genesis_hash: H256::repeat_byte(2),
+            mortality_checkpoint: H256::repeat_byte(2),
+            era: Era::immortal(),
+            nonce: 0,
+            tip: 0,
+            tip_asset: None,
+            metadata_hash: None,
+        };
+        let call = ([6u8, 0], [1u8; 32], 42u128);
+        let xt = sign_with_placeholder(
+            call,
+            &SignedExtensions::default(),
+            &identifiers,
+            params,
+        )
+        .unwrap();
+
+        let encoded = xt.encode();
+        let mut input = &encoded[..];
+        let len = Compact::<u32>::decode(&mut input).unwrap().0;
+        assert_eq!(len as usize, input.len());
+        // a signed extrinsic of version 4
+        assert_eq!(u8::decode(&mut input).unwrap(), 0b1000_0100);
+        assert_eq!(
+            GenericAddress::decode(&mut input).unwrap(),
+            GenericAddress::Id(AccountId32::new([0; 32]))
+        );
+        assert_eq!(
+            MultiSignature::decode(&mut input).unwrap(),
+            MultiSignature::Sr25519(sr25519::Signature::from_raw([0; 64]))
+        );
+        // the signed extra: era, nonce and tip
+        let era = Era::decode(&mut input).unwrap();
+        assert!(matches!(era, Era::Mortal(..)));
+        assert_eq!(era, Era::mortal(DEFAULT_MORTAL_PERIOD, 0));
+        assert_eq!(Compact::<u32>::decode(&mut input).unwrap().0, 0);
+        assert_eq!(Compact::<u128>::decode(&mut input).unwrap().0, 0);
+        assert_eq!(input, &call.encode()[..]);
+    }
+}
@@ -0,0 +1,205 @@
+//! Iterate over all the entries of a storage map
+use crate::{
+    api::Api,
+    error::Error,
+    types::{
+        metadata::MetadataError,
+        storage::{
+            DecodeStorageKeys,
+            StorageKeys,
+        },
+    },
+};
+use codec::Decode;
+use futures::{
+    future,
+    stream,
+    Stream,
+    StreamExt,
+};
+use sp_core::{
+    storage::StorageKey,
+    H256,
+};
+use std::collections::VecDeque;
+
+/// The number of keys fetched in a single `state_getKeysPaged` request,
+/// their values are then fetched in a single `state_queryStorageAt` request
+const ITER_PAGE_SIZE: u32 = 256;
+
+/// The position of a storage map iteration
+struct MapIter<K, V, F> {
+    prefix: StorageKey,
+    /// decode the map keys out of a storage key
+    decode_key: F,
+    /// the block all the pages are read from, set with the first page
+    block_hash: Option<H256>,
+    /// the last key of the previous page
+    start_key: Option<StorageKey>,
+    entries: VecDeque<(K, V)>,
+    finished: bool,
+}
+
+impl Api {
+    /// Iterate over all the entries of the storage map `module.storage_name`.
+    ///
+    /// All the entries are read from the block `at`, or from the best block at the time
+    /// of the first page if `at` is `None`, so the entries are consistent even when
+    /// the map changes in later blocks.
+    /// The keys are fetched by pages of 256 raw storage keys, continuing after the last key
+    /// of the previous page, and the values of each page are fetched in a single request.
+    ///
+    /// The map key must use a hasher which keeps the key, such as `Twox64Concat`,
+    /// `Blake2_128Concat` or `Identity`, so it can be decoded into `K`.
+    pub fn iter_storage_map<'a, K, V>(
+        &'a self,
+        module: &str,
+        storage_name: &str,
+        at: Option<H256>,
+    ) -> impl Stream<Item = Result<(K, V), Error>> + 'a
+    where
+        K: Decode + 'a,
+        V: Decode + 'a,
+    {
+        let prefix = self.metadata.storage_map_key_prefix(module, storage_name);
+        let (module, storage_name) =
+            (module.to_string(), storage_name.to_string());
+        self.iter_storage_by_prefix(
+            prefix,
+            move |storage_key| {
+                self.metadata.decode_storage_map_key(
+                    &module,
+                    &storage_name,
+                    storage_key,
+                )
+            },
+            at,
+        )
+    }
+
+    /// Iterate over the entries of the n-map `module.storage_name` which start with the
+    /// `partial_keys`, ie: `(era,)` to get all the entries of an era in a map keyed by
+    /// `(era, account, index)`, or `()` for the whole map.
+    /// Each entry has the complete keys `K`, see [`Api::iter_storage_map`].
+    pub fn iter_storage_nmap<'a, P, K, V>(
+        &'a self,
+        module: &str,
+        storage_name: &str,
+        partial_keys: P,
+        at: Option<H256>,
+    ) -> impl Stream<Item = Result<(K, V), Error>> + 'a
+    where
+        P: StorageKeys,
+        K: DecodeStorageKeys + 'a,
+        V: Decode + 'a,
+    {
+        let prefix = self.metadata.storage_nmap_key_prefix(
+            module,
+            storage_name,
+            &partial_keys,
+        );
+        let (module, storage_name) =
+            (module.to_string(), storage_name.to_string());
+        self.iter_storage_by_prefix(
+            prefix,
+            move |storage_key| {
+                self.metadata.decode_storage_nmap_key(
+                    &module,
+                    &storage_name,
+                    storage_key,
+                )
+            },
+            at,
+        )
+    }
+
+    /// iterate over the entries whose storage key starts with `prefix`
+    fn iter_storage_by_prefix<'a, K, V, F>(
+        &'a self,
+        prefix: Result<StorageKey, MetadataError>,
+        decode_key: F,
+        at: Option<H256>,
+    ) -> impl Stream<Item = Result<(K, V), Error>> + 'a
+    where
+        K: 'a,
+        V: Decode + 'a,
+        F: Fn(&StorageKey) -> Result<K, MetadataError> + 'a,
+    {
+        let prefix = match prefix {
+            Ok(prefix) => prefix,
+            Err(e) => {
+                return stream::once(future::ready(Err(e.into()))).left_stream()
+            }
+        };
+        let state = MapIter {
+            prefix,
+            decode_key,
+            block_hash: at,
+            start_key: None,
+            entries: VecDeque::new(),
+            finished: false,
+        };
+        stream::try_unfold(state, move |mut state| {
+            async move {
+                loop {
+                    if let Some(entry) = state.entries.pop_front() {
+                        return Ok(Some((entry, state)));
+                    }
+                    if state.finished {
+                        return Ok(None);
+                    }
+                    self.fetch_storage_page(&mut state).await?;
+                }
+            }
+        })
+        .right_stream()
+    }
+
+    /// fetch the next page of keys and their values into the `state`
+    async fn fetch_storage_page<K, V, F>(
+        &self,
+        state: &mut MapIter<K, V, F>,
+    ) -> Result<(), Error>
+    where
+        V: Decode,
+        F: Fn(&StorageKey) -> Result<K, MetadataError>,
+    {
+        let block_hash = match state.block_hash {
+            Some(block_hash) => block_hash,
+            None => {
+                let block_hash = self
+                    .base_api
+                    .fetch_best_block_hash()
+                    .await?
+                    .ok_or(Error::NoBestBlock)?;
+                state.block_hash = Some(block_hash);
+                block_hash
+            }
+        };
+        let storage_keys = self
+            .fetch_storage_keys_paged_by_prefix(
+                state.prefix.clone(),
+                ITER_PAGE_SIZE,
+                state.start_key.take(),
+                Some(block_hash),
+            )
+            .await?
+            .unwrap_or_default();
+        if storage_keys.len() < ITER_PAGE_SIZE as usize {
+            state.finished = true;
+        }
+        state.start_key = storage_keys.last().cloned();
+
+        let storage_values = self
+            .fetch_opaque_storage_many(&storage_keys, Some(block_hash))
+            .await?;
+        for (storage_key, value) in storage_keys.iter().zip(storage_values) {
+            if let Some(value) = value {
+                let key = (state.decode_key)(storage_key)?;
+                let value = V::decode(&mut value.as_slice())?;
+                state.entries.push_back((key, value));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+//! Hands out the nonces of the accounts locally,
+//! so transactions can be submitted back to back without waiting for them to be included.
+use crate::{
+    api::BaseApi,
+    error::Error,
+    types::extrinsics::{
+        GenericAddress,
+        UncheckedExtrinsicV4,
+    },
+};
+use sp_core::crypto::AccountId32;
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        Mutex,
+        MutexGuard,
+    },
+};
+
+/// Keeps track of the next nonce of each account.
+/// The nonce of an account is seeded from `system_accountNextIndex`, which accounts for the
+/// transactions in the pool, then it is incremented locally for each transaction.
+#[derive(Clone, Default, Debug)]
+pub struct NonceManager {
+    nonces: Arc<Mutex<HashMap<AccountId32, u32>>>,
+}
+
+impl NonceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the next nonce of the `account` and increment it,
+    /// the nonce is fetched from the node the first time the account is used
+    /// or after it has been resynced.
+    pub async fn next_nonce(
+        &self,
+        base_api: &BaseApi,
+        account: &AccountId32,
+    ) -> Result<u32, Error> {
+        if let Some(nonce) = self.increment(account) {
+            return Ok(nonce);
+        }
+        let seed = base_api
+            .fetch_account_next_index(account)
+            .await?
+            .unwrap_or(0);
+        // the lock is not held while fetching the seed, another task may have seeded
+        // the account in the meantime, in which case its nonce is used instead
+        let mut nonces = self.lock();
+        let next = nonces.entry(account.clone()).or_insert(seed);
+        let nonce = *next;
+        *next += 1;
+        Ok(nonce)
+    }
+
+    /// Forget the nonce of the `account`, the next nonce will be fetched from the node.
+    /// Call this when a transaction is rejected because its nonce is stale or in the future.
+    pub fn resync(&self, account: &AccountId32) {
+        self.lock().remove(account);
+    }
+
+    /// Resync the nonce of the signer of the extrinsic `xt`
+    /// when the node rejected it with an `error` about its nonce
+    pub(crate) fn resync_on_nonce_error<Call, Extra>(
+        &self,
+        xt: &UncheckedExtrinsicV4<Call, Extra>,
+        error: &Error,
+    ) {
+        if let Some((GenericAddress::Id(account), _, _)) = &xt.signature {
+            if error.is_nonce_error() {
+                self.resync(account);
+            }
+        }
+    }
+
+    /// Forget the nonces of all the accounts
+    pub fn reset(&self) {
+        self.lock().clear();
+    }
+
+    fn increment(&self, account: &AccountId32) -> Option<u32> {
+        let mut nonces = self.lock();
+        let next = nonces.get_mut(account)?;
+        let nonce = *next;
+        *next += 1;
+        Some(nonce)
+    }
+
+    fn lock(&self) -> MutexGuard<HashMap<AccountId32, u32>> {
+        // the map is always left in a consistent state, so a poisoned lock is still usable
+        self.nonces
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::metadata::Encoded;
+    use codec::Compact;
+    use sp_core::sr25519;
+    use sp_runtime::MultiSignature;
+
+    #[test]
+    fn test_increment_and_resync() {
+        let manager = NonceManager::new();
+        let alice = AccountId32::new([1; 32]);
+        assert_eq!(manager.increment(&alice), None);
+        manager.lock().insert(alice.clone(), 5);
+        assert_eq!(manager.increment(&alice), Some(5));
+        assert_eq!(manager.clone().increment(&alice), Some(6));
+        manager.resync(&alice);
+        assert_eq!(manager.increment(&alice), None);
+    }
+
+    #[test]
+    fn test_resync_on_nonce_error_of_balance_transfer() {
+        let manager = NonceManager::new();
+        let alice = AccountId32::new([1; 32]);
+        let bob = AccountId32::new([2; 32]);
+        let balance_call: ([u8; 2], GenericAddress, Compact<u128>) =
+            ([5, 0], GenericAddress::Id(bob), Compact(1_000));
+        let extrinsic = UncheckedExtrinsicV4::new_signed(
+            balance_call,
+            GenericAddress::Id(alice.clone()),
+            MultiSignature::Sr25519(sr25519::Signature::from_raw([0; 64])),
+            Encoded(vec![]),
+        );
+
+        manager.lock().insert(alice.clone(), 5);
+        let other_error = Error::ResponseJsonError(serde_json::json!({
+            "code": 1010,
+            "message": "Invalid Transaction",
+            "data": "Inability to pay some fees (e.g. account balance too low)",
+        }));
+        manager.resync_on_nonce_error(&extrinsic, &other_error);
+        assert_eq!(manager.increment(&alice), Some(5));
+
+        let stale_error = Error::ResponseJsonError(serde_json::json!({
+            "code": 1010,
+            "message": "Invalid Transaction",
+            "data": "Transaction is outdated",
+        }));
+        manager.resync_on_nonce_error(&extrinsic, &stale_error);
+        assert_eq!(manager.increment(&alice), None);
+    }
+}
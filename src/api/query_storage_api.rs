@@ -0,0 +1,208 @@
+//! Query how storage values changed over a range of blocks
+use crate::{
+    api::Api,
+    error::Error,
+    types::{
+        dynamic,
+        storage::StorageChange,
+    },
+    utils::FromHexStr,
+};
+use codec::Decode;
+use sp_core::{
+    storage::StorageKey,
+    H256,
+};
+use sp_runtime::{
+    generic,
+    traits::BlakeTwo256,
+};
+use std::collections::HashMap;
+
+/// The number of blocks queried in a single `state_queryStorage` request,
+/// nodes time out or reject requests spanning too many blocks
+const QUERY_STORAGE_CHUNK: u64 = 256;
+
+impl Api {
+    /// The changes of the values at `storage_keys` from block `from` to block `to`,
+    /// or up to the best block if `to` is `None`.
+    ///
+    /// The first changes are the values at block `from`, followed by the changes in the
+    /// later blocks ordered by block number.
+    /// Long ranges are split into several requests of 256 blocks.
+    pub async fn query_storage_opaque(
+        &self,
+        storage_keys: &[StorageKey],
+        from: H256,
+        to: Option<H256>,
+    ) -> Result<Vec<StorageChange<Vec<u8>>>, Error> {
+        let to = match to {
+            Some(to) => to,
+            None => {
+                self.base_api
+                    .fetch_best_block_hash()
+                    .await?
+                    .ok_or(Error::NoBestBlock)?
+            }
+        };
+        let from_number = self.block_number(from).await?;
+        let to_number = self.block_number(to).await?;
+
+        // the last known value of each key, the first block of each chunk reports
+        // the values of all the keys even if they didn't change
+        let mut last_values: HashMap<Vec<u8>, Option<Vec<u8>>> = HashMap::new();
+        let mut timeline = vec![];
+        let mut start_number = from_number;
+        let mut start_hash = from;
+        while start_number <= to_number {
+            let end_number =
+                (start_number + QUERY_STORAGE_CHUNK - 1).min(to_number);
+            let end_hash = if end_number == to_number {
+                to
+            } else {
+                self.block_hash_of(end_number).await?
+            };
+            let changes = self
+                .query_storage_range(storage_keys, start_hash, end_hash)
+                .await?;
+            for change in changes {
+                let last = last_values.get(&change.key.0);
+                if last != Some(&change.value) {
+                    last_values
+                        .insert(change.key.0.clone(), change.value.clone());
+                    timeline.push(change);
+                }
+            }
+            start_number = end_number + 1;
+            if start_number <= to_number {
+                start_hash = self.block_hash_of(start_number).await?;
+            }
+        }
+        Ok(timeline)
+    }
+
+    /// Same as [`Api::query_storage_opaque`] with the values decoded into `V`
+    pub async fn query_storage<V>(
+        &self,
+        storage_keys: &[StorageKey],
+        from: H256,
+        to: Option<H256>,
+    ) -> Result<Vec<StorageChange<V>>, Error>
+    where
+        V: Decode,
+    {
+        self.query_storage_opaque(storage_keys, from, to)
+            .await?
+            .into_iter()
+            .map(|change| {
+                change.try_map(|bytes| {
+                    V::decode(&mut bytes.as_slice()).map_err(Error::from)
+                })
+            })
+            .collect()
+    }
+
+    /// Same as [`Api::query_storage_opaque`] with the values decoded dynamically,
+    /// each key is paired with the type id of its value,
+    /// see [`Metadata::storage_value_type_id`](crate::Metadata::storage_value_type_id).
+    ///
+    /// The values are decoded using the current metadata, the types may have changed if the
+    /// runtime was upgraded within the range.
+    pub async fn query_storage_dynamic(
+        &self,
+        storage_keys: &[(StorageKey, u32)],
+        from: H256,
+        to: Option<H256>,
+    ) -> Result<Vec<StorageChange<dynamic::Value>>, Error> {
+        let type_ids: HashMap<&[u8], u32> = storage_keys
+            .iter()
+            .map(|(key, type_id)| (key.0.as_slice(), *type_id))
+            .collect();
+        let keys: Vec<StorageKey> =
+            storage_keys.iter().map(|(key, _)| key.clone()).collect();
+        self.query_storage_opaque(&keys, from, to)
+            .await?
+            .into_iter()
+            // skip the keys which were not requested, the node is not expected to return any
+            .filter_map(|change| {
+                let type_id = *type_ids.get(change.key.0.as_slice())?;
+                Some(change.try_map(|bytes| {
+                    self.metadata
+                        .decode_dynamic(type_id, &bytes)
+                        .map_err(Error::from)
+                }))
+            })
+            .collect()
+    }
+
+    /// A single `state_queryStorage` request
+    async fn query_storage_range(
+        &self,
+        storage_keys: &[StorageKey],
+        from: H256,
+        to: H256,
+    ) -> Result<Vec<StorageChange<Vec<u8>>>, Error> {
+        let value = self
+            .base_api
+            .json_request_value("state_queryStorage", (storage_keys, from, to))
+            .await?;
+        let mut changes = vec![];
+        if let Some(value) = value {
+            let change_sets = value.as_array().ok_or_else(|| {
+                Error::UnexpectedResponse(
+                    "state_queryStorage".to_string(),
+                    value.clone(),
+                )
+            })?;
+            for change_set in change_sets {
+                changes.extend(parse_change_set(change_set)?);
+            }
+        }
+        Ok(changes)
+    }
+
+    async fn block_number(&self, block_hash: H256) -> Result<u64, Error> {
+        let header: generic::Header<u64, BlakeTwo256> =
+            self.chain_get_header(block_hash).await?.ok_or_else(|| {
+                Error::EmptyResponse(format!(
+                    "chain_getHeader({:?})",
+                    block_hash
+                ))
+            })?;
+        Ok(header.number)
+    }
+
+    async fn block_hash_of(&self, number: u64) -> Result<H256, Error> {
+        self.fetch_block_hash(number as u32).await?.ok_or_else(|| {
+            Error::EmptyResponse(format!("chain_getBlockHash({})", number))
+        })
+    }
+}
+
+/// The changes of a `{ block, changes: [[key, value]] }` change set,
+/// as returned by `state_queryStorage` and `state_subscribeStorage`
+pub(crate) fn parse_change_set(
+    change_set: &serde_json::Value,
+) -> Result<Vec<StorageChange<Vec<u8>>>, Error> {
+    let invalid = || {
+        Error::UnexpectedResponse(
+            "storage change set".to_string(),
+            change_set.clone(),
+        )
+    };
+    let block_hash = change_set["block"].as_str().ok_or_else(invalid)?;
+    let block_hash = H256::from_hex(block_hash)?;
+    let changes = change_set["changes"].as_array().ok_or_else(invalid)?;
+    changes
+        .iter()
+        .map(|change| {
+            let key = change[0].as_str().ok_or_else(invalid)?;
+            let value = change[1].as_str().map(Vec::from_hex).transpose()?;
+            Ok(StorageChange {
+                block_hash,
+                key: StorageKey(Vec::from_hex(key)?),
+                value,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,275 @@
+//! Caches the reads of the data which never changes once it is at a block hash:
+//! the storage values at a block, the headers, the blocks and the runtime versions,
+//! along with the metadata of each runtime version.
+use crate::{
+    utils,
+    Metadata,
+};
+use lru::LruCache;
+use sp_core::H256;
+use sp_version::RuntimeVersion;
+use std::{
+    sync::{
+        Arc,
+        Mutex,
+        MutexGuard,
+    },
+    time::Duration,
+};
+
+#[derive(Clone, Debug)]
+pub struct CacheConfig {
+    /// the maximum number of entries of each kind
+    pub capacity: usize,
+    /// how long the reads without a block hash, ie: at the best block, are kept
+    pub latest_ttl: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 4096,
+            latest_ttl: Duration::from_secs(2),
+        }
+    }
+}
+
+/// The number of reads served from the cache and from the node
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// the fraction of the reads served from the cache
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// A LRU cache shared by the clones of the [`Api`](crate::Api)
+#[derive(Clone)]
+pub struct ReadCache {
+    latest_ttl: Duration,
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    storage: LruCache<(H256, Vec<u8>), Option<Vec<u8>>>,
+    /// the reads at the best block, with the time they were fetched
+    latest: LruCache<Vec<u8>, (Duration, Option<Vec<u8>>)>,
+    headers: LruCache<H256, serde_json::Value>,
+    blocks: LruCache<H256, serde_json::Value>,
+    runtime_versions: LruCache<H256, RuntimeVersion>,
+    /// keyed by the spec version of the runtime
+    metadata: LruCache<u32, Metadata>,
+    stats: CacheStats,
+}
+
+impl ReadCache {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            latest_ttl: config.latest_ttl,
+            inner: Arc::new(Mutex::new(Inner {
+                storage: LruCache::new(config.capacity),
+                latest: LruCache::new(config.capacity),
+                headers: LruCache::new(config.capacity),
+                blocks: LruCache::new(config.capacity),
+                runtime_versions: LruCache::new(config.capacity),
+                metadata: LruCache::new(config.capacity),
+                stats: CacheStats::default(),
+            })),
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.lock().stats
+    }
+
+    /// Forget the reads at the best block, call this when a new block is imported
+    pub fn invalidate_latest(&self) {
+        self.lock().latest.clear();
+    }
+
+    /// Forget all the entries, the statistics are kept
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.storage.clear();
+        inner.latest.clear();
+        inner.headers.clear();
+        inner.blocks.clear();
+        inner.runtime_versions.clear();
+        inner.metadata.clear();
+    }
+
+    /// The cached value at `storage_key`, at block `at` or at the best block.
+    /// The outer `None` is a miss, the inner one is a key without value.
+    pub(crate) fn storage(
+        &self,
+        storage_key: &[u8],
+        at: Option<H256>,
+    ) -> Option<Option<Vec<u8>>> {
+        let mut inner = self.lock();
+        let value = match at {
+            Some(at) => inner.storage.get(&(at, storage_key.to_vec())).cloned(),
+            None => {
+                let now = utils::now();
+                match inner.latest.get(&storage_key.to_vec()) {
+                    Some((fetched, value))
+                        if now.saturating_sub(*fetched) < self.latest_ttl =>
+                    {
+                        Some(value.clone())
+                    }
+                    _ => None,
+                }
+            }
+        };
+        inner.count(value.is_some());
+        value
+    }
+
+    pub(crate) fn put_storage(
+        &self,
+        storage_key: Vec<u8>,
+        at: Option<H256>,
+        value: Option<Vec<u8>>,
+    ) {
+        let mut inner = self.lock();
+        match at {
+            Some(at) => {
+                inner.storage.put((at, storage_key), value);
+            }
+            None => {
+                inner.latest.put(storage_key, (utils::now(), value));
+            }
+        }
+    }
+
+    pub(crate) fn header(&self, hash: H256) -> Option<serde_json::Value> {
+        let mut inner = self.lock();
+        let header = inner.headers.get(&hash).cloned();
+        inner.count(header.is_some());
+        header
+    }
+
+    pub(crate) fn put_header(&self, hash: H256, header: serde_json::Value) {
+        self.lock().headers.put(hash, header);
+    }
+
+    pub(crate) fn block(&self, hash: H256) -> Option<serde_json::Value> {
+        let mut inner = self.lock();
+        let block = inner.blocks.get(&hash).cloned();
+        inner.count(block.is_some());
+        block
+    }
+
+    pub(crate) fn put_block(&self, hash: H256, block: serde_json::Value) {
+        self.lock().blocks.put(hash, block);
+    }
+
+    pub(crate) fn runtime_version(&self, hash: H256) -> Option<RuntimeVersion> {
+        let mut inner = self.lock();
+        let runtime_version = inner.runtime_versions.get(&hash).cloned();
+        inner.count(runtime_version.is_some());
+        runtime_version
+    }
+
+    pub(crate) fn put_runtime_version(
+        &self,
+        hash: H256,
+        runtime_version: RuntimeVersion,
+    ) {
+        self.lock().runtime_versions.put(hash, runtime_version);
+    }
+
+    pub(crate) fn metadata(&self, spec_version: u32) -> Option<Metadata> {
+        let mut inner = self.lock();
+        let metadata = inner.metadata.get(&spec_version).cloned();
+        inner.count(metadata.is_some());
+        metadata
+    }
+
+    pub(crate) fn put_metadata(&self, spec_version: u32, metadata: Metadata) {
+        self.lock().metadata.put(spec_version, metadata);
+    }
+
+    fn lock(&self) -> MutexGuard<Inner> {
+        // the caches are always left in a consistent state, so a poisoned lock is still usable
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Inner {
+    fn count(&mut self, hit: bool) {
+        if hit {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_hits_and_misses() {
+        let cache = ReadCache::new(CacheConfig {
+            capacity: 2,
+            latest_ttl: Duration::from_secs(60),
+        });
+        let block = H256::repeat_byte(1);
+        assert_eq!(cache.storage(b"alice", Some(block)), None);
+        cache.put_storage(b"alice".to_vec(), Some(block), Some(vec![1]));
+        cache.put_storage(b"bob".to_vec(), Some(block), None);
+        assert_eq!(cache.storage(b"alice", Some(block)), Some(Some(vec![1])));
+        assert_eq!(cache.storage(b"bob", Some(block)), Some(None));
+        // other blocks and the best block are separate entries
+        assert_eq!(cache.storage(b"alice", Some(H256::repeat_byte(2))), None);
+        assert_eq!(cache.storage(b"alice", None), None);
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 3 });
+
+        // the least recently used entry is evicted
+        cache.put_storage(b"charlie".to_vec(), Some(block), Some(vec![3]));
+        assert_eq!(cache.storage(b"alice", Some(block)), None);
+    }
+
+    #[test]
+    fn latest_reads_expire() {
+        let cache = ReadCache::new(CacheConfig {
+            capacity: 8,
+            latest_ttl: Duration::ZERO,
+        });
+        cache.put_storage(b"alice".to_vec(), None, Some(vec![1]));
+        assert_eq!(cache.storage(b"alice", None), None);
+
+        let cache = ReadCache::new(CacheConfig::default());
+        cache.put_storage(b"alice".to_vec(), None, Some(vec![1]));
+        assert_eq!(cache.storage(b"alice", None), Some(Some(vec![1])));
+        cache.invalidate_latest();
+        assert_eq!(cache.storage(b"alice", None), None);
+    }
+
+    #[test]
+    fn metadata_by_spec_version() {
+        let cache = ReadCache::new(CacheConfig::default());
+        assert!(cache.metadata(100).is_none());
+        cache.put_metadata(100, crate::types::metadata::test_utils::metadata());
+        let metadata = cache.metadata(100).expect("must be cached");
+        assert!(metadata.pallet("ForumModule").is_ok());
+        assert!(cache.metadata(101).is_none());
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2 });
+
+        cache.clear();
+        assert!(cache.metadata(100).is_none());
+    }
+}
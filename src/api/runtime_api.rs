@@ -0,0 +1,164 @@
+//! Call the runtime apis through `state_call`,
+//! these are the apis declared in `impl_runtime_apis!` of the runtime
+use crate::{
+    api::Api,
+    error::Error,
+    types::{
+        fee::{
+            FeeDetails,
+            RuntimeDispatchInfo,
+        },
+        metadata::Encoded,
+    },
+    Metadata,
+};
+use codec::{
+    Decode,
+    Encode,
+};
+use frame_metadata::RuntimeMetadataPrefixed;
+use sp_core::{
+    crypto::AccountId32,
+    ed25519,
+    sr25519,
+    H256,
+};
+use sp_runtime::transaction_validity::{
+    TransactionSource,
+    TransactionValidity,
+};
+use sp_version::RuntimeVersion;
+
+impl Api {
+    /// Call the runtime api `method`, ie: `AccountNonceApi_account_nonce`,
+    /// at block `at` or at the best block if `at` is `None`.
+    ///
+    /// The `args` are encoded one after the other, so multiple arguments are passed as a tuple.
+    /// Use [`Encoded`] for arguments which are already encoded such as extrinsics.
+    pub async fn runtime_call<Args, Ret>(
+        &self,
+        method: &str,
+        args: Args,
+        at: Option<H256>,
+    ) -> Result<Option<Ret>, Error>
+    where
+        Args: Encode,
+        Ret: Decode,
+    {
+        let bytes =
+            self.base_api.state_call(method, &args.encode(), at).await?;
+        match bytes {
+            Some(bytes) => Ok(Some(Ret::decode(&mut bytes.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// `Core_version`, the runtime version as seen by the runtime at block `at`
+    pub async fn runtime_api_version(
+        &self,
+        at: Option<H256>,
+    ) -> Result<Option<RuntimeVersion>, Error> {
+        self.runtime_call("Core_version", (), at).await
+    }
+
+    /// `Metadata_metadata`, the metadata of the runtime at block `at`
+    pub async fn runtime_api_metadata(
+        &self,
+        at: Option<H256>,
+    ) -> Result<Option<Metadata>, Error> {
+        // the metadata is returned as an opaque byte vector
+        let opaque: Option<Vec<u8>> =
+            self.runtime_call("Metadata_metadata", (), at).await?;
+        match opaque {
+            Some(opaque) => {
+                let rt_metadata =
+                    RuntimeMetadataPrefixed::decode(&mut opaque.as_slice())?;
+                Ok(Some(Metadata::try_from(rt_metadata)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// `AccountNonceApi_account_nonce`, the nonce of the `account` at block `at`,
+    /// unlike `system_accountNextIndex` this doesn't include the transactions in the pool
+    pub async fn runtime_api_account_nonce(
+        &self,
+        account: &AccountId32,
+        at: Option<H256>,
+    ) -> Result<Option<u32>, Error> {
+        self.runtime_call("AccountNonceApi_account_nonce", account, at)
+            .await
+    }
+
+    /// `TransactionPaymentApi_query_info`, the weight, class and fee of the encoded extrinsic
+    pub async fn runtime_api_query_info(
+        &self,
+        extrinsic: &[u8],
+        at: Option<H256>,
+    ) -> Result<Option<RuntimeDispatchInfo>, Error> {
+        let len = extrinsic.len() as u32;
+        self.runtime_call(
+            "TransactionPaymentApi_query_info",
+            (Encoded(extrinsic.to_vec()), len),
+            at,
+        )
+        .await
+    }
+
+    /// `TransactionPaymentApi_query_fee_details`, the breakdown of the fee of the encoded extrinsic
+    pub async fn runtime_api_query_fee_details(
+        &self,
+        extrinsic: &[u8],
+        at: Option<H256>,
+    ) -> Result<Option<FeeDetails>, Error> {
+        let len = extrinsic.len() as u32;
+        self.runtime_call(
+            "TransactionPaymentApi_query_fee_details",
+            (Encoded(extrinsic.to_vec()), len),
+            at,
+        )
+        .await
+    }
+
+    /// `TaggedTransactionQueue_validate_transaction`, validate the encoded extrinsic
+    /// as the transaction pool would when it is received from the `source`.
+    /// The extrinsic is validated on top of the block `block_hash`.
+    pub async fn runtime_api_validate_transaction(
+        &self,
+        source: TransactionSource,
+        extrinsic: &[u8],
+        block_hash: H256,
+    ) -> Result<Option<TransactionValidity>, Error> {
+        self.runtime_call(
+            "TaggedTransactionQueue_validate_transaction",
+            (source, Encoded(extrinsic.to_vec()), block_hash),
+            Some(block_hash),
+        )
+        .await
+    }
+
+    /// `AuraApi_slot_duration`, the slot duration in milliseconds
+    pub async fn runtime_api_aura_slot_duration(
+        &self,
+        at: Option<H256>,
+    ) -> Result<Option<u64>, Error> {
+        self.runtime_call("AuraApi_slot_duration", (), at).await
+    }
+
+    /// `AuraApi_authorities`, the current block authors
+    pub async fn runtime_api_aura_authorities(
+        &self,
+        at: Option<H256>,
+    ) -> Result<Option<Vec<sr25519::Public>>, Error> {
+        self.runtime_call("AuraApi_authorities", (), at).await
+    }
+
+    /// `GrandpaApi_grandpa_authorities`, the current finality authorities and their weights
+    pub async fn runtime_api_grandpa_authorities(
+        &self,
+        at: Option<H256>,
+    ) -> Result<Option<Vec<(ed25519::Public, u64)>>, Error> {
+        self.runtime_call("GrandpaApi_grandpa_authorities", (), at)
+            .await
+    }
+}
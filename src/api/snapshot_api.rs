@@ -0,0 +1,124 @@
+//! Export the storage of some pallets, or of the whole state, into a snapshot
+use crate::{
+    api::Api,
+    error::Error,
+    types::snapshot::{
+        Header,
+        StorageSnapshot,
+        SNAPSHOT_VERSION,
+    },
+    Metadata,
+};
+use codec::Encode;
+use sp_core::{
+    storage::StorageKey,
+    H256,
+};
+use std::convert::TryFrom;
+
+/// The number of keys fetched in a single `state_getKeysPaged` request
+const SNAPSHOT_PAGE_SIZE: u32 = 512;
+
+impl Api {
+    /// Export the storage entries of the `pallets` at block `at` into a snapshot,
+    /// or the whole state if `pallets` is empty.
+    /// The best block is used if `at` is `None`.
+    ///
+    /// The snapshot also has the header and the metadata of the block, so it can be read
+    /// offline with [`SnapshotStorage`](crate::types::snapshot::SnapshotStorage).
+    pub async fn export_snapshot(
+        &self,
+        pallets: &[&str],
+        at: Option<H256>,
+    ) -> Result<StorageSnapshot, Error> {
+        let block_hash = match at {
+            Some(block_hash) => block_hash,
+            None => {
+                self.base_api
+                    .fetch_best_block_hash()
+                    .await?
+                    .ok_or(Error::NoBestBlock)?
+            }
+        };
+        let header: Header =
+            self.chain_get_header(block_hash).await?.ok_or_else(|| {
+                Error::EmptyResponse(format!(
+                    "chain_getHeader({:?})",
+                    block_hash
+                ))
+            })?;
+        let runtime_metadata = self
+            .base_api
+            .fetch_runtime_metadata_at(Some(block_hash))
+            .await?
+            .ok_or(Error::NoMetadata)?;
+        let encoded_metadata = runtime_metadata.encode();
+        let metadata = Metadata::try_from(runtime_metadata)?;
+
+        // all the storage items of a pallet start with the hash of its name
+        let prefixes = if pallets.is_empty() {
+            vec![vec![]]
+        } else {
+            pallets
+                .iter()
+                .map(|pallet| {
+                    let pallet = metadata.pallet(pallet)?;
+                    Ok(sp_core::twox_128(pallet.name.as_bytes()).to_vec())
+                })
+                .collect::<Result<Vec<_>, Error>>()?
+        };
+
+        let mut entries = vec![];
+        for prefix in prefixes.iter() {
+            entries.extend(
+                self.fetch_opaque_storage_entries(
+                    StorageKey(prefix.clone()),
+                    block_hash,
+                )
+                .await?,
+            );
+        }
+        Ok(StorageSnapshot {
+            version: SNAPSHOT_VERSION,
+            block_hash,
+            header,
+            metadata: encoded_metadata,
+            prefixes,
+            entries,
+        })
+    }
+
+    /// all the key/value pairs whose key starts with `prefix`
+    pub(crate) async fn fetch_opaque_storage_entries(
+        &self,
+        prefix: StorageKey,
+        block_hash: H256,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error> {
+        let mut entries = vec![];
+        let mut start_key = None;
+        loop {
+            let storage_keys = self
+                .fetch_storage_keys_paged_by_prefix(
+                    prefix.clone(),
+                    SNAPSHOT_PAGE_SIZE,
+                    start_key,
+                    Some(block_hash),
+                )
+                .await?
+                .unwrap_or_default();
+            let values = self
+                .fetch_opaque_storage_many(&storage_keys, Some(block_hash))
+                .await?;
+            start_key = storage_keys.last().cloned();
+            let page_len = storage_keys.len();
+            for (storage_key, value) in storage_keys.into_iter().zip(values) {
+                if let Some(value) = value {
+                    entries.push((storage_key.0, value));
+                }
+            }
+            if page_len < SNAPSHOT_PAGE_SIZE as usize {
+                return Ok(entries);
+            }
+        }
+    }
+}
@@ -1,5 +1,11 @@
 use crate::{
-    api::Api,
+    api::{
+        storage_backend::StorageAt,
+        Api,
+        StorageBackend,
+    },
+    signer::MaybeSendSync,
+    types::storage::StorageKeys,
     utils::FromHexStr,
     Error,
 };
@@ -11,31 +17,42 @@ use scale_info::{
     form::PortableForm,
     Type,
 };
-use sp_core::storage::StorageKey;
+use sp_core::{
+    storage::StorageKey,
+    H256,
+};
+use std::collections::HashMap;
 
 impl Api {
+    /// the storage of the node at the block `at`, or at the best block if `at` is `None`
+    pub(crate) fn storage_at(&self, at: Option<H256>) -> StorageAt<'_> {
+        StorageAt { api: self, at }
+    }
+
     // curl -H "Content-Type: application/json" -d '{"id":"1","jsonrpc":"2.0","method":"state_getStorage","params":["0x26aa394eea5630e07c48ae0c9558cef7b99d880ec681799c0cf30e8886371da9de1e86a9a8c739864cf3cc5ec2bea59fd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d",null]}' http://localhost:9933/
     pub async fn fetch_storage_value<V>(
         &self,
         module: &str,
         storage_name: &str,
+        at: Option<H256>,
     ) -> Result<Option<V>, Error>
     where
         V: Decode,
     {
-        let storage_key =
-            self.metadata.storage_value_key(module, storage_name)?;
-        self.fetch_storage_by_key_hash(storage_key).await
+        self.storage_at(at)
+            .fetch_storage_value(module, storage_name)
+            .await
     }
 
     pub async fn fetch_opaque_storage_value(
         &self,
         module: &str,
         storage_name: &str,
+        at: Option<H256>,
     ) -> Result<Option<Vec<u8>>, Error> {
-        let storage_key =
-            self.metadata.storage_value_key(module, storage_name)?;
-        self.fetch_opaque_storage_by_key_hash(storage_key).await
+        self.storage_at(at)
+            .fetch_opaque_storage_value(module, storage_name)
+            .await
     }
 
     pub async fn fetch_storage_map<K, V>(
@@ -43,14 +60,15 @@ impl Api {
         module: &str,
         storage_name: &str,
         key: K,
+        at: Option<H256>,
     ) -> Result<Option<V>, Error>
     where
-        K: Encode,
+        K: Encode + MaybeSendSync,
         V: Decode,
     {
-        let storage_key =
-            self.metadata.storage_map_key(module, storage_name, key)?;
-        self.fetch_storage_by_key_hash(storage_key).await
+        self.storage_at(at)
+            .fetch_storage_map(module, storage_name, key)
+            .await
     }
 
     pub async fn fetch_opaque_storage_map<K>(
@@ -58,13 +76,14 @@ impl Api {
         module: &str,
         storage_name: &str,
         key: K,
+        at: Option<H256>,
     ) -> Result<Option<Vec<u8>>, Error>
     where
-        K: Encode,
+        K: Encode + MaybeSendSync,
     {
-        let storage_key =
-            self.metadata.storage_map_key(module, storage_name, key)?;
-        self.fetch_opaque_storage_by_key_hash(storage_key).await
+        self.storage_at(at)
+            .fetch_opaque_storage_map(module, storage_name, key)
+            .await
     }
 
     pub async fn fetch_storage_double_map<K, Q, V>(
@@ -73,19 +92,16 @@ impl Api {
         storage_name: &str,
         first: K,
         second: Q,
+        at: Option<H256>,
     ) -> Result<Option<V>, Error>
     where
-        K: Encode,
-        Q: Encode,
+        K: Encode + MaybeSendSync,
+        Q: Encode + MaybeSendSync,
         V: Decode,
     {
-        let storage_key = self.metadata.storage_double_map_key(
-            module,
-            storage_name,
-            first,
-            second,
-        )?;
-        self.fetch_storage_by_key_hash(storage_key).await
+        self.storage_at(at)
+            .fetch_storage_double_map(module, storage_name, first, second)
+            .await
     }
 
     pub async fn fetch_opaque_storage_double_map<K, Q>(
@@ -94,50 +110,127 @@ impl Api {
         storage_name: &str,
         first: K,
         second: Q,
+        at: Option<H256>,
+    ) -> Result<Option<Vec<u8>>, Error>
+    where
+        K: Encode + MaybeSendSync,
+        Q: Encode + MaybeSendSync,
+    {
+        self.storage_at(at)
+            .fetch_opaque_storage_double_map(
+                module,
+                storage_name,
+                first,
+                second,
+            )
+            .await
+    }
+
+    /// fetch the value of an n-map entry, `keys` is a tuple with one key per hasher
+    pub async fn fetch_storage_nmap<K, V>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        keys: K,
+        at: Option<H256>,
+    ) -> Result<Option<V>, Error>
+    where
+        K: StorageKeys + MaybeSendSync,
+        V: Decode,
+    {
+        self.storage_at(at)
+            .fetch_storage_nmap(module, storage_name, keys)
+            .await
+    }
+
+    pub async fn fetch_opaque_storage_nmap<K>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        keys: K,
+        at: Option<H256>,
     ) -> Result<Option<Vec<u8>>, Error>
     where
-        K: Encode,
-        Q: Encode,
+        K: StorageKeys + MaybeSendSync,
+    {
+        self.storage_at(at)
+            .fetch_opaque_storage_nmap(module, storage_name, keys)
+            .await
+    }
+
+    /// see [`StorageBackend::fetch_or_default`]
+    pub async fn fetch_or_default<V>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        storage_key: StorageKey,
+        at: Option<H256>,
+    ) -> Result<Option<V>, Error>
+    where
+        V: Decode,
     {
-        let storage_key = self.metadata.storage_double_map_key(
-            module,
-            storage_name,
-            first,
-            second,
-        )?;
-        self.fetch_opaque_storage_by_key_hash(storage_key).await
+        self.storage_at(at)
+            .fetch_or_default(module, storage_name, storage_key)
+            .await
     }
 
+    /// see [`StorageBackend::fetch_raw`]
+    pub async fn fetch_raw<V>(
+        &self,
+        storage_key: StorageKey,
+        at: Option<H256>,
+    ) -> Result<Option<V>, Error>
+    where
+        V: Decode,
+    {
+        self.storage_at(at).fetch_raw(storage_key).await
+    }
+
+    /// same as [`Api::fetch_raw`]
     pub async fn fetch_storage_by_key_hash<V>(
         &self,
         storage_key: StorageKey,
+        at: Option<H256>,
     ) -> Result<Option<V>, Error>
     where
         V: Decode,
     {
-        match self.fetch_opaque_storage_by_key_hash(storage_key).await? {
-            Some(storage) => Ok(Some(Decode::decode(&mut storage.as_slice())?)),
-            None => Ok(None),
-        }
+        self.fetch_raw(storage_key, at).await
     }
 
     pub async fn fetch_opaque_storage_by_key_hash(
         &self,
         storage_key: StorageKey,
+        at: Option<H256>,
     ) -> Result<Option<Vec<u8>>, Error> {
+        if let Some(cached) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.storage(&storage_key.0, at))
+        {
+            return Ok(cached);
+        }
         let value = self
             .base_api
-            .json_request_value("state_getStorage", [storage_key])
+            .json_request_value("state_getStorage", (&storage_key, at))
             .await?;
 
-        match value {
+        let data = match value {
             Some(value) => {
-                let value_str = value.as_str().expect("must be a str");
-                let data = Vec::from_hex(value_str)?;
-                Ok(Some(data))
+                let value_str = value.as_str().ok_or_else(|| {
+                    Error::UnexpectedResponse(
+                        "state_getStorage".to_string(),
+                        value.clone(),
+                    )
+                })?;
+                Some(Vec::from_hex(value_str)?)
             }
-            None => Ok(None),
+            None => None,
+        };
+        if let Some(cache) = &self.cache {
+            cache.put_storage(storage_key.0, at, data.clone());
         }
+        Ok(data)
     }
 
     pub async fn fetch_opaque_storage_map_paged<K>(
@@ -146,32 +239,101 @@ impl Api {
         storage_name: &str,
         count: u32,
         start_key: Option<K>,
+        at: Option<H256>,
     ) -> Result<Option<Vec<Vec<u8>>>, Error>
     where
-        K: Encode,
+        K: Encode + MaybeSendSync,
     {
-        let storage_keys: Option<Vec<StorageKey>> = self
-            .fetch_opaque_storage_keys_paged(
+        self.storage_at(at)
+            .fetch_opaque_storage_map_paged(
                 module,
                 storage_name,
                 count,
                 start_key,
             )
+            .await
+    }
+
+    /// fetch and decode the values of the `storage_keys` in a single request,
+    /// the values are returned in the same order as the keys
+    pub async fn fetch_storage_many<V>(
+        &self,
+        storage_keys: &[StorageKey],
+        at: Option<H256>,
+    ) -> Result<Vec<Option<V>>, Error>
+    where
+        V: Decode,
+    {
+        self.storage_at(at).fetch_storage_many(storage_keys).await
+    }
+
+    /// fetch the values of the `storage_keys` in a single `state_queryStorageAt` request,
+    /// so all the values are read from the same block.
+    /// The values are returned in the same order as the keys
+    pub async fn fetch_opaque_storage_many(
+        &self,
+        storage_keys: &[StorageKey],
+        at: Option<H256>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        // only the keys which are not in the cache are fetched
+        let cached: Vec<Option<Option<Vec<u8>>>> = storage_keys
+            .iter()
+            .map(|storage_key| {
+                self.cache
+                    .as_ref()
+                    .and_then(|cache| cache.storage(&storage_key.0, at))
+            })
+            .collect();
+        let missing_keys: Vec<&StorageKey> = storage_keys
+            .iter()
+            .zip(cached.iter())
+            .filter(|(_storage_key, cached)| cached.is_none())
+            .map(|(storage_key, _cached)| storage_key)
+            .collect();
+        if missing_keys.is_empty() {
+            return Ok(cached.into_iter().flatten().collect());
+        }
+        let value = self
+            .base_api
+            .json_request_value("state_queryStorageAt", (&missing_keys, at))
             .await?;
 
-        if let Some(storage_keys) = storage_keys {
-            let mut storage_values = Vec::with_capacity(storage_keys.len());
-            for storage_key in storage_keys.into_iter() {
-                if let Some(bytes) =
-                    self.fetch_opaque_storage_by_key_hash(storage_key).await?
-                {
-                    storage_values.push(bytes);
+        // the changes are in a single change set of the requested block
+        let mut values: HashMap<Vec<u8>, Option<Vec<u8>>> = HashMap::new();
+        if let Some(value) = value {
+            let invalid = || {
+                Error::UnexpectedResponse(
+                    "state_queryStorageAt".to_string(),
+                    value.clone(),
+                )
+            };
+            let change_sets = value.as_array().ok_or_else(invalid)?;
+            for change_set in change_sets {
+                let changes =
+                    change_set["changes"].as_array().ok_or_else(invalid)?;
+                for change in changes {
+                    let key = change[0].as_str().ok_or_else(invalid)?;
+                    let data =
+                        change[1].as_str().map(Vec::from_hex).transpose()?;
+                    values.insert(Vec::from_hex(key)?, data);
                 }
             }
-            Ok(Some(storage_values))
-        } else {
-            Ok(None)
         }
+        if let Some(cache) = &self.cache {
+            for storage_key in missing_keys {
+                let value = values.get(&storage_key.0).cloned().flatten();
+                cache.put_storage(storage_key.0.clone(), at, value);
+            }
+        }
+        Ok(storage_keys
+            .iter()
+            .zip(cached)
+            .map(|(storage_key, cached)| {
+                cached.unwrap_or_else(|| {
+                    values.get(&storage_key.0).cloned().flatten()
+                })
+            })
+            .collect())
     }
 
     pub fn storage_map_type(
@@ -188,43 +350,54 @@ impl Api {
         storage_name: &str,
         count: u32,
         start_key: Option<K>,
+        at: Option<H256>,
     ) -> Result<Option<Vec<StorageKey>>, Error>
     where
-        K: Encode,
+        K: Encode + MaybeSendSync,
     {
-        let storage_key =
-            self.metadata.storage_map_key_prefix(module, storage_name)?;
-        let start_storage_key = if let Some(start_key) = start_key {
-            Some(self.metadata.storage_map_key(
+        self.storage_at(at)
+            .fetch_opaque_storage_keys_paged(
                 module,
                 storage_name,
+                count,
                 start_key,
-            )?)
-        } else {
-            None
-        };
+            )
+            .await
+    }
+
+    /// fetch `count` storage keys which starts with `prefix`, after the `start_key`
+    pub async fn fetch_storage_keys_paged_by_prefix(
+        &self,
+        prefix: StorageKey,
+        count: u32,
+        start_key: Option<StorageKey>,
+        at: Option<H256>,
+    ) -> Result<Option<Vec<StorageKey>>, Error> {
         let value = self
             .base_api
             .json_request_value(
                 "state_getKeysPaged",
-                (storage_key, count, start_storage_key),
+                (prefix, count, start_key, at),
             )
             .await?;
 
         match value {
             Some(value) => {
-                let value_array =
-                    value.as_array().expect("must be an array of str");
-                let data: Vec<StorageKey> = value_array
-                    .into_iter()
+                let invalid = || {
+                    Error::UnexpectedResponse(
+                        "state_getKeysPaged".to_string(),
+                        value.clone(),
+                    )
+                };
+                let data = value
+                    .as_array()
+                    .ok_or_else(invalid)?
+                    .iter()
                     .map(|v| {
-                        let value_str =
-                            v.as_str().expect("each item must be a str");
-                        let bytes = Vec::from_hex(value_str)
-                            .expect("must convert hex value to bytes");
-                        StorageKey(bytes)
+                        let value_str = v.as_str().ok_or_else(invalid)?;
+                        Ok(StorageKey(Vec::from_hex(value_str)?))
                     })
-                    .collect();
+                    .collect::<Result<Vec<_>, Error>>()?;
                 Ok(Some(data))
             }
             None => Ok(None),
@@ -0,0 +1,415 @@
+//! The typed storage reads shared by the node and the offline sources of storage
+use crate::{
+    api::Api,
+    error::Error,
+    signer::MaybeSendSync,
+    types::{
+        account_info::AccountInfo,
+        storage::{
+            self,
+            StorageKeys,
+        },
+    },
+    Metadata,
+};
+use async_trait::async_trait;
+use codec::{
+    Decode,
+    Encode,
+};
+use sp_core::{
+    crypto::AccountId32,
+    storage::StorageKey,
+    H256,
+};
+
+/// A source of the storage of the chain at one block.
+///
+/// The implementors only provide the raw reads, the typed reads compute the storage keys with
+/// the [`Metadata`] of the source, so the same code can read from the node at the best block
+/// ([`Api`]), at an older block ([`AtBlock`](crate::AtBlock)) or from a snapshot file
+/// ([`SnapshotStorage`](crate::types::snapshot::SnapshotStorage)).
+///
+/// The methods of [`Api`] with an `at` argument take precedence over the methods of this
+/// trait when called directly on the [`Api`].
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait StorageBackend: MaybeSendSync {
+    /// the metadata the storage keys are computed with
+    fn metadata(&self) -> &Metadata;
+
+    /// the value at `storage_key` as it is stored
+    async fn fetch_opaque_storage_by_key_hash(
+        &self,
+        storage_key: StorageKey,
+    ) -> Result<Option<Vec<u8>>, Error>;
+
+    /// the values of the `storage_keys`, in the same order as the keys
+    async fn fetch_opaque_storage_many(
+        &self,
+        storage_keys: &[StorageKey],
+    ) -> Result<Vec<Option<Vec<u8>>>, Error>;
+
+    /// `count` storage keys which starts with `prefix`, after the `start_key`
+    async fn fetch_storage_keys_paged_by_prefix(
+        &self,
+        prefix: StorageKey,
+        count: u32,
+        start_key: Option<StorageKey>,
+    ) -> Result<Option<Vec<StorageKey>>, Error>;
+
+    async fn fetch_storage_value<V>(
+        &self,
+        module: &str,
+        storage_name: &str,
+    ) -> Result<Option<V>, Error>
+    where
+        V: Decode,
+    {
+        let storage_key =
+            self.metadata().storage_value_key(module, storage_name)?;
+        self.fetch_storage_by_key_hash(storage_key).await
+    }
+
+    async fn fetch_opaque_storage_value(
+        &self,
+        module: &str,
+        storage_name: &str,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let storage_key =
+            self.metadata().storage_value_key(module, storage_name)?;
+        self.fetch_opaque_storage_by_key_hash(storage_key).await
+    }
+
+    async fn fetch_storage_map<K, V>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        key: K,
+    ) -> Result<Option<V>, Error>
+    where
+        K: Encode + MaybeSendSync,
+        V: Decode,
+    {
+        let storage_key =
+            self.metadata().storage_map_key(module, storage_name, key)?;
+        self.fetch_storage_by_key_hash(storage_key).await
+    }
+
+    async fn fetch_opaque_storage_map<K>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        key: K,
+    ) -> Result<Option<Vec<u8>>, Error>
+    where
+        K: Encode + MaybeSendSync,
+    {
+        let storage_key =
+            self.metadata().storage_map_key(module, storage_name, key)?;
+        self.fetch_opaque_storage_by_key_hash(storage_key).await
+    }
+
+    async fn fetch_storage_double_map<K, Q, V>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        first: K,
+        second: Q,
+    ) -> Result<Option<V>, Error>
+    where
+        K: Encode + MaybeSendSync,
+        Q: Encode + MaybeSendSync,
+        V: Decode,
+    {
+        let storage_key = self.metadata().storage_double_map_key(
+            module,
+            storage_name,
+            first,
+            second,
+        )?;
+        self.fetch_storage_by_key_hash(storage_key).await
+    }
+
+    async fn fetch_opaque_storage_double_map<K, Q>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        first: K,
+        second: Q,
+    ) -> Result<Option<Vec<u8>>, Error>
+    where
+        K: Encode + MaybeSendSync,
+        Q: Encode + MaybeSendSync,
+    {
+        let storage_key = self.metadata().storage_double_map_key(
+            module,
+            storage_name,
+            first,
+            second,
+        )?;
+        self.fetch_opaque_storage_by_key_hash(storage_key).await
+    }
+
+    /// fetch the value of an n-map entry, `keys` is a tuple with one key per hasher
+    async fn fetch_storage_nmap<K, V>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        keys: K,
+    ) -> Result<Option<V>, Error>
+    where
+        K: StorageKeys + MaybeSendSync,
+        V: Decode,
+    {
+        let storage_key =
+            self.metadata()
+                .storage_nmap_key(module, storage_name, &keys)?;
+        self.fetch_storage_by_key_hash(storage_key).await
+    }
+
+    async fn fetch_opaque_storage_nmap<K>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        keys: K,
+    ) -> Result<Option<Vec<u8>>, Error>
+    where
+        K: StorageKeys + MaybeSendSync,
+    {
+        let storage_key =
+            self.metadata()
+                .storage_nmap_key(module, storage_name, &keys)?;
+        self.fetch_opaque_storage_by_key_hash(storage_key).await
+    }
+
+    /// fetch the value at `storage_key` of the storage entry `module.storage_name`,
+    /// falling back to the default of the entry in the metadata when it was never written,
+    /// unlike the `fetch_storage_*` methods which return the value as it is stored.
+    /// The entries with the `Default` modifier (`ValueQuery`) always have a value,
+    /// the `Optional` entries (`OptionQuery`) are `None` when absent.
+    /// The `storage_key` must be a key of the entry, or the default of the entry would be
+    /// returned as the value of an unrelated key.
+    async fn fetch_or_default<V>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        storage_key: StorageKey,
+    ) -> Result<Option<V>, Error>
+    where
+        V: Decode,
+    {
+        storage::strip_prefix(
+            &storage_key,
+            module.as_bytes(),
+            storage_name.as_bytes(),
+        )?;
+        match self.fetch_raw(storage_key).await? {
+            Some(value) => Ok(Some(value)),
+            None => {
+                Ok(self
+                    .metadata()
+                    .storage_default_value(module, storage_name)?)
+            }
+        }
+    }
+
+    /// fetch the value at `storage_key` as it is stored,
+    /// `None` if it was never written regardless of the default of the entry
+    async fn fetch_raw<V>(
+        &self,
+        storage_key: StorageKey,
+    ) -> Result<Option<V>, Error>
+    where
+        V: Decode,
+    {
+        match self.fetch_opaque_storage_by_key_hash(storage_key).await? {
+            Some(storage) => Ok(Some(Decode::decode(&mut storage.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// same as [`StorageBackend::fetch_raw`]
+    async fn fetch_storage_by_key_hash<V>(
+        &self,
+        storage_key: StorageKey,
+    ) -> Result<Option<V>, Error>
+    where
+        V: Decode,
+    {
+        self.fetch_raw(storage_key).await
+    }
+
+    /// fetch and decode the values of the `storage_keys`,
+    /// the values are returned in the same order as the keys
+    async fn fetch_storage_many<V>(
+        &self,
+        storage_keys: &[StorageKey],
+    ) -> Result<Vec<Option<V>>, Error>
+    where
+        V: Decode,
+    {
+        self.fetch_opaque_storage_many(storage_keys)
+            .await?
+            .into_iter()
+            .map(|bytes| {
+                bytes
+                    .map(|bytes| V::decode(&mut bytes.as_slice()))
+                    .transpose()
+                    .map_err(Error::from)
+            })
+            .collect()
+    }
+
+    async fn fetch_opaque_storage_map_paged<K>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        count: u32,
+        start_key: Option<K>,
+    ) -> Result<Option<Vec<Vec<u8>>>, Error>
+    where
+        K: Encode + MaybeSendSync,
+    {
+        let storage_keys = self
+            .fetch_opaque_storage_keys_paged(
+                module,
+                storage_name,
+                count,
+                start_key,
+            )
+            .await?;
+        match storage_keys {
+            Some(storage_keys) => {
+                // skip the keys which have no value
+                let storage_values = self
+                    .fetch_opaque_storage_many(&storage_keys)
+                    .await?
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                Ok(Some(storage_values))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn fetch_opaque_storage_keys_paged<K>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        count: u32,
+        start_key: Option<K>,
+    ) -> Result<Option<Vec<StorageKey>>, Error>
+    where
+        K: Encode + MaybeSendSync,
+    {
+        let storage_key = self
+            .metadata()
+            .storage_map_key_prefix(module, storage_name)?;
+        let start_storage_key = match start_key {
+            Some(start_key) => {
+                Some(self.metadata().storage_map_key(
+                    module,
+                    storage_name,
+                    start_key,
+                )?)
+            }
+            None => None,
+        };
+        self.fetch_storage_keys_paged_by_prefix(
+            storage_key,
+            count,
+            start_storage_key,
+        )
+        .await
+    }
+
+    async fn get_account_info(
+        &self,
+        account_id: &AccountId32,
+    ) -> Result<Option<AccountInfo>, Error> {
+        self.fetch_storage_map("System", "Account", account_id)
+            .await
+    }
+}
+
+/// The storage of the node at the block `at`, or at the best block if `at` is `None`,
+/// the methods of [`Api`] with an `at` argument read through it.
+pub(crate) struct StorageAt<'a> {
+    pub(crate) api: &'a Api,
+    pub(crate) at: Option<H256>,
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl StorageBackend for StorageAt<'_> {
+    fn metadata(&self) -> &Metadata {
+        &self.api.metadata
+    }
+
+    async fn fetch_opaque_storage_by_key_hash(
+        &self,
+        storage_key: StorageKey,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.api
+            .fetch_opaque_storage_by_key_hash(storage_key, self.at)
+            .await
+    }
+
+    async fn fetch_opaque_storage_many(
+        &self,
+        storage_keys: &[StorageKey],
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        self.api
+            .fetch_opaque_storage_many(storage_keys, self.at)
+            .await
+    }
+
+    async fn fetch_storage_keys_paged_by_prefix(
+        &self,
+        prefix: StorageKey,
+        count: u32,
+        start_key: Option<StorageKey>,
+    ) -> Result<Option<Vec<StorageKey>>, Error> {
+        self.api
+            .fetch_storage_keys_paged_by_prefix(
+                prefix, count, start_key, self.at,
+            )
+            .await
+    }
+}
+
+/// Reads at the best block
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl StorageBackend for Api {
+    fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    async fn fetch_opaque_storage_by_key_hash(
+        &self,
+        storage_key: StorageKey,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.fetch_opaque_storage_by_key_hash(storage_key, None)
+            .await
+    }
+
+    async fn fetch_opaque_storage_many(
+        &self,
+        storage_keys: &[StorageKey],
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        self.fetch_opaque_storage_many(storage_keys, None).await
+    }
+
+    async fn fetch_storage_keys_paged_by_prefix(
+        &self,
+        prefix: StorageKey,
+        count: u32,
+        start_key: Option<StorageKey>,
+    ) -> Result<Option<Vec<StorageKey>>, Error> {
+        self.fetch_storage_keys_paged_by_prefix(prefix, count, start_key, None)
+            .await
+    }
+}
@@ -0,0 +1,48 @@
+//! Compare the storage of a pallet between two blocks
+use crate::{
+    api::Api,
+    error::Error,
+    types::storage_diff::StorageDiff,
+};
+use sp_core::{
+    storage::StorageKey,
+    H256,
+};
+use std::collections::BTreeMap;
+
+impl Api {
+    /// The keys of the pallet which were added, removed or modified from block `from`
+    /// to block `to`, with their values before and after.
+    ///
+    /// All the storage items of the pallet are read at both blocks, the keys and values are
+    /// decoded with the metadata of the runtime of each block where possible.
+    pub async fn storage_diff(
+        &self,
+        pallet: &str,
+        from: H256,
+        to: H256,
+    ) -> Result<StorageDiff, Error> {
+        let from_block = self.at(from).await?;
+        let to_block = self.at(to).await?;
+        // check the pallet exists in at least one of the blocks
+        if from_block.metadata().pallet(pallet).is_err() {
+            to_block.metadata().pallet(pallet)?;
+        }
+        let prefix = StorageKey(sp_core::twox_128(pallet.as_bytes()).to_vec());
+        let before: BTreeMap<Vec<u8>, Vec<u8>> = self
+            .fetch_opaque_storage_entries(prefix.clone(), from)
+            .await?
+            .into_iter()
+            .collect();
+        let after: BTreeMap<Vec<u8>, Vec<u8>> = self
+            .fetch_opaque_storage_entries(prefix, to)
+            .await?
+            .into_iter()
+            .collect();
+        Ok(StorageDiff::new(
+            pallet,
+            (from, from_block.metadata(), before),
+            (to, to_block.metadata(), after),
+        ))
+    }
+}
@@ -0,0 +1,112 @@
+//! Read storage values along with a proof, verified locally against the block header
+use crate::{
+    api::Api,
+    error::Error,
+    types::storage_proof::verify_read_proof,
+    utils::FromHexStr,
+};
+use codec::Decode;
+use sp_core::{
+    storage::StorageKey,
+    H256,
+};
+use sp_runtime::{
+    generic,
+    traits::{
+        BlakeTwo256,
+        Header,
+    },
+};
+
+impl Api {
+    /// Fetch the values of the `storage_keys` at block `at` with `state_getReadProof` and
+    /// verify the proof against the `state_root` of the block header.
+    /// The values are returned in the same order as the keys, only if the proof checks out.
+    ///
+    /// The header is checked to hash to the block hash, so the node can not forge the values
+    /// of a block whose hash is known. Pass a hash obtained from a trusted source as `at`;
+    /// if `at` is `None` the finalized head reported by the node is used, which is only
+    /// as trustworthy as the node itself.
+    pub async fn fetch_opaque_storage_with_proof(
+        &self,
+        storage_keys: &[StorageKey],
+        at: Option<H256>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        // pin the block before asking for the proof,
+        // so the node can not pick the block of the proof
+        let at = match at {
+            Some(at) => at,
+            None => {
+                self.chain_get_finalized_head()
+                    .await?
+                    .ok_or(Error::NoFinalizedHead)?
+            }
+        };
+        let value = self
+            .base_api
+            .json_request_value("state_getReadProof", (storage_keys, at))
+            .await?
+            .ok_or_else(|| {
+                Error::EmptyResponse("state_getReadProof".to_string())
+            })?;
+        let invalid = |field: &str| {
+            Error::InvalidStorageProof(format!(
+                "`{}` is missing or malformed in the response: {}",
+                field, value
+            ))
+        };
+        let block_hash =
+            H256::from_hex(value["at"].as_str().ok_or_else(|| invalid("at"))?)?;
+        if at != block_hash {
+            return Err(Error::InvalidStorageProof(format!(
+                "requested block {:?} but the proof is for {:?}",
+                at, block_hash
+            )));
+        }
+        let proof = value["proof"]
+            .as_array()
+            .ok_or_else(|| invalid("proof"))?
+            .iter()
+            .map(|node| {
+                let node = node.as_str().ok_or_else(|| invalid("proof"))?;
+                Ok(Vec::from_hex(node)?)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let header: generic::Header<u64, BlakeTwo256> =
+            self.chain_get_header(block_hash).await?.ok_or_else(|| {
+                Error::EmptyResponse(format!(
+                    "chain_getHeader({:?})",
+                    block_hash
+                ))
+            })?;
+        if header.hash() != block_hash {
+            return Err(Error::InvalidStorageProof(format!(
+                "the header does not hash to {:?}",
+                block_hash
+            )));
+        }
+        verify_read_proof(header.state_root, proof, storage_keys)
+    }
+
+    /// Same as [`Api::fetch_opaque_storage_with_proof`] with the values decoded into `V`
+    pub async fn fetch_storage_with_proof<V>(
+        &self,
+        storage_keys: &[StorageKey],
+        at: Option<H256>,
+    ) -> Result<Vec<Option<V>>, Error>
+    where
+        V: Decode,
+    {
+        self.fetch_opaque_storage_with_proof(storage_keys, at)
+            .await?
+            .into_iter()
+            .map(|bytes| {
+                bytes
+                    .map(|bytes| V::decode(&mut bytes.as_slice()))
+                    .transpose()
+                    .map_err(Error::from)
+            })
+            .collect()
+    }
+}
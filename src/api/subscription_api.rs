@@ -0,0 +1,217 @@
+//! Subscribe to the changes of storage values
+use crate::{
+    api::{
+        query_storage_api::parse_change_set,
+        Api,
+    },
+    error::Error,
+    types::storage::{
+        StorageChange,
+        StorageChangeSet,
+    },
+    utils,
+};
+use codec::Decode;
+use futures::{
+    stream::{
+        self,
+        LocalBoxStream,
+    },
+    Stream,
+    StreamExt,
+};
+use sp_core::{
+    storage::StorageKey,
+    H256,
+};
+use std::{
+    collections::HashMap,
+    time::Duration,
+};
+
+/// How often the finalized head is polled when there is no WebSocket endpoint
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// The position of a polling subscription
+struct PollState {
+    storage_keys: Vec<StorageKey>,
+    /// the last finalized block which was compared
+    last_block: Option<H256>,
+    last_values: HashMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl Api {
+    /// Subscribe to the changes of the values at `storage_keys`,
+    /// each item has the changes of one block.
+    /// The first change set has the current values of all the keys.
+    ///
+    /// This uses `state_subscribeStorage` when a WebSocket endpoint is set with
+    /// [`Api::set_ws_url`], which notifies the changes of the best blocks.
+    /// Otherwise, and always in wasm, it falls back to [`Api::poll_storage`].
+    pub async fn subscribe_storage<'a, V>(
+        &'a self,
+        storage_keys: &[StorageKey],
+    ) -> Result<LocalBoxStream<'a, Result<StorageChangeSet<V>, Error>>, Error>
+    where
+        V: Decode + 'a,
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(ws_url) = &self.ws_url {
+            let change_sets = ws::subscribe_storage(ws_url, storage_keys)
+                .await?
+                .map(|change_set| decode_change_set(change_set?));
+            return Ok(change_sets.boxed_local());
+        }
+        #[cfg(target_arch = "wasm32")]
+        if self.ws_url.is_some() {
+            log::debug!("no WebSocket subscriptions in wasm, polling instead");
+        }
+        Ok(self.poll_storage(storage_keys, POLL_INTERVAL).boxed_local())
+    }
+
+    /// Poll the values at `storage_keys` every `interval` over http, yielding the values which
+    /// differ from the previous finalized block that was polled.
+    /// The changes in between two polls are merged into the change set of the later block.
+    pub fn poll_storage<'a, V>(
+        &'a self,
+        storage_keys: &[StorageKey],
+        interval: Duration,
+    ) -> impl Stream<Item = Result<StorageChangeSet<V>, Error>> + 'a
+    where
+        V: Decode + 'a,
+    {
+        let state = PollState {
+            storage_keys: storage_keys.to_vec(),
+            last_block: None,
+            last_values: HashMap::new(),
+        };
+        stream::try_unfold(state, move |mut state| {
+            async move {
+                loop {
+                    if state.last_block.is_some() {
+                        utils::sleep(interval).await;
+                    }
+                    let change_set = self.poll_changes(&mut state).await?;
+                    if !change_set.is_empty() {
+                        return Ok(Some((
+                            decode_change_set(change_set)?,
+                            state,
+                        )));
+                    }
+                }
+            }
+        })
+    }
+
+    /// compare the values at the latest finalized block with the last polled values
+    async fn poll_changes(
+        &self,
+        state: &mut PollState,
+    ) -> Result<StorageChangeSet<Vec<u8>>, Error> {
+        let block_hash = self
+            .chain_get_finalized_head()
+            .await?
+            .ok_or(Error::NoFinalizedHead)?;
+        if state.last_block == Some(block_hash) {
+            return Ok(vec![]);
+        }
+        let values = self
+            .fetch_opaque_storage_many(&state.storage_keys, Some(block_hash))
+            .await?;
+        let first_poll = state.last_block.is_none();
+        state.last_block = Some(block_hash);
+
+        let mut changes = vec![];
+        for (storage_key, value) in state.storage_keys.iter().zip(values) {
+            let last = state.last_values.get(&storage_key.0);
+            if first_poll || last != Some(&value) {
+                state
+                    .last_values
+                    .insert(storage_key.0.clone(), value.clone());
+                changes.push(StorageChange {
+                    block_hash,
+                    key: storage_key.clone(),
+                    value,
+                });
+            }
+        }
+        Ok(changes)
+    }
+}
+
+fn decode_change_set<V: Decode>(
+    change_set: StorageChangeSet<Vec<u8>>,
+) -> Result<StorageChangeSet<V>, Error> {
+    change_set
+        .into_iter()
+        .map(|change| {
+            change.try_map(|bytes| {
+                V::decode(&mut bytes.as_slice()).map_err(Error::from)
+            })
+        })
+        .collect()
+}
+
+/// `state_subscribeStorage` over a WebSocket connection
+#[cfg(not(target_arch = "wasm32"))]
+mod ws {
+    use super::*;
+    use futures::{
+        future,
+        SinkExt,
+    };
+    use serde_json::json;
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// the raw change sets notified by the node,
+    /// the subscription ends when the stream is dropped and the connection closed
+    pub(super) async fn subscribe_storage(
+        ws_url: &str,
+        storage_keys: &[StorageKey],
+    ) -> Result<
+        impl Stream<Item = Result<StorageChangeSet<Vec<u8>>, Error>>,
+        Error,
+    > {
+        let (mut socket, _response) = tokio_tungstenite::connect_async(ws_url)
+            .await
+            .map_err(|e| Error::WebSocketError(e.to_string()))?;
+        let request = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "method": "state_subscribeStorage",
+            "params": [storage_keys],
+        });
+        socket
+            .send(Message::Text(request.to_string()))
+            .await
+            .map_err(|e| Error::WebSocketError(e.to_string()))?;
+
+        let change_sets = socket.filter_map(|message| {
+            future::ready(match message {
+                Ok(Message::Text(text)) => parse_notification(&text),
+                Ok(_) => None,
+                Err(e) => Some(Err(Error::WebSocketError(e.to_string()))),
+            })
+        });
+        Ok(change_sets)
+    }
+
+    /// the change set of a `state_storage` notification,
+    /// `None` for the other messages such as the response with the subscription id
+    fn parse_notification(
+        text: &str,
+    ) -> Option<Result<StorageChangeSet<Vec<u8>>, Error>> {
+        let message: serde_json::Value = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(e) => return Some(Err(e.into())),
+        };
+        if let Some(error) = message.get("error") {
+            return Some(Err(Error::ResponseJsonError(error.clone())));
+        }
+        if message["method"] == "state_storage" {
+            Some(parse_change_set(&message["params"]["result"]))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Check whether an extrinsic would be accepted before broadcasting it
+use crate::{
+    api::Api,
+    error::Error,
+    types::{
+        extrinsics::UncheckedExtrinsicV4,
+        validity::DryRunResult,
+    },
+};
+use codec::{
+    Decode,
+    Encode,
+};
+use sp_runtime::{
+    transaction_validity::{
+        TransactionSource,
+        TransactionValidity,
+    },
+    ApplyExtrinsicResult,
+};
+
+impl Api {
+    /// Validate the extrinsic on top of the best block, as the transaction pool would
+    /// when receiving it from an external source.
+    ///
+    /// Returns the priority, the requires/provides tags and the longevity of a valid
+    /// transaction, or the reason it is invalid, ie: a stale nonce or an unpaid fee.
+    pub async fn validate<Call, Extra>(
+        &self,
+        xt: &UncheckedExtrinsicV4<Call, Extra>,
+    ) -> Result<TransactionValidity, Error>
+    where
+        Call: Encode,
+        Extra: Encode,
+    {
+        let best_block = self
+            .base_api
+            .fetch_best_block_hash()
+            .await?
+            .ok_or(Error::NoBestBlock)?;
+        self.runtime_api_validate_transaction(
+            TransactionSource::External,
+            &xt.encode(),
+            best_block,
+        )
+        .await?
+        .ok_or_else(|| {
+            Error::EmptyResponse(
+                "TaggedTransactionQueue_validate_transaction".to_string(),
+            )
+        })
+    }
+
+    /// Apply the extrinsic on top of the best block without committing it,
+    /// the errors of the pallets are resolved through the metadata.
+    ///
+    /// This uses the `system_dryRun` rpc which is only allowed on local nodes
+    /// or nodes started with `--rpc-methods unsafe`.
+    pub async fn dry_run<Call, Extra>(
+        &self,
+        xt: &UncheckedExtrinsicV4<Call, Extra>,
+    ) -> Result<DryRunResult, Error>
+    where
+        Call: Encode,
+        Extra: Encode,
+    {
+        let bytes = self
+            .base_api
+            .system_dry_run(&xt.hex_encode(), None)
+            .await?
+            .ok_or_else(|| Error::EmptyResponse("system_dryRun".to_string()))?;
+        let result = ApplyExtrinsicResult::decode(&mut bytes.as_slice())?;
+        Ok(DryRunResult::from_apply_result(result, self.metadata()))
+    }
+}
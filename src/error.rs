@@ -16,10 +16,52 @@ pub enum Error {
     NoGenesisHash,
     #[error("Unable to get chain Runtime version")]
     NoRuntimeVersion,
+    #[error("Unable to get the latest finalized block")]
+    NoFinalizedHead,
+    #[error("Unable to get the fee of the extrinsic")]
+    NoFeeInfo,
+    #[error("Unable to get the best block")]
+    NoBestBlock,
+    #[error("The node returned no result for `{0}`")]
+    EmptyResponse(String),
+    #[error("Unexpected response of `{0}`: {1}")]
+    UnexpectedResponse(String, serde_json::Value),
     #[error("Metadata error: {0}")]
     MetadataError(#[from] metadata::MetadataError),
     #[error("Codec error: {0}")]
     CodecError(#[from] codec::Error),
     #[error("Error response: {0}")]
     ResponseJsonError(serde_json::Value),
+    #[error("Signer error: {0}")]
+    SignerError(String),
+    #[error("Invalid transaction document: {0}")]
+    InvalidTransactionDocument(String),
+    #[error("Invalid storage proof: {0}")]
+    InvalidStorageProof(String),
+    #[error("WebSocket error: {0}")]
+    WebSocketError(String),
+    #[error("Invalid storage snapshot: {0}")]
+    InvalidSnapshot(String),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+impl Error {
+    /// Returns true when the node rejected a transaction because of its nonce, ie: the nonce
+    /// is outdated (`Stale`), ahead of the account nonce (`Future`) or already used by
+    /// another transaction in the pool
+    pub fn is_nonce_error(&self) -> bool {
+        match self {
+            Error::ResponseJsonError(error) => {
+                let data = error
+                    .get("data")
+                    .and_then(|data| data.as_str())
+                    .unwrap_or_default();
+                data.contains("outdated")
+                    || data.contains("in the future")
+                    || data.contains("Priority is too low")
+            }
+            _ => false,
+        }
+    }
 }
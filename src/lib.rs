@@ -2,9 +2,16 @@
 
 pub use api::{
     Api,
+    AtBlock,
     BaseApi,
+    CacheConfig,
+    CacheStats,
+    NonceManager,
+    ReadCache,
+    StorageBackend,
 };
 pub use error::Error;
+pub use signer::Signer;
 pub use types::metadata::Metadata;
 
 // reexport dependencies crates
@@ -16,5 +23,6 @@ pub use sp_version;
 
 mod api;
 mod error;
+pub mod signer;
 pub mod types;
 pub mod utils;
@@ -0,0 +1,186 @@
+//! Signing of transaction payloads and messages.
+//!
+//! The [`Signer`] trait only exposes the account id and a way to sign a payload,
+//! so the private keys don't have to live in this process. Remote signers, key management
+//! services or browser wallets can implement it, while the sr25519, ed25519 and ecdsa
+//! key pairs of `sp_core` are supported out of the box.
+use crate::{
+    error::Error,
+    types::signed_extensions::ExtensionParams,
+};
+use async_trait::async_trait;
+use codec::Encode;
+use serde_json::json;
+use sp_core::{
+    crypto::{
+        AccountId32,
+        Pair,
+        Ss58Codec,
+    },
+    ecdsa,
+    ed25519,
+    sr25519,
+};
+use sp_runtime::{
+    traits::IdentifyAccount,
+    MultiSignature,
+    MultiSigner,
+};
+
+#[cfg(target_arch = "wasm32")]
+pub use js::{
+    JsSigner,
+    KeyType,
+};
+
+#[cfg(target_arch = "wasm32")]
+mod js;
+
+/// `Send + Sync` on native targets, the javascript values used by signers in the browser
+/// can not be shared across threads so this has no bounds in wasm.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSendSync: Send + Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + Sync> MaybeSendSync for T {}
+
+/// `Send + Sync` on native targets, the javascript values used by signers in the browser
+/// can not be shared across threads so this has no bounds in wasm.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSendSync {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSendSync for T {}
+
+/// Signs the payload of transactions on behalf of an account
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait Signer: MaybeSendSync {
+    /// the account which signs the payload, this is also the sender of the transactions
+    fn account_id(&self) -> AccountId32;
+
+    /// sign the payload, the payload of transactions longer than 256 bytes
+    /// are already hashed before being passed here
+    async fn sign(&self, payload: &[u8]) -> Result<MultiSignature, Error>;
+
+    /// sign the payload of a transaction, wallets which needs to display the details of the
+    /// transaction to the user can make use of the fields of the [`SignerPayload`].
+    /// By default, this signs the encoded payload.
+    async fn sign_payload(
+        &self,
+        payload: &SignerPayload,
+    ) -> Result<MultiSignature, Error> {
+        self.sign(&payload.encoded).await
+    }
+}
+
+/// The transaction details which are signed, along with the encoded payload
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignerPayload {
+    /// the account signing the transaction
+    pub address: AccountId32,
+    /// the encoded call
+    pub method: Vec<u8>,
+    /// the values of the signed extensions
+    pub params: ExtensionParams,
+    /// the block number of the mortality checkpoint, this is only used by wallets
+    /// to display from which block a mortal transaction is valid
+    pub block_number: u32,
+    /// the identifiers of the signed extensions of the chain, in the order of the metadata
+    pub signed_extensions: Vec<String>,
+    /// the extrinsic version
+    pub version: u8,
+    /// the payload to be signed,
+    /// which is already hashed when the payload is longer than 256 bytes
+    pub encoded: Vec<u8>,
+}
+
+impl SignerPayload {
+    /// The payload in the `SignerPayloadJSON` format used by the polkadot-js extension
+    pub fn to_json(&self) -> serde_json::Value {
+        let params = &self.params;
+        let mut payload = json!({
+            "address": self.address.to_ss58check(),
+            "blockHash": format!("{:?}", params.mortality_checkpoint),
+            "blockNumber": format!("0x{:08x}", self.block_number),
+            "era": format!("0x{}", hex::encode(params.era.encode())),
+            "genesisHash": format!("{:?}", params.genesis_hash),
+            "method": format!("0x{}", hex::encode(&self.method)),
+            "nonce": format!("0x{:08x}", params.nonce),
+            "signedExtensions": self.signed_extensions,
+            "specVersion": format!("0x{:08x}", params.spec_version),
+            "tip": format!("0x{:032x}", params.tip),
+            "transactionVersion": format!("0x{:08x}", params.transaction_version),
+            "version": self.version,
+        });
+        if let Some(asset) = params.tip_asset {
+            payload["assetId"] = json!(asset);
+        }
+        if let Some(metadata_hash) = params.metadata_hash {
+            payload["mode"] = json!(1);
+            payload["metadataHash"] =
+                json!(format!("0x{}", hex::encode(metadata_hash)));
+        }
+        payload
+    }
+}
+
+macro_rules! impl_signer_for_pair {
+    ($($crypto:ident),*) => {
+        $(
+            #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+            #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+            impl Signer for $crypto::Pair {
+                fn account_id(&self) -> AccountId32 {
+                    MultiSigner::from(self.public()).into_account()
+                }
+
+                async fn sign(
+                    &self,
+                    payload: &[u8],
+                ) -> Result<MultiSignature, Error> {
+                    Ok(MultiSignature::from(Pair::sign(self, payload)))
+                }
+            }
+        )*
+    };
+}
+
+impl_signer_for_pair!(sr25519, ed25519, ecdsa);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::H256;
+    use sp_runtime::generic::Era;
+
+    #[test]
+    fn test_signer_payload_json() {
+        let payload = SignerPayload {
+            address: AccountId32::new([1; 32]),
+            method: vec![8, 0, 1],
+            params: ExtensionParams {
+                spec_version: 100,
+                transaction_version: 1,
+                genesis_hash: H256::repeat_byte(2),
+                mortality_checkpoint: H256::repeat_byte(2),
+                era: Era::immortal(),
+                nonce: 5,
+                tip: 10,
+                tip_asset: None,
+                metadata_hash: None,
+            },
+            block_number: 0,
+            signed_extensions: vec!["CheckNonce".to_string()],
+            version: 4,
+            encoded: vec![],
+        };
+        let json = payload.to_json();
+        assert_eq!(json["method"], "0x080001");
+        assert_eq!(json["era"], "0x00");
+        assert_eq!(json["nonce"], "0x00000005");
+        assert_eq!(json["specVersion"], "0x00000064");
+        assert_eq!(json["tip"], "0x0000000000000000000000000000000a");
+        assert_eq!(json["blockHash"], format!("0x{}", "02".repeat(32)));
+        assert_eq!(json["signedExtensions"][0], "CheckNonce");
+        assert!(json.get("assetId").is_none());
+    }
+}
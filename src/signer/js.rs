@@ -0,0 +1,220 @@
+//! A [`Signer`] which asks a browser wallet for the signatures,
+//! such as the `signer` injected by the polkadot-js extension.
+use super::{
+    Signer,
+    SignerPayload,
+};
+use crate::error::Error;
+use async_trait::async_trait;
+use codec::Decode;
+use js_sys::{
+    Function,
+    Promise,
+    Reflect,
+    JSON,
+};
+use serde_json::json;
+use sp_core::{
+    crypto::{
+        AccountId32,
+        Ss58Codec,
+    },
+    ecdsa,
+    ed25519,
+    sr25519,
+};
+use sp_runtime::MultiSignature;
+use std::{
+    convert::TryFrom,
+    str::FromStr,
+};
+use wasm_bindgen::{
+    JsCast,
+    JsValue,
+};
+use wasm_bindgen_futures::JsFuture;
+
+/// The crypto of the account, used to interpret the signatures
+/// which are returned without the type prefix
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyType {
+    Sr25519,
+    Ed25519,
+    Ecdsa,
+}
+
+impl FromStr for KeyType {
+    type Err = Error;
+
+    /// The `type` of an account injected by the polkadot-js extension,
+    /// ie: `sr25519`, `ed25519` or `ecdsa`
+    fn from_str(key_type: &str) -> Result<Self, Error> {
+        match key_type {
+            "sr25519" => Ok(KeyType::Sr25519),
+            "ed25519" => Ok(KeyType::Ed25519),
+            "ecdsa" => Ok(KeyType::Ecdsa),
+            _ => {
+                Err(Error::SignerError(format!(
+                    "unsupported key type: {}",
+                    key_type
+                )))
+            }
+        }
+    }
+}
+
+/// Signs the transactions by calling the `signPayload` and `signRaw` functions
+/// of a javascript object, following the shape of the polkadot-js extension signer.
+///
+/// Both functions are called with a json request and must return, or resolve to,
+/// an object with a hex encoded `signature`.
+pub struct JsSigner {
+    account_id: AccountId32,
+    address: String,
+    key_type: KeyType,
+    signer: JsValue,
+}
+
+impl JsSigner {
+    /// `address` is the ss58 address of the account in the wallet
+    pub fn new(
+        address: &str,
+        key_type: KeyType,
+        signer: JsValue,
+    ) -> Result<Self, Error> {
+        let account_id = AccountId32::from_ss58check(address).map_err(|e| {
+            Error::SignerError(format!("invalid address {}: {:?}", address, e))
+        })?;
+        Ok(Self {
+            account_id,
+            address: address.to_string(),
+            key_type,
+            signer,
+        })
+    }
+
+    /// Use an account injected by the polkadot-js extension, ie: an item of
+    /// `injected.accounts.get()`, with its `address` and key `type`.
+    /// The `type` is optional in the injected accounts, it defaults to `sr25519`.
+    pub fn from_injected_account(
+        account: &JsValue,
+        signer: JsValue,
+    ) -> Result<Self, Error> {
+        let address = Reflect::get(account, &JsValue::from_str("address"))
+            .map_err(js_error)?
+            .as_string()
+            .ok_or_else(|| {
+                Error::SignerError("the account has no address".to_string())
+            })?;
+        let key_type = match Reflect::get(account, &JsValue::from_str("type"))
+            .map_err(js_error)?
+            .as_string()
+        {
+            Some(key_type) => key_type.parse()?,
+            None => KeyType::Sr25519,
+        };
+        Self::new(&address, key_type, signer)
+    }
+
+    /// call the `function` of the signer with the `request` and return the signature bytes
+    async fn call(
+        &self,
+        function: &str,
+        request: &serde_json::Value,
+    ) -> Result<Vec<u8>, Error> {
+        let func: Function =
+            Reflect::get(&self.signer, &JsValue::from_str(function))
+                .map_err(js_error)?
+                .dyn_into()
+                .map_err(|_| {
+                    Error::SignerError(format!(
+                        "signer has no `{}` function",
+                        function
+                    ))
+                })?;
+        let request = JSON::parse(&request.to_string()).map_err(js_error)?;
+        let result = func.call1(&self.signer, &request).map_err(js_error)?;
+        let result = JsFuture::from(Promise::resolve(&result))
+            .await
+            .map_err(js_error)?;
+        let signature = Reflect::get(&result, &JsValue::from_str("signature"))
+            .map_err(js_error)?
+            .as_string()
+            .ok_or_else(|| {
+                Error::SignerError(format!(
+                    "`{}` returned no signature",
+                    function
+                ))
+            })?;
+        Ok(hex::decode(signature.trim_start_matches("0x"))?)
+    }
+
+    /// The polkadot-js extension returns the signature of `signPayload` prefixed with the crypto type,
+    /// while the signature of `signRaw` is returned as is.
+    fn to_multi_signature(
+        &self,
+        signature: &[u8],
+    ) -> Result<MultiSignature, Error> {
+        let raw_len = match self.key_type {
+            KeyType::Ecdsa => 65,
+            KeyType::Sr25519 | KeyType::Ed25519 => 64,
+        };
+        if signature.len() != raw_len {
+            return Ok(MultiSignature::decode(&mut &signature[..])?);
+        }
+        let invalid =
+            |_| Error::SignerError("invalid signature length".to_string());
+        let multi_signature = match self.key_type {
+            KeyType::Sr25519 => {
+                sr25519::Signature::try_from(signature)
+                    .map_err(invalid)?
+                    .into()
+            }
+            KeyType::Ed25519 => {
+                ed25519::Signature::try_from(signature)
+                    .map_err(invalid)?
+                    .into()
+            }
+            KeyType::Ecdsa => {
+                ecdsa::Signature::try_from(signature)
+                    .map_err(invalid)?
+                    .into()
+            }
+        };
+        Ok(multi_signature)
+    }
+}
+
+#[async_trait(?Send)]
+impl Signer for JsSigner {
+    fn account_id(&self) -> AccountId32 {
+        self.account_id.clone()
+    }
+
+    /// Sign using `signRaw`, note that the polkadot-js extension wraps the data in `<Bytes>`
+    /// tags before signing, so this is only meant for messages and not for transactions.
+    async fn sign(&self, payload: &[u8]) -> Result<MultiSignature, Error> {
+        let request = json!({
+            "address": self.address,
+            "data": format!("0x{}", hex::encode(payload)),
+            "type": "bytes",
+        });
+        let signature = self.call("signRaw", &request).await?;
+        self.to_multi_signature(&signature)
+    }
+
+    async fn sign_payload(
+        &self,
+        payload: &SignerPayload,
+    ) -> Result<MultiSignature, Error> {
+        let mut request = payload.to_json();
+        // use the address as it is known by the wallet
+        request["address"] = json!(self.address);
+        let signature = self.call("signPayload", &request).await?;
+        self.to_multi_signature(&signature)
+    }
+}
+
+fn js_error(value: JsValue) -> Error {
+    Error::SignerError(format!("{:?}", value))
+}
@@ -1,5 +1,15 @@
 pub mod account_info;
+pub mod child_storage;
+pub mod dynamic;
+pub mod explorer;
 pub mod extrinsic_params;
 pub mod extrinsics;
+pub mod fee;
 pub mod metadata;
+pub mod signed_extensions;
+pub mod snapshot;
 pub mod storage;
+pub mod storage_diff;
+pub mod storage_proof;
+pub mod unsigned_transaction;
+pub mod validity;
@@ -0,0 +1,57 @@
+//! For reading the child tries, ie: the storage of the smart contracts.
+
+use codec::Decode;
+use sp_core::storage::{
+    ChildInfo,
+    StorageKey,
+};
+
+/// The contract info of `pallet_contracts`, only the trie id is needed to read its storage,
+/// the fields after it are not decoded
+#[derive(Clone, Debug, PartialEq, Eq, Decode)]
+pub struct ContractTrieId {
+    pub trie_id: Vec<u8>,
+}
+
+impl ContractTrieId {
+    /// The child trie holding the storage of the contract
+    pub fn child_info(&self) -> ChildInfo {
+        ChildInfo::new_default(&self.trie_id)
+    }
+}
+
+/// The key in the contract child trie of the 32 bytes storage `key` used by the contract
+pub fn contract_storage_key(key: &[u8; 32]) -> StorageKey {
+    StorageKey(sp_core::blake2_256(key).to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::Encode;
+
+    #[test]
+    fn test_child_info() {
+        // the fields after the trie id are not decoded
+        let encoded = (b"trie".to_vec(), 42u32).encode();
+        let trie_id = ContractTrieId::decode(&mut encoded.as_slice()).unwrap();
+        assert_eq!(trie_id.trie_id, b"trie".to_vec());
+        assert_eq!(
+            trie_id.child_info().prefixed_storage_key().into_inner(),
+            b":child_storage:default:trie".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_contract_storage_key() {
+        assert_eq!(
+            contract_storage_key(&[0; 32]),
+            StorageKey(
+                hex::decode(
+                    "89eb0d6a8a691dae2cd15ed0369931ce0a949ecafa5c3f93f8121833646e15c3"
+                )
+                .unwrap()
+            )
+        );
+    }
+}
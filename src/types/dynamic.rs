@@ -0,0 +1,500 @@
+//! Decode scale encoded values without knowing their rust type,
+//! using the type information in the metadata.
+//!
+//! This is useful when the types of the runtime are not available to the client, such as
+//! storage values read from a chain which has been upgraded.
+
+use crate::types::signed_extensions::is_empty_type;
+use codec::{
+    Compact,
+    Decode,
+    Error as CodecError,
+};
+use scale_info::{
+    form::PortableForm,
+    Field,
+    PortableRegistry,
+    TypeDef,
+    TypeDefPrimitive,
+};
+use serde_json::json;
+
+/// How deep we decode nested values before giving up,
+/// this guards against self-referencing types with malicious input.
+const MAX_DEPTH: usize = 64;
+
+/// How many zero-sized items a sequence may have,
+/// their length can't be checked against the remaining input.
+const MAX_ZERO_SIZED_ITEMS: usize = 1 << 16;
+
+/// A dynamically decoded value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Bool(bool),
+    Char(char),
+    String(String),
+    /// any of the unsigned integers up to u128
+    U128(u128),
+    /// any of the signed integers up to i128
+    I128(i128),
+    /// u256 and i256 in little endian bytes
+    U256([u8; 32]),
+    I256([u8; 32]),
+    /// a sequence or array of `u8`
+    Bytes(Vec<u8>),
+    /// a sequence, an array or a tuple
+    Sequence(Vec<Value>),
+    /// a struct, the field names are `None` for tuple structs
+    Composite(Vec<(Option<String>, Value)>),
+    /// a variant of an enum
+    Variant {
+        name: String,
+        fields: Vec<(Option<String>, Value)>,
+    },
+    BitSequence(Vec<bool>),
+}
+
+impl Value {
+    /// Convert into json, integers bigger than `u64` are converted into strings
+    /// since most json parsers can not handle them
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Bool(v) => json!(v),
+            Value::Char(v) => json!(v.to_string()),
+            Value::String(v) => json!(v),
+            Value::U128(v) => {
+                match u64::try_from(*v) {
+                    Ok(v) => json!(v),
+                    Err(_) => json!(v.to_string()),
+                }
+            }
+            Value::I128(v) => {
+                match i64::try_from(*v) {
+                    Ok(v) => json!(v),
+                    Err(_) => json!(v.to_string()),
+                }
+            }
+            Value::U256(v) | Value::I256(v) => {
+                json!(format!("0x{}", hex::encode(v)))
+            }
+            Value::Bytes(v) => json!(format!("0x{}", hex::encode(v))),
+            Value::Sequence(values) => {
+                serde_json::Value::Array(
+                    values.iter().map(Value::to_json).collect(),
+                )
+            }
+            Value::Composite(fields) => fields_to_json(fields),
+            Value::Variant { name, fields } => {
+                if fields.is_empty() {
+                    json!(name)
+                } else {
+                    let mut map = serde_json::Map::new();
+                    map.insert(name.clone(), fields_to_json(fields));
+                    serde_json::Value::Object(map)
+                }
+            }
+            Value::BitSequence(bits) => json!(bits),
+        }
+    }
+
+    /// The unsigned integer value, if this is one
+    pub fn as_u128(&self) -> Option<u128> {
+        match self {
+            Value::U128(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// The field with the `name` of a struct or an enum variant
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        let fields = match self {
+            Value::Composite(fields) => fields,
+            Value::Variant { fields, .. } => fields,
+            _ => return None,
+        };
+        fields
+            .iter()
+            .find(|(field_name, _)| field_name.as_deref() == Some(name))
+            .map(|(_, value)| value)
+    }
+}
+
+/// Named fields are converted into an object, unnamed fields into an array
+/// and a single unnamed field is converted into its inner value
+fn fields_to_json(fields: &[(Option<String>, Value)]) -> serde_json::Value {
+    if fields.iter().all(|(name, _)| name.is_some()) && !fields.is_empty() {
+        let map = fields
+            .iter()
+            .map(|(name, value)| {
+                (name.clone().expect("must have a name"), value.to_json())
+            })
+            .collect();
+        serde_json::Value::Object(map)
+    } else if fields.len() == 1 {
+        fields[0].1.to_json()
+    } else {
+        serde_json::Value::Array(
+            fields.iter().map(|(_, value)| value.to_json()).collect(),
+        )
+    }
+}
+
+/// Decode a value of type `type_id` from the `input`
+pub fn decode_value(
+    registry: &PortableRegistry,
+    type_id: u32,
+    input: &mut &[u8],
+) -> Result<Value, CodecError> {
+    decode_with_depth(registry, type_id, input, 0)
+}
+
+/// Decode a value of type `type_id` from the `bytes`, all of the bytes must be consumed
+pub fn decode_value_exact(
+    registry: &PortableRegistry,
+    type_id: u32,
+    bytes: &[u8],
+) -> Result<Value, CodecError> {
+    let mut input = bytes;
+    let value = decode_value(registry, type_id, &mut input)?;
+    if !input.is_empty() {
+        return Err(CodecError::from("input is not fully consumed"));
+    }
+    Ok(value)
+}
+
+fn decode_with_depth(
+    registry: &PortableRegistry,
+    type_id: u32,
+    input: &mut &[u8],
+    depth: usize,
+) -> Result<Value, CodecError> {
+    if depth > MAX_DEPTH {
+        return Err(CodecError::from("value is nested too deep"));
+    }
+    let ty = registry
+        .resolve(type_id)
+        .ok_or_else(|| CodecError::from("type not found in the registry"))?;
+    let decode = |id: u32, input: &mut &[u8]| {
+        decode_with_depth(registry, id, input, depth + 1)
+    };
+    match ty.type_def() {
+        TypeDef::Primitive(primitive) => decode_primitive(primitive, input),
+        TypeDef::Sequence(seq) => {
+            let len = Compact::<u32>::decode(input)?.0 as usize;
+            decode_items(registry, seq.type_param().id(), len, input, depth)
+        }
+        TypeDef::Array(array) => {
+            decode_items(
+                registry,
+                array.type_param().id(),
+                array.len() as usize,
+                input,
+                depth,
+            )
+        }
+        TypeDef::Tuple(tuple) => {
+            let values = tuple
+                .fields()
+                .iter()
+                .map(|field| decode(field.id(), input))
+                .collect::<Result<_, _>>()?;
+            Ok(Value::Sequence(values))
+        }
+        TypeDef::Composite(composite) => {
+            let fields =
+                decode_fields(registry, composite.fields(), input, depth)?;
+            Ok(Value::Composite(fields))
+        }
+        TypeDef::Variant(variant) => {
+            let index = u8::decode(input)?;
+            let variant = variant
+                .variants()
+                .iter()
+                .find(|v| v.index() == index)
+                .ok_or_else(|| CodecError::from("unknown variant index"))?;
+            let fields =
+                decode_fields(registry, variant.fields(), input, depth)?;
+            Ok(Value::Variant {
+                name: variant.name().clone(),
+                fields,
+            })
+        }
+        TypeDef::Compact(compact) => {
+            decode_compact(registry, compact.type_param().id(), input, depth)
+        }
+        TypeDef::BitSequence(bits) => {
+            let store = registry
+                .resolve(bits.bit_store_type().id())
+                .map(|ty| ty.type_def());
+            let store_bytes = match store {
+                Some(TypeDef::Primitive(TypeDefPrimitive::U8)) => 1,
+                Some(TypeDef::Primitive(TypeDefPrimitive::U16)) => 2,
+                Some(TypeDef::Primitive(TypeDefPrimitive::U32)) => 4,
+                Some(TypeDef::Primitive(TypeDefPrimitive::U64)) => 8,
+                _ => {
+                    return Err(CodecError::from(
+                        "unsupported bit sequence store type",
+                    ))
+                }
+            };
+            let msb0 = registry
+                .resolve(bits.bit_order_type().id())
+                .and_then(|ty| ty.path().ident())
+                .map(|ident| ident == "Msb0")
+                .unwrap_or(false);
+            decode_bits(store_bytes, msb0, input)
+        }
+    }
+}
+
+fn decode_items(
+    registry: &PortableRegistry,
+    type_id: u32,
+    len: usize,
+    input: &mut &[u8],
+    depth: usize,
+) -> Result<Value, CodecError> {
+    if is_empty_type(registry, type_id, 0) {
+        // the zero-sized items don't consume the input,
+        // so a huge length would spin without ever running out of data
+        if len > MAX_ZERO_SIZED_ITEMS {
+            return Err(CodecError::from("too many zero-sized items"));
+        }
+    } else if input.len() < len {
+        // each of the other items takes at least one byte
+        return Err(CodecError::from("not enough data for the items"));
+    }
+    let is_u8 = matches!(
+        registry.resolve(type_id).map(|ty| ty.type_def()),
+        Some(TypeDef::Primitive(TypeDefPrimitive::U8))
+    );
+    if is_u8 {
+        let (bytes, rest) = input.split_at(len);
+        *input = rest;
+        return Ok(Value::Bytes(bytes.to_vec()));
+    }
+    // don't trust the length for the capacity, it comes from the input
+    let mut values = Vec::new();
+    for _ in 0..len {
+        values.push(decode_with_depth(registry, type_id, input, depth + 1)?);
+    }
+    Ok(Value::Sequence(values))
+}
+
+fn decode_fields(
+    registry: &PortableRegistry,
+    fields: &[Field<PortableForm>],
+    input: &mut &[u8],
+    depth: usize,
+) -> Result<Vec<(Option<String>, Value)>, CodecError> {
+    fields
+        .iter()
+        .map(|field| {
+            let value =
+                decode_with_depth(registry, field.ty().id(), input, depth + 1)?;
+            Ok((field.name().cloned(), value))
+        })
+        .collect()
+}
+
+fn decode_primitive(
+    primitive: &TypeDefPrimitive,
+    input: &mut &[u8],
+) -> Result<Value, CodecError> {
+    let value = match primitive {
+        TypeDefPrimitive::Bool => Value::Bool(bool::decode(input)?),
+        TypeDefPrimitive::Char => {
+            let c = u32::decode(input)?;
+            Value::Char(
+                char::from_u32(c)
+                    .ok_or_else(|| CodecError::from("invalid char"))?,
+            )
+        }
+        TypeDefPrimitive::Str => Value::String(String::decode(input)?),
+        TypeDefPrimitive::U8 => Value::U128(u8::decode(input)?.into()),
+        TypeDefPrimitive::U16 => Value::U128(u16::decode(input)?.into()),
+        TypeDefPrimitive::U32 => Value::U128(u32::decode(input)?.into()),
+        TypeDefPrimitive::U64 => Value::U128(u64::decode(input)?.into()),
+        TypeDefPrimitive::U128 => Value::U128(u128::decode(input)?),
+        TypeDefPrimitive::U256 => Value::U256(<[u8; 32]>::decode(input)?),
+        TypeDefPrimitive::I8 => Value::I128(i8::decode(input)?.into()),
+        TypeDefPrimitive::I16 => Value::I128(i16::decode(input)?.into()),
+        TypeDefPrimitive::I32 => Value::I128(i32::decode(input)?.into()),
+        TypeDefPrimitive::I64 => Value::I128(i64::decode(input)?.into()),
+        TypeDefPrimitive::I128 => Value::I128(i128::decode(input)?),
+        TypeDefPrimitive::I256 => Value::I256(<[u8; 32]>::decode(input)?),
+    };
+    Ok(value)
+}
+
+/// Compact encoding is only defined for unsigned integers
+/// and structs wrapping a single unsigned integer, ie: `Perbill`
+fn decode_compact(
+    registry: &PortableRegistry,
+    type_id: u32,
+    input: &mut &[u8],
+    depth: usize,
+) -> Result<Value, CodecError> {
+    if depth > MAX_DEPTH {
+        return Err(CodecError::from("value is nested too deep"));
+    }
+    let ty = registry
+        .resolve(type_id)
+        .ok_or_else(|| CodecError::from("type not found in the registry"))?;
+    match ty.type_def() {
+        TypeDef::Primitive(primitive) => {
+            let value = match primitive {
+                TypeDefPrimitive::U8 => Compact::<u8>::decode(input)?.0.into(),
+                TypeDefPrimitive::U16 => {
+                    Compact::<u16>::decode(input)?.0.into()
+                }
+                TypeDefPrimitive::U32 => {
+                    Compact::<u32>::decode(input)?.0.into()
+                }
+                TypeDefPrimitive::U64 => {
+                    Compact::<u64>::decode(input)?.0.into()
+                }
+                TypeDefPrimitive::U128 => Compact::<u128>::decode(input)?.0,
+                _ => {
+                    return Err(CodecError::from(
+                        "compact is only supported for unsigned integers",
+                    ))
+                }
+            };
+            Ok(Value::U128(value))
+        }
+        TypeDef::Composite(composite) if composite.fields().len() == 1 => {
+            let field = &composite.fields()[0];
+            let value =
+                decode_compact(registry, field.ty().id(), input, depth + 1)?;
+            Ok(Value::Composite(vec![(field.name().cloned(), value)]))
+        }
+        TypeDef::Tuple(tuple) if tuple.fields().is_empty() => {
+            Ok(Value::Sequence(vec![]))
+        }
+        _ => Err(CodecError::from("unsupported compact type")),
+    }
+}
+
+/// A bit sequence is encoded as the number of bits followed by the store items
+fn decode_bits(
+    store_bytes: usize,
+    msb0: bool,
+    input: &mut &[u8],
+) -> Result<Value, CodecError> {
+    let bit_len = Compact::<u32>::decode(input)?.0 as usize;
+    let store_bits = store_bytes * 8;
+    let items = bit_len
+        .checked_add(store_bits - 1)
+        .ok_or_else(|| CodecError::from("bit sequence is too long"))?
+        / store_bits;
+    let byte_len = items * store_bytes;
+    if input.len() < byte_len {
+        return Err(CodecError::from("not enough data for the bit sequence"));
+    }
+    let (bytes, rest) = input.split_at(byte_len);
+    *input = rest;
+    let bits = (0..bit_len)
+        .map(|i| {
+            let item = &bytes[(i / store_bits) * store_bytes..][..store_bytes];
+            // the store items are little endian integers
+            let item = item
+                .iter()
+                .rev()
+                .fold(0u64, |acc, byte| (acc << 8) | *byte as u64);
+            let bit = i % store_bits;
+            let shift = if msb0 { store_bits - 1 - bit } else { bit };
+            (item >> shift) & 1 == 1
+        })
+        .collect();
+    Ok(Value::BitSequence(bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::Encode;
+    use scale_info::{
+        meta_type,
+        Registry,
+        TypeInfo,
+    };
+
+    #[derive(Encode, TypeInfo)]
+    struct Post {
+        post_id: u32,
+        content: Vec<u8>,
+        #[codec(compact)]
+        amount: u128,
+        kind: Kind,
+    }
+
+    #[derive(Encode, TypeInfo)]
+    enum Kind {
+        #[allow(dead_code)]
+        Comment,
+        Post(u64, bool),
+    }
+
+    fn registry_for<T: TypeInfo + 'static>() -> (PortableRegistry, u32) {
+        let mut registry = Registry::new();
+        let id = registry.register_type(&meta_type::<T>()).id();
+        (registry.into(), id)
+    }
+
+    #[test]
+    fn decode_struct() {
+        let (registry, id) = registry_for::<Post>();
+        let post = Post {
+            post_id: 7,
+            content: b"hello".to_vec(),
+            amount: u128::MAX,
+            kind: Kind::Post(3, true),
+        };
+        let value = decode_value_exact(&registry, id, &post.encode()).unwrap();
+        assert_eq!(value.field("post_id"), Some(&Value::U128(7)));
+        assert_eq!(
+            value.to_json(),
+            json!({
+                "post_id": 7,
+                "content": "0x68656c6c6f",
+                "amount": u128::MAX.to_string(),
+                "kind": {"Post": [3, true]},
+            })
+        );
+    }
+
+    #[test]
+    fn reject_trailing_bytes() {
+        let (registry, id) = registry_for::<u32>();
+        let mut bytes = 1u32.encode();
+        bytes.push(0);
+        assert!(decode_value_exact(&registry, id, &bytes).is_err());
+    }
+
+    #[test]
+    fn reject_length_longer_than_input() {
+        let (registry, id) = registry_for::<Vec<u32>>();
+        let mut bytes = codec::Compact(u32::MAX).encode();
+        bytes.extend(7u32.encode());
+        assert!(decode_value_exact(&registry, id, &bytes).is_err());
+    }
+
+    #[test]
+    fn decode_zero_sized_items() {
+        let (registry, id) = registry_for::<Vec<()>>();
+        let bytes = vec![(), (), ()].encode();
+        let value = decode_value_exact(&registry, id, &bytes).unwrap();
+        assert_eq!(
+            value,
+            Value::Sequence(vec![
+                Value::Sequence(vec![]),
+                Value::Sequence(vec![]),
+                Value::Sequence(vec![]),
+            ])
+        );
+
+        let bytes = codec::Compact(u32::MAX).encode();
+        assert!(decode_value_exact(&registry, id, &bytes).is_err());
+    }
+}
@@ -0,0 +1,810 @@
+//! Browse the content of the chain metadata.
+//!
+//! The [`MetadataExplorer`] lists the pallets with their calls, storage entries, events, errors
+//! and constants along with their docs. Type ids are resolved into readable rust-like names such
+//! as `BoundedVec<u8, 280>`.
+
+use crate::{
+    types::metadata::{
+        Metadata,
+        MetadataError,
+    },
+    utils::FromHexStr,
+};
+use frame_metadata::v14::{
+    PalletConstantMetadata,
+    PalletMetadata as RuntimePalletMetadata,
+    StorageEntryMetadata,
+    StorageEntryType,
+};
+use scale_info::{
+    form::PortableForm,
+    Field,
+    PortableRegistry,
+    TypeDef,
+    TypeDefPrimitive,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::fmt;
+
+/// How deep we resolve nested type parameters before giving up,
+/// this guards against self-referencing types.
+const MAX_TYPE_DEPTH: usize = 32;
+
+/// The kind of item in a pallet which matched a search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ItemKind {
+    Pallet,
+    Call,
+    Storage,
+    Event,
+    Error,
+    Constant,
+}
+
+/// A named field or argument, with its type rendered as a rust-like type signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldInfo {
+    pub name: Option<String>,
+    pub ty: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CallInfo {
+    pub name: String,
+    pub index: u8,
+    pub args: Vec<FieldInfo>,
+    pub docs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageInfo {
+    pub name: String,
+    /// `Optional` or `Default`
+    pub modifier: String,
+    pub hashers: Vec<String>,
+    pub key_types: Vec<String>,
+    pub value_type: String,
+    /// the hex encoded default value of this storage entry
+    pub default: String,
+    pub docs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventInfo {
+    pub name: String,
+    pub index: u8,
+    pub fields: Vec<FieldInfo>,
+    pub docs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorInfo {
+    pub name: String,
+    pub index: u8,
+    pub docs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConstantInfo {
+    pub name: String,
+    pub ty: String,
+    /// the hex encoded value of this constant
+    pub value: String,
+    pub docs: Vec<String>,
+}
+
+/// Everything there is to know about a pallet
+#[derive(Debug, Clone, Serialize)]
+pub struct PalletInfo {
+    pub name: String,
+    pub index: u8,
+    /// Only available from V15 metadata onwards
+    pub docs: Vec<String>,
+    pub calls: Vec<CallInfo>,
+    pub storage: Vec<StorageInfo>,
+    pub events: Vec<EventInfo>,
+    pub errors: Vec<ErrorInfo>,
+    pub constants: Vec<ConstantInfo>,
+}
+
+/// An item that matched the search query
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchMatch {
+    pub pallet: String,
+    pub kind: ItemKind,
+    pub name: String,
+    /// the higher the better
+    pub score: u32,
+    pub docs: Vec<String>,
+}
+
+/// Explore the pallets, calls, storage, events, errors and constants of the metadata
+pub struct MetadataExplorer<'a> {
+    metadata: &'a Metadata,
+}
+
+impl Metadata {
+    /// Return an explorer to browse and search this metadata
+    pub fn explorer(&self) -> MetadataExplorer {
+        MetadataExplorer::new(self)
+    }
+
+    /// Return a rust-like name of the type `id`, ie: `Vec<u8>`, `Option<AccountId32>`
+    pub fn type_name(&self, id: u32) -> String {
+        type_name(&self.metadata.types, id)
+    }
+}
+
+impl<'a> MetadataExplorer<'a> {
+    pub fn new(metadata: &'a Metadata) -> Self {
+        Self { metadata }
+    }
+
+    /// List all the pallets, ordered by their index
+    pub fn pallets(&self) -> Vec<PalletInfo> {
+        let mut pallets: Vec<PalletInfo> = self
+            .metadata
+            .metadata
+            .pallets
+            .iter()
+            .map(|pallet| self.pallet_info(pallet))
+            .collect();
+        pallets.sort_by_key(|pallet| pallet.index);
+        pallets
+    }
+
+    /// Get the info of pallet with name `pallet_name`
+    pub fn pallet(
+        &self,
+        pallet_name: &str,
+    ) -> Result<PalletInfo, MetadataError> {
+        self.metadata
+            .metadata
+            .pallets
+            .iter()
+            .find(|pallet| pallet.name == pallet_name)
+            .map(|pallet| self.pallet_info(pallet))
+            .ok_or_else(|| {
+                MetadataError::PalletNotFound(pallet_name.to_string())
+            })
+    }
+
+    /// Fuzzy search the pallets and their items by name.
+    /// The query matches if its characters appear in order in the name, case insensitively.
+    /// The best matches comes first.
+    pub fn search(&self, query: &str) -> Vec<SearchMatch> {
+        let mut matches = vec![];
+        for pallet in self.pallets() {
+            let mut push_match =
+                |kind: ItemKind, name: &str, docs: &[String]| {
+                    let qualified = format!("{}.{}", pallet.name, name);
+                    let score = fuzzy_score(query, name)
+                        .max(fuzzy_score(query, &qualified));
+                    if let Some(score) = score {
+                        matches.push(SearchMatch {
+                            pallet: pallet.name.clone(),
+                            kind,
+                            name: name.to_string(),
+                            score,
+                            docs: docs.to_vec(),
+                        });
+                    }
+                };
+            push_match(ItemKind::Pallet, &pallet.name, &pallet.docs);
+            for call in pallet.calls.iter() {
+                push_match(ItemKind::Call, &call.name, &call.docs);
+            }
+            for storage in pallet.storage.iter() {
+                push_match(ItemKind::Storage, &storage.name, &storage.docs);
+            }
+            for event in pallet.events.iter() {
+                push_match(ItemKind::Event, &event.name, &event.docs);
+            }
+            for error in pallet.errors.iter() {
+                push_match(ItemKind::Error, &error.name, &error.docs);
+            }
+            for constant in pallet.constants.iter() {
+                push_match(ItemKind::Constant, &constant.name, &constant.docs);
+            }
+        }
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.pallet.cmp(&b.pallet))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        matches
+    }
+
+    /// Render all the pallets into a readable text
+    pub fn to_text(&self) -> String {
+        self.pallets()
+            .iter()
+            .map(|pallet| pallet.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Render all the pallets into json
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self.pallets())
+    }
+
+    fn pallet_info(
+        &self,
+        pallet: &RuntimePalletMetadata<PortableForm>,
+    ) -> PalletInfo {
+        let constants: Vec<ConstantInfo> = pallet
+            .constants
+            .iter()
+            .map(|constant| self.constant_info(constant))
+            .collect();
+
+        let calls = pallet
+            .calls
+            .as_ref()
+            .map(|calls| {
+                self.variants(calls.ty.id())
+                    .into_iter()
+                    .map(|(name, index, fields, docs)| {
+                        CallInfo {
+                            name,
+                            index,
+                            args: self.fields_info(&fields, &constants),
+                            docs,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let events = pallet
+            .event
+            .as_ref()
+            .map(|event| {
+                self.variants(event.ty.id())
+                    .into_iter()
+                    .map(|(name, index, fields, docs)| {
+                        EventInfo {
+                            name,
+                            index,
+                            fields: self.fields_info(&fields, &constants),
+                            docs,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let errors = pallet
+            .error
+            .as_ref()
+            .map(|error| {
+                self.variants(error.ty.id())
+                    .into_iter()
+                    .map(|(name, index, _fields, docs)| {
+                        ErrorInfo { name, index, docs }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let storage = pallet
+            .storage
+            .as_ref()
+            .map(|storage| {
+                storage
+                    .entries
+                    .iter()
+                    .map(|entry| self.storage_info(entry))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let docs = self
+            .metadata
+            .pallets
+            .get(&pallet.name)
+            .map(|pallet| pallet.docs.clone())
+            .unwrap_or_default();
+
+        PalletInfo {
+            name: pallet.name.clone(),
+            index: pallet.index,
+            docs,
+            calls,
+            storage,
+            events,
+            errors,
+            constants,
+        }
+    }
+
+    /// the variants of an enum type, sorted by their index
+    fn variants(
+        &self,
+        type_id: u32,
+    ) -> Vec<(String, u8, Vec<Field<PortableForm>>, Vec<String>)> {
+        let mut variants = match self
+            .metadata
+            .get_resolve_type(type_id)
+            .map(|ty| ty.type_def())
+        {
+            Some(TypeDef::Variant(variant)) => {
+                variant
+                    .variants()
+                    .iter()
+                    .map(|v| {
+                        (
+                            v.name().clone(),
+                            v.index(),
+                            v.fields().to_vec(),
+                            v.docs().to_vec(),
+                        )
+                    })
+                    .collect()
+            }
+            _ => vec![],
+        };
+        variants.sort_by_key(|(_, index, _, _)| *index);
+        variants
+    }
+
+    /// Render the fields using the resolved type names.
+    /// When the resolved name has type parameters that are not captured in the metadata,
+    /// such as the bound of a `BoundedVec`, the type name written in the source is used instead
+    /// with the pallet constants substituted with their values.
+    fn fields_info(
+        &self,
+        fields: &[Field<PortableForm>],
+        constants: &[ConstantInfo],
+    ) -> Vec<FieldInfo> {
+        fields
+            .iter()
+            .map(|field| {
+                let (resolved, erased) = resolve_type_name(
+                    &self.metadata.metadata.types,
+                    field.ty().id(),
+                    0,
+                );
+                let ty = match field.type_name() {
+                    Some(type_name) if erased => {
+                        substitute_constants(type_name, constants)
+                    }
+                    _ => resolved,
+                };
+                FieldInfo {
+                    name: field.name().cloned(),
+                    ty,
+                }
+            })
+            .collect()
+    }
+
+    fn storage_info(
+        &self,
+        entry: &StorageEntryMetadata<PortableForm>,
+    ) -> StorageInfo {
+        let (hashers, key_types, value_type) = match &entry.ty {
+            StorageEntryType::Plain(value) => {
+                (vec![], vec![], self.metadata.type_name(value.id()))
+            }
+            StorageEntryType::Map {
+                hashers,
+                key,
+                value,
+            } => {
+                let key_types = match self
+                    .metadata
+                    .get_resolve_type(key.id())
+                    .map(|ty| ty.type_def())
+                {
+                    Some(TypeDef::Tuple(tuple)) if hashers.len() > 1 => {
+                        tuple
+                            .fields()
+                            .iter()
+                            .map(|field| self.metadata.type_name(field.id()))
+                            .collect()
+                    }
+                    _ => vec![self.metadata.type_name(key.id())],
+                };
+                (
+                    hashers.iter().map(|h| format!("{:?}", h)).collect(),
+                    key_types,
+                    self.metadata.type_name(value.id()),
+                )
+            }
+        };
+        StorageInfo {
+            name: entry.name.clone(),
+            modifier: format!("{:?}", entry.modifier),
+            hashers,
+            key_types,
+            value_type,
+            default: format!("0x{}", hex::encode(&entry.default)),
+            docs: entry.docs.clone(),
+        }
+    }
+
+    fn constant_info(
+        &self,
+        constant: &PalletConstantMetadata<PortableForm>,
+    ) -> ConstantInfo {
+        ConstantInfo {
+            name: constant.name.clone(),
+            ty: self.metadata.type_name(constant.ty.id()),
+            value: format!("0x{}", hex::encode(&constant.value)),
+            docs: constant.docs.clone(),
+        }
+    }
+}
+
+impl ConstantInfo {
+    /// decode the value of this constant if it is an unsigned integer
+    fn as_unsigned(&self) -> Option<u128> {
+        let bytes = Vec::from_hex(&self.value).ok()?;
+        match (self.ty.as_str(), bytes.len()) {
+            ("u8", 1) | ("u16", 2) | ("u32", 4) | ("u64", 8) | ("u128", 16) => {
+                let mut le = [0u8; 16];
+                le[..bytes.len()].copy_from_slice(&bytes);
+                Some(u128::from_le_bytes(le))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Return a rust-like name of the type `id` in the type `registry`
+pub fn type_name(registry: &PortableRegistry, id: u32) -> String {
+    resolve_type_name(registry, id, 0).0
+}
+
+/// Returns the type name and whether or not there are type parameters which was not captured in
+/// the metadata
+fn resolve_type_name(
+    registry: &PortableRegistry,
+    id: u32,
+    depth: usize,
+) -> (String, bool) {
+    let ty = match registry.resolve(id) {
+        Some(ty) if depth < MAX_TYPE_DEPTH => ty,
+        Some(_) => return ("..".to_string(), false),
+        None => return (format!("<unknown type {}>", id), false),
+    };
+    let resolve = |id: u32| resolve_type_name(registry, id, depth + 1);
+    match ty.type_def() {
+        TypeDef::Primitive(primitive) => {
+            (primitive_name(primitive).to_string(), false)
+        }
+        TypeDef::Sequence(seq) => {
+            let (inner, erased) = resolve(seq.type_param().id());
+            (format!("Vec<{}>", inner), erased)
+        }
+        TypeDef::Array(array) => {
+            let (inner, erased) = resolve(array.type_param().id());
+            (format!("[{}; {}]", inner, array.len()), erased)
+        }
+        TypeDef::Compact(compact) => {
+            let (inner, erased) = resolve(compact.type_param().id());
+            (format!("Compact<{}>", inner), erased)
+        }
+        TypeDef::Tuple(tuple) => {
+            let (names, erased): (Vec<String>, Vec<bool>) =
+                tuple.fields().iter().map(|f| resolve(f.id())).unzip();
+            (format!("({})", names.join(", ")), erased.contains(&true))
+        }
+        TypeDef::BitSequence(bits) => {
+            let (store, _) = resolve(bits.bit_store_type().id());
+            let (order, _) = resolve(bits.bit_order_type().id());
+            (format!("BitVec<{}, {}>", store, order), false)
+        }
+        TypeDef::Composite(_) | TypeDef::Variant(_) => {
+            let ident = match ty.path().ident() {
+                Some(ident) => ident,
+                // types without a path are transparent wrappers
+                None => {
+                    return match ty.type_def() {
+                        TypeDef::Composite(composite)
+                            if composite.fields().len() == 1 =>
+                        {
+                            resolve(composite.fields()[0].ty().id())
+                        }
+                        _ => ("<anonymous>".to_string(), false),
+                    };
+                }
+            };
+            if ty.type_params().is_empty() {
+                return (ident, false);
+            }
+            let mut erased = false;
+            let params: Vec<String> = ty
+                .type_params()
+                .iter()
+                .map(|param| {
+                    match param.ty() {
+                        Some(param_ty) => {
+                            let (name, param_erased) = resolve(param_ty.id());
+                            erased |= param_erased;
+                            name
+                        }
+                        None => {
+                            erased = true;
+                            param.name().clone()
+                        }
+                    }
+                })
+                .collect();
+            (format!("{}<{}>", ident, params.join(", ")), erased)
+        }
+    }
+}
+
+fn primitive_name(primitive: &TypeDefPrimitive) -> &'static str {
+    match primitive {
+        TypeDefPrimitive::Bool => "bool",
+        TypeDefPrimitive::Char => "char",
+        TypeDefPrimitive::Str => "String",
+        TypeDefPrimitive::U8 => "u8",
+        TypeDefPrimitive::U16 => "u16",
+        TypeDefPrimitive::U32 => "u32",
+        TypeDefPrimitive::U64 => "u64",
+        TypeDefPrimitive::U128 => "u128",
+        TypeDefPrimitive::U256 => "u256",
+        TypeDefPrimitive::I8 => "i8",
+        TypeDefPrimitive::I16 => "i16",
+        TypeDefPrimitive::I32 => "i32",
+        TypeDefPrimitive::I64 => "i64",
+        TypeDefPrimitive::I128 => "i128",
+        TypeDefPrimitive::I256 => "i256",
+    }
+}
+
+/// Replace the associated constants of the pallet config, ie: `T::MaxContentLength` in the source
+/// type name with the value of the constant
+fn substitute_constants(type_name: &str, constants: &[ConstantInfo]) -> String {
+    let mut result = type_name.to_string();
+    for constant in constants.iter() {
+        if let Some(value) = constant.as_unsigned() {
+            let value = value.to_string();
+            for pattern in [
+                format!("<T as Config>::{}", constant.name),
+                format!("T::{}", constant.name),
+            ] {
+                result = replace_path(&result, &pattern, &value);
+            }
+        }
+    }
+    result
+}
+
+/// Replace the occurrences of the path `pattern` in `type_name` which are not part of
+/// a longer identifier, so `T::MaxLen` is not replaced in `T::MaxLenBytes`
+fn replace_path(type_name: &str, pattern: &str, value: &str) -> String {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut result = String::with_capacity(type_name.len());
+    let mut rest = type_name;
+    while let Some(start) = rest.find(pattern) {
+        let end = start + pattern.len();
+        let before = rest[..start].chars().next_back();
+        let after = rest[end..].chars().next();
+        result.push_str(&rest[..start]);
+        if before.is_some_and(is_ident) || after.is_some_and(is_ident) {
+            result.push_str(pattern);
+        } else {
+            result.push_str(value);
+        }
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Score how well `query` matches the `candidate`, returns `None` if it doesn't match at all.
+/// Exact matches scores the highest, followed by prefix, substring and then subsequence matches.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<u32> {
+    let query = query.trim().to_lowercase();
+    let candidate = candidate.to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+    if candidate == query {
+        return Some(1000);
+    }
+    if candidate.starts_with(&query) {
+        return Some(800 - candidate.len().min(100) as u32);
+    }
+    if candidate.contains(&query) {
+        return Some(600 - candidate.len().min(100) as u32);
+    }
+    // every character of the query must appear in order in the candidate,
+    // each gap in between lowers the score
+    let mut gaps = 0;
+    let mut candidate_chars = candidate.chars();
+    for qc in query.chars() {
+        loop {
+            match candidate_chars.next() {
+                Some(cc) if cc == qc => break,
+                Some(_) => gaps += 1,
+                None => return None,
+            }
+        }
+    }
+    Some(400_u32.saturating_sub(gaps).max(1))
+}
+
+fn fmt_docs(
+    f: &mut fmt::Formatter,
+    docs: &[String],
+    indent: &str,
+) -> fmt::Result {
+    for doc in docs.iter() {
+        writeln!(f, "{}///{}", indent, doc)?;
+    }
+    Ok(())
+}
+
+fn fmt_fields(fields: &[FieldInfo]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            match &field.name {
+                Some(name) => format!("{}: {}", name, field.ty),
+                None => field.ty.clone(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl fmt::Display for PalletInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_docs(f, &self.docs, "")?;
+        writeln!(f, "pallet {} (index: {})", self.name, self.index)?;
+        if !self.calls.is_empty() {
+            writeln!(f, "  calls:")?;
+            for call in self.calls.iter() {
+                fmt_docs(f, &call.docs, "    ")?;
+                writeln!(f, "    {}({})", call.name, fmt_fields(&call.args))?;
+            }
+        }
+        if !self.storage.is_empty() {
+            writeln!(f, "  storage:")?;
+            for storage in self.storage.iter() {
+                fmt_docs(f, &storage.docs, "    ")?;
+                if storage.key_types.is_empty() {
+                    write!(f, "    {}: {}", storage.name, storage.value_type)?;
+                } else {
+                    let keys: Vec<String> = storage
+                        .hashers
+                        .iter()
+                        .zip(storage.key_types.iter())
+                        .map(|(hasher, key)| format!("{}({})", hasher, key))
+                        .collect();
+                    write!(
+                        f,
+                        "    {}: map [{}] => {}",
+                        storage.name,
+                        keys.join(", "),
+                        storage.value_type
+                    )?;
+                }
+                writeln!(
+                    f,
+                    " ({}, default: {})",
+                    storage.modifier, storage.default
+                )?;
+            }
+        }
+        if !self.events.is_empty() {
+            writeln!(f, "  events:")?;
+            for event in self.events.iter() {
+                fmt_docs(f, &event.docs, "    ")?;
+                writeln!(
+                    f,
+                    "    {}({})",
+                    event.name,
+                    fmt_fields(&event.fields)
+                )?;
+            }
+        }
+        if !self.errors.is_empty() {
+            writeln!(f, "  errors:")?;
+            for error in self.errors.iter() {
+                fmt_docs(f, &error.docs, "    ")?;
+                writeln!(f, "    {}", error.name)?;
+            }
+        }
+        if !self.constants.is_empty() {
+            writeln!(f, "  constants:")?;
+            for constant in self.constants.iter() {
+                fmt_docs(f, &constant.docs, "    ")?;
+                writeln!(
+                    f,
+                    "    {}: {} = {}",
+                    constant.name, constant.ty, constant.value
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale_info::{
+        meta_type,
+        Registry,
+    };
+
+    #[test]
+    fn test_type_names() {
+        let mut registry = Registry::new();
+        let id = registry
+            .register_type(&meta_type::<Option<(u32, Vec<u8>, [u8; 4])>>())
+            .id();
+        let registry = PortableRegistry::from(registry);
+        assert_eq!(type_name(&registry, id), "Option<(u32, Vec<u8>, [u8; 4])>");
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("AllPosts", "AllPosts"), Some(1000));
+        assert!(
+            fuzzy_score("allp", "AllPosts") > fuzzy_score("posts", "AllPosts")
+        );
+        assert!(
+            fuzzy_score("posts", "AllPosts") > fuzzy_score("alps", "AllPosts")
+        );
+        assert_eq!(fuzzy_score("xyz", "AllPosts"), None);
+        assert_eq!(fuzzy_score("", "AllPosts"), None);
+    }
+
+    #[test]
+    fn test_substitute_constants() {
+        let constants = vec![ConstantInfo {
+            name: "MaxContentLength".to_string(),
+            ty: "u32".to_string(),
+            value: "0x18010000".to_string(),
+            docs: vec![],
+        }];
+        assert_eq!(
+            substitute_constants(
+                "BoundedVec<u8, T::MaxContentLength>",
+                &constants
+            ),
+            "BoundedVec<u8, 280>"
+        );
+    }
+
+    #[test]
+    fn test_substitute_constants_with_prefixed_names() {
+        let constant = |name: &str, value: &str| {
+            ConstantInfo {
+                name: name.to_string(),
+                ty: "u32".to_string(),
+                value: value.to_string(),
+                docs: vec![],
+            }
+        };
+        let constants = vec![
+            constant("MaxLen", "0x20000000"),
+            constant("MaxLenBytes", "0x40000000"),
+        ];
+        assert_eq!(
+            substitute_constants(
+                "(BoundedVec<u8, T::MaxLenBytes>, BoundedVec<u32, T::MaxLen>)",
+                &constants
+            ),
+            "(BoundedVec<u8, 64>, BoundedVec<u32, 32>)"
+        );
+        assert_eq!(
+            substitute_constants("BoundedVec<u8, OtherT::MaxLen>", &constants),
+            "BoundedVec<u8, OtherT::MaxLen>"
+        );
+    }
+}
@@ -1,5 +1,13 @@
-use codec::{Compact, Decode, Encode};
-use sp_core::{blake2_256, H256};
+use crate::types::signed_extensions::ExtensionParams;
+use codec::{
+    Compact,
+    Decode,
+    Encode,
+};
+use sp_core::{
+    blake2_256,
+    H256,
+};
 use sp_runtime::generic::Era;
 use sp_std::prelude::*;
 
@@ -30,11 +38,59 @@ pub trait ExtrinsicParams {
     /// Construct a new instance of our [`ExtrinsicParams`]
     fn new(nonce: u32, other_params: Self::OtherParams) -> Self;
 
-    /// This is expected to SCALE encode the "signed extra" parameters
-    /// to some buffer that has been provided. These are the parameters
-    /// which are sent along with the transaction, as well as taken into
-    /// account when signing the transaction.
-    fn encode_extra_to(&self, v: &mut Vec<u8>);
+    /// Set the values of the signed extensions which are configured by these parameters,
+    /// ie: the nonce, era and tip. The chain specific values such as the spec version
+    /// and genesis hash are already set by the `Api`.
+    ///
+    /// The signed extra is then encoded from these values by the signed extensions of the
+    /// chain, see [`SignedExtensions`](crate::types::signed_extensions::SignedExtensions).
+    fn fill_extension_params(&self, params: &mut ExtensionParams);
+
+    /// How long the transaction is valid for. When it is [`Mortality::Mortal`], the `Api`
+    /// computes the era and the checkpoint from the latest finalized block.
+    fn mortality(&self) -> Mortality;
+}
+
+/// How long a transaction is valid for
+#[derive(Decode, Encode, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Mortality {
+    /// Valid forever, the transaction can be replayed once the account is reaped
+    /// and its nonce starts again from zero.
+    Immortal,
+    /// Valid for a period of blocks starting from the latest finalized block, the era and
+    /// its checkpoint are computed when signing. The period is capped by `System.BlockHashCount`,
+    /// `None` uses the default period.
+    Mortal(Option<u64>),
+    /// Valid for the given `era`, which starts at the block `checkpoint`
+    Era { era: Era, checkpoint: H256 },
+}
+
+impl Mortality {
+    /// The era of this mortality, this is immortal for a [`Mortality::Mortal`]
+    /// which has not been computed yet
+    pub fn era(&self) -> Era {
+        match self {
+            Mortality::Era { era, .. } => *era,
+            Mortality::Immortal | Mortality::Mortal(_) => Era::Immortal,
+        }
+    }
+}
+
+impl Default for Mortality {
+    fn default() -> Self {
+        Mortality::Mortal(None)
+    }
+}
+
+/// The amount and the asset of a tip, used to fill in the [`ExtensionParams`]
+pub trait TipPayment {
+    /// the amount of the tip
+    fn amount(&self) -> u128;
+
+    /// the asset in which the tip is paid, `None` for the native currency
+    fn asset(&self) -> Option<u32> {
+        None
+    }
 }
 
 /// A struct representing the signed extra and additional parameters required
@@ -53,7 +109,7 @@ pub type PlainTipExtrinsicParamsBuilder = BaseExtrinsicParamsBuilder<PlainTip>;
 
 #[derive(Decode, Encode, Clone, Eq, PartialEq, Debug)]
 pub struct BaseExtrinsicParams<Tip> {
-    era: Era,
+    mortality: Mortality,
     nonce: u32,
     tip: Tip,
 }
@@ -62,8 +118,7 @@ pub struct BaseExtrinsicParams<Tip> {
 /// construct a [`BaseExtrinsicParams`] value.
 #[derive(Decode, Encode, Copy, Clone, Eq, PartialEq, Debug)]
 pub struct BaseExtrinsicParamsBuilder<Tip> {
-    era: Era,
-    mortality_checkpoint: Option<H256>,
+    mortality: Mortality,
     tip: Tip,
 }
 
@@ -79,8 +134,21 @@ impl<Tip: Default> BaseExtrinsicParamsBuilder<Tip> {
     /// becomes valid, and must align with the era phase (see the [`Era::Mortal`] docs
     /// for more detail on that).
     pub fn era(mut self, era: Era, checkpoint: H256) -> Self {
-        self.era = era;
-        self.mortality_checkpoint = Some(checkpoint);
+        self.mortality = Mortality::Era { era, checkpoint };
+        self
+    }
+
+    /// Make the transaction valid for `period` blocks from the latest finalized block,
+    /// the era and its checkpoint are computed when signing.
+    /// This is the default, with a period chosen by the `Api`.
+    pub fn mortal(mut self, period: u64) -> Self {
+        self.mortality = Mortality::Mortal(Some(period));
+        self
+    }
+
+    /// Make the transaction valid forever
+    pub fn immortal(mut self) -> Self {
+        self.mortality = Mortality::Immortal;
         self
     }
 
@@ -95,8 +163,7 @@ impl<Tip: Default> BaseExtrinsicParamsBuilder<Tip> {
 impl<Tip: Default> Default for BaseExtrinsicParamsBuilder<Tip> {
     fn default() -> Self {
         Self {
-            era: Era::Immortal,
-            mortality_checkpoint: None,
+            mortality: Mortality::default(),
             tip: Tip::default(),
         }
     }
@@ -108,26 +175,47 @@ where
     u128: From<Tip>,
 {
     fn from(p: BaseExtrinsicParams<Tip>) -> GenericExtra {
-        let BaseExtrinsicParams { era, nonce, tip } = p;
-        GenericExtra(era, Compact(nonce), Compact(tip.into()))
+        let BaseExtrinsicParams {
+            mortality,
+            nonce,
+            tip,
+        } = p;
+        GenericExtra(mortality.era(), Compact(nonce), Compact(tip.into()))
     }
 }
 
-impl<Tip: Encode> ExtrinsicParams for BaseExtrinsicParams<Tip> {
+impl<Tip: Encode + TipPayment> ExtrinsicParams for BaseExtrinsicParams<Tip> {
     type OtherParams = BaseExtrinsicParamsBuilder<Tip>;
 
     fn new(nonce: u32, other_params: Self::OtherParams) -> Self {
         BaseExtrinsicParams {
-            era: other_params.era,
+            mortality: other_params.mortality,
             tip: other_params.tip,
             nonce,
         }
     }
 
-    fn encode_extra_to(&self, v: &mut Vec<u8>) {
-        let nonce: u64 = self.nonce.into();
-        let tip = self.tip.encode(); //?
-        (self.era, Compact(nonce), tip).encode_to(v);
+    fn fill_extension_params(&self, params: &mut ExtensionParams) {
+        match self.mortality {
+            Mortality::Era { era, checkpoint } => {
+                params.era = era;
+                params.mortality_checkpoint = checkpoint;
+            }
+            // immortal transactions are checkpointed at the genesis block
+            Mortality::Immortal => {
+                params.era = Era::Immortal;
+                params.mortality_checkpoint = params.genesis_hash;
+            }
+            // computed by the api
+            Mortality::Mortal(_) => (),
+        }
+        params.nonce = self.nonce;
+        params.tip = self.tip.amount();
+        params.tip_asset = self.tip.asset();
+    }
+
+    fn mortality(&self) -> Mortality {
+        self.mortality
     }
 }
 
@@ -135,17 +223,25 @@ impl<Tip: Encode> ExtrinsicParams for BaseExtrinsicParams<Tip> {
 /// Order is the same as declared in the extra.
 pub type AdditionalSigned = (u32, u32, H256, H256, (), (), ());
 
+/// The payload to be signed, `Extra` and `Additional` are the pre-encoded bytes
+/// of the signed extensions when they are composed from the metadata
 #[derive(Decode, Encode, Clone, Eq, PartialEq, Debug)]
-pub struct SignedPayload<Call>(pub (Call, GenericExtra, AdditionalSigned));
+pub struct SignedPayload<
+    Call,
+    Extra = GenericExtra,
+    Additional = AdditionalSigned,
+>(pub (Call, Extra, Additional));
 
-impl<Call> SignedPayload<Call>
+impl<Call, Extra, Additional> SignedPayload<Call, Extra, Additional>
 where
     Call: Encode,
+    Extra: Encode,
+    Additional: Encode,
 {
     pub fn from_raw(
         call: Call,
-        extra: GenericExtra,
-        additional_signed: AdditionalSigned,
+        extra: Extra,
+        additional_signed: Additional,
     ) -> Self {
         Self((call, extra, additional_signed))
     }
@@ -190,6 +286,12 @@ impl From<PlainTip> for u128 {
     }
 }
 
+impl TipPayment for PlainTip {
+    fn amount(&self) -> u128 {
+        self.tip
+    }
+}
+
 /// A tip payment made in the form of a specific asset.
 #[derive(Copy, Clone, Debug, Default, Decode, Encode, Eq, PartialEq)]
 pub struct AssetTip {
@@ -226,3 +328,13 @@ impl From<AssetTip> for u128 {
         tip.tip
     }
 }
+
+impl TipPayment for AssetTip {
+    fn amount(&self) -> u128 {
+        self.tip
+    }
+
+    fn asset(&self) -> Option<u32> {
+        self.asset
+    }
+}
@@ -42,21 +42,24 @@ pub type CallIndex = [u8; 2];
 
 /// Mirrors the currently used Extrinsic format (V4) from substrate. Has less traits and methods though.
 /// The SingedExtra used does not need to implement SingedExtension here.
+/// The `Extra` is [`Encoded`](crate::types::metadata::Encoded) when the signed extra is composed from the
+/// signed extensions in the metadata.
 #[derive(Clone, Eq, PartialEq)]
-pub struct UncheckedExtrinsicV4<Call> {
-    pub signature: Option<(GenericAddress, MultiSignature, GenericExtra)>,
+pub struct UncheckedExtrinsicV4<Call, Extra = GenericExtra> {
+    pub signature: Option<(GenericAddress, MultiSignature, Extra)>,
     pub function: Call,
 }
 
-impl<Call> UncheckedExtrinsicV4<Call>
+impl<Call, Extra> UncheckedExtrinsicV4<Call, Extra>
 where
     Call: Encode,
+    Extra: Encode,
 {
     pub fn new_signed(
         function: Call,
         signed: GenericAddress,
         signature: MultiSignature,
-        extra: GenericExtra,
+        extra: Extra,
     ) -> Self {
         UncheckedExtrinsicV4 {
             signature: Some((signed, signature, extra)),
@@ -78,9 +81,10 @@ where
     }
 }
 
-impl<Call> fmt::Debug for UncheckedExtrinsicV4<Call>
+impl<Call, Extra> fmt::Debug for UncheckedExtrinsicV4<Call, Extra>
 where
     Call: fmt::Debug,
+    Extra: fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -94,9 +98,10 @@ where
 
 const V4: u8 = 4;
 
-impl<Call> Encode for UncheckedExtrinsicV4<Call>
+impl<Call, Extra> Encode for UncheckedExtrinsicV4<Call, Extra>
 where
     Call: Encode,
+    Extra: Encode,
 {
     fn encode(&self) -> Vec<u8> {
         encode_with_vec_prefix::<Self, _>(|v| {
@@ -114,9 +119,10 @@ where
     }
 }
 
-impl<Call> Decode for UncheckedExtrinsicV4<Call>
+impl<Call, Extra> Decode for UncheckedExtrinsicV4<Call, Extra>
 where
     Call: Decode + Encode,
+    Extra: Decode + Encode,
 {
     fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
         // This is a little more complicated than usual since the binary format must be compatible
@@ -0,0 +1,223 @@
+//! The fee of an extrinsic as returned by the `payment_queryInfo`
+//! and `payment_queryFeeDetails` rpc of the transaction payment pallet.
+//!
+//! The json format of these rpc changed across substrate versions, ie: the weight is either a
+//! number or a struct with a `ref_time`, and the balances are either numbers, decimal strings or
+//! hex strings. The values are parsed leniently to support all of them.
+//!
+//! The scale encoding, as returned by the `TransactionPaymentApi` runtime api, follows the
+//! types of the node template where the weight is a plain `u64`.
+
+use crate::error::Error;
+use codec::{
+    Decode,
+    Encode,
+};
+use serde_json::Value;
+
+/// The class of a dispatchable, it determines the part of the block weight it can use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+pub enum DispatchClass {
+    Normal,
+    Operational,
+    Mandatory,
+}
+
+/// The weight, class and fee of an extrinsic, without the tip
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct RuntimeDispatchInfo {
+    /// the `ref_time` part of the weight
+    pub weight: u64,
+    pub class: DispatchClass,
+    /// the fee of the extrinsic, without the tip
+    pub partial_fee: u128,
+}
+
+/// The breakdown of the fee of an extrinsic
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FeeDetails {
+    /// the minimum fee of any extrinsic
+    pub base: u128,
+    /// the fee for the length of the extrinsic
+    pub len: u128,
+    /// the fee for the weight of the extrinsic, adjusted to the congestion of the chain
+    pub adjusted_weight: u128,
+    /// the tip, some nodes doesn't return it in which case this is zero
+    pub tip: u128,
+}
+
+/// The estimated fee of an extrinsic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeEstimate {
+    pub info: RuntimeDispatchInfo,
+    pub details: FeeDetails,
+}
+
+impl FeeEstimate {
+    /// The total amount paid for the extrinsic, including the tip
+    pub fn total(&self) -> u128 {
+        self.info.partial_fee.saturating_add(self.details.tip)
+    }
+}
+
+impl DispatchClass {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        match value.as_str().map(|class| class.to_lowercase()).as_deref() {
+            Some("normal") => Ok(DispatchClass::Normal),
+            Some("operational") => Ok(DispatchClass::Operational),
+            Some("mandatory") => Ok(DispatchClass::Mandatory),
+            _ => Err(Error::ResponseJsonError(value.clone())),
+        }
+    }
+}
+
+impl RuntimeDispatchInfo {
+    /// Parse the result of `payment_queryInfo`
+    pub fn from_json(value: &Value) -> Result<Self, Error> {
+        let invalid = || Error::ResponseJsonError(value.clone());
+        let weight = &value["weight"];
+        // newer nodes returns a 2 dimensional weight
+        let ref_time = match weight.get("ref_time").or(weight.get("refTime")) {
+            Some(ref_time) => ref_time,
+            None => weight,
+        };
+        Ok(Self {
+            weight: parse_balance(ref_time)
+                .and_then(|weight| u64::try_from(weight).ok())
+                .ok_or_else(invalid)?,
+            class: DispatchClass::from_json(&value["class"])?,
+            partial_fee: parse_balance(&value["partialFee"])
+                .ok_or_else(invalid)?,
+        })
+    }
+}
+
+impl FeeDetails {
+    /// Parse the result of `payment_queryFeeDetails`,
+    /// the inclusion fee is missing for unsigned extrinsics which are free.
+    pub fn from_json(value: &Value) -> Result<Self, Error> {
+        let invalid = || Error::ResponseJsonError(value.clone());
+        let tip = match value.get("tip") {
+            Some(tip) => parse_balance(tip).ok_or_else(invalid)?,
+            None => 0,
+        };
+        let inclusion_fee = &value["inclusionFee"];
+        if inclusion_fee.is_null() {
+            return Ok(Self {
+                tip,
+                ..Default::default()
+            });
+        }
+        Ok(Self {
+            base: parse_balance(&inclusion_fee["baseFee"])
+                .ok_or_else(invalid)?,
+            len: parse_balance(&inclusion_fee["lenFee"]).ok_or_else(invalid)?,
+            adjusted_weight: parse_balance(&inclusion_fee["adjustedWeightFee"])
+                .ok_or_else(invalid)?,
+            tip,
+        })
+    }
+}
+
+/// The inclusion fee is an `Option` in the runtime, it is `None` for unsigned extrinsics
+impl Decode for FeeDetails {
+    fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+        let inclusion_fee: Option<(u128, u128, u128)> = Decode::decode(input)?;
+        let tip = u128::decode(input)?;
+        let (base, len, adjusted_weight) = inclusion_fee.unwrap_or_default();
+        Ok(Self {
+            base,
+            len,
+            adjusted_weight,
+            tip,
+        })
+    }
+}
+
+/// Parse a balance which is either a json number, a decimal string or a hex string
+fn parse_balance(value: &Value) -> Option<u128> {
+    match value {
+        Value::Number(number) => number.as_u64().map(u128::from),
+        Value::String(s) => {
+            match s.strip_prefix("0x") {
+                Some(hex) => u128::from_str_radix(hex, 16).ok(),
+                None => s.parse().ok(),
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_dispatch_info() {
+        let old = json!({"weight": 195000000, "class": "normal", "partialFee": "158000000"});
+        let new = json!({
+            "weight": {"ref_time": 195000000, "proof_size": 0},
+            "class": "Normal",
+            "partialFee": "158000000"
+        });
+        let expected = RuntimeDispatchInfo {
+            weight: 195_000_000,
+            class: DispatchClass::Normal,
+            partial_fee: 158_000_000,
+        };
+        assert_eq!(RuntimeDispatchInfo::from_json(&old).unwrap(), expected);
+        assert_eq!(RuntimeDispatchInfo::from_json(&new).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_fee_details() {
+        let details = json!({
+            "inclusionFee": {
+                "baseFee": "0x3b9aca00",
+                "lenFee": 1200,
+                "adjustedWeightFee": "125000"
+            }
+        });
+        assert_eq!(
+            FeeDetails::from_json(&details).unwrap(),
+            FeeDetails {
+                base: 1_000_000_000,
+                len: 1200,
+                adjusted_weight: 125_000,
+                tip: 0,
+            }
+        );
+        let free = json!({"inclusionFee": null, "tip": "0x10"});
+        assert_eq!(
+            FeeDetails::from_json(&free).unwrap(),
+            FeeDetails {
+                tip: 16,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_scale() {
+        let info = RuntimeDispatchInfo {
+            weight: 195_000_000,
+            class: DispatchClass::Operational,
+            partial_fee: 158_000_000,
+        };
+        assert_eq!(
+            RuntimeDispatchInfo::decode(&mut info.encode().as_slice()).unwrap(),
+            info
+        );
+        let encoded = (Some((1u128, 2u128, 3u128)), 4u128).encode();
+        assert_eq!(
+            FeeDetails::decode(&mut encoded.as_slice()).unwrap(),
+            FeeDetails {
+                base: 1,
+                len: 2,
+                adjusted_weight: 3,
+                tip: 4,
+            }
+        );
+    }
+}
@@ -19,23 +19,45 @@
 //!
 //! This file is mostly subxt.
 
-use crate::types::storage::GetStorage;
+use crate::types::{
+    dynamic,
+    storage::{
+        self,
+        DecodeStorageKeys,
+        GetStorage,
+        StorageKeys,
+    },
+};
 use codec::{
+    Decode,
     Encode,
     Error as CodecError,
 };
 use frame_metadata::{
-    v14::StorageEntryType,
-    PalletConstantMetadata,
+    v14::{
+        ExtrinsicMetadata,
+        PalletConstantMetadata,
+        PalletMetadata as RuntimePalletMetadata,
+        RuntimeMetadataV14,
+        SignedExtensionMetadata,
+        StorageEntryMetadata,
+        StorageEntryModifier,
+        StorageEntryType,
+        StorageHasher,
+    },
+    v15::{
+        OuterEnums,
+        RuntimeApiMetadata,
+        RuntimeMetadataV15,
+    },
     RuntimeMetadata,
-    RuntimeMetadataLastVersion,
     RuntimeMetadataPrefixed,
-    StorageEntryMetadata,
     META_RESERVED,
 };
 use scale_info::{
     form::PortableForm,
     Type,
+    TypeDef,
     Variant,
 };
 use serde::Serialize;
@@ -45,6 +67,11 @@ use std::{
     convert::TryFrom,
 };
 
+#[cfg(feature = "legacy-metadata")]
+mod legacy;
+#[cfg(test)]
+pub(crate) mod test_utils;
+
 /// Wraps an already encoded byte vector, prevents being encoded as a raw byte vector as part of
 /// the transaction payload
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -93,15 +120,40 @@ pub enum MetadataError {
     ConstantNotFound(String),
     #[error("Type {0} missing from type registry")]
     TypeNotFound(u32),
+    /// Signed extension has no encoder and does not encode to empty bytes
+    #[error("Unknown signed extension: {0}")]
+    UnknownSignedExtension(String),
+    /// The tip is paid in an asset but the chain has no `ChargeAssetTxPayment`
+    #[error("The tip can not be paid in the asset {0}, the chain has no ChargeAssetTxPayment")]
+    AssetTipNotSupported(u32),
+    /// The storage key does not belong to the storage entry
+    #[error("Invalid storage key: {0}")]
+    InvalidStorageKey(String),
+    /// The key can not be recovered from its hash
+    #[error("The key hashed with {0:?} can not be recovered")]
+    NonReversibleHasher(StorageHasher),
+    /// The number of keys does not match the hashers of the map
+    #[error("The storage map has {expected} keys, got {got}")]
+    StorageKeyCountMismatch { expected: usize, got: usize },
+    /// Failure to decode the key from the storage key
+    #[error("Failed to decode the storage key: {0}")]
+    StorageKeyDecodeError(CodecError),
 }
 
 /// Runtime metadata.
 #[derive(Clone, Debug)]
 pub struct Metadata {
-    pub metadata: RuntimeMetadataLastVersion,
+    /// The metadata in V14 layout, other versions are converted into this layout.
+    pub metadata: RuntimeMetadataV14,
     pub pallets: HashMap<String, PalletMetadata>,
     pub events: HashMap<(u8, u8), EventMetadata>,
     pub errors: HashMap<(u8, u8), ErrorMetadata>,
+    /// The version of the metadata as served by the node
+    pub version: u32,
+    /// The runtime api descriptors, only available from V15 onwards
+    pub apis: Vec<RuntimeApiMetadata<PortableForm>>,
+    /// The types of the outer Call, Event and Error enums, only available from V15 onwards
+    pub outer_enums: Option<OuterEnums<PortableForm>>,
 }
 
 impl Metadata {
@@ -164,10 +216,26 @@ impl Metadata {
     }
 
     /// Return the runtime metadata.
-    pub fn get_runtime_metadata(&self) -> &RuntimeMetadataLastVersion {
+    pub fn get_runtime_metadata(&self) -> &RuntimeMetadataV14 {
         &self.metadata
     }
 
+    /// Returns true if this metadata was converted from V12 or V13.
+    /// The types of legacy metadata only carry the name as written in the source code,
+    /// they have no fields and therefore can not be used to dynamically decode values.
+    pub fn is_legacy(&self) -> bool {
+        self.version < 14
+    }
+
+    /// Returns the runtime api descriptor with the name `api_name`, ie: `AccountNonceApi`.
+    /// This is only available for V15 metadata onwards.
+    pub fn runtime_api(
+        &self,
+        api_name: &str,
+    ) -> Option<&RuntimeApiMetadata<PortableForm>> {
+        self.apis.iter().find(|api| api.name == api_name)
+    }
+
     pub fn storage_value_type(
         &self,
         pallet_name: &str,
@@ -218,12 +286,57 @@ impl Metadata {
             _ => Ok(None),
         }
     }
+
+    /// The type id of the values of a storage entry, for both plain values and maps
+    pub fn storage_value_type_id(
+        &self,
+        pallet_name: &str,
+        storage_name: &str,
+    ) -> Result<u32, MetadataError> {
+        let pallet = self.pallet(pallet_name)?;
+        let storage_metadata = pallet.storage(storage_name)?;
+        match &storage_metadata.ty {
+            StorageEntryType::Plain(plain) => Ok(plain.id()),
+            StorageEntryType::Map { value, .. } => Ok(value.id()),
+        }
+    }
+
+    /// The value of a storage entry which has never been written.
+    /// Only the entries with the `Default` modifier (`ValueQuery`) have one,
+    /// the `Optional` entries (`OptionQuery`) are `None` when absent.
+    pub fn storage_default_value<V: Decode>(
+        &self,
+        pallet_name: &str,
+        storage_name: &str,
+    ) -> Result<Option<V>, MetadataError> {
+        let storage_metadata =
+            self.pallet(pallet_name)?.storage(storage_name)?;
+        match storage_metadata.modifier {
+            StorageEntryModifier::Default => {
+                let value = V::decode(&mut storage_metadata.default.as_slice())
+                    .map_err(MetadataError::DefaultError)?;
+                Ok(Some(value))
+            }
+            StorageEntryModifier::Optional => Ok(None),
+        }
+    }
+
+    /// Decode the `bytes` as a value of type `type_id` without knowing its rust type
+    pub fn decode_dynamic(
+        &self,
+        type_id: u32,
+        bytes: &[u8],
+    ) -> Result<dynamic::Value, CodecError> {
+        dynamic::decode_value_exact(&self.metadata.types, type_id, bytes)
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
 pub struct PalletMetadata {
     pub index: u8,
     pub name: String,
+    /// Only available from V15 onwards
+    pub docs: Vec<String>,
     pub calls: HashMap<String, u8>,
     pub storage: HashMap<String, StorageEntryMetadata<PortableForm>>,
     pub constants: HashMap<String, PalletConstantMetadata<PortableForm>>,
@@ -320,6 +433,10 @@ pub enum InvalidMetadataError {
     InvalidPrefix,
     #[error("Invalid version")]
     InvalidVersion,
+    #[error("Unsupported metadata version: {0}")]
+    UnsupportedVersion(u32),
+    #[error("Legacy metadata is not in decoded form")]
+    LegacyNotDecoded,
     #[error("Type {0} missing from type registry")]
     MissingType(u32),
     #[error("Type {0} was not a variant/enum type")]
@@ -335,11 +452,99 @@ impl TryFrom<RuntimeMetadataPrefixed> for Metadata {
         if metadata.0 != META_RESERVED {
             return Err(InvalidMetadataError::InvalidPrefix);
         }
-        let metadata = match metadata.1 {
-            RuntimeMetadata::V14(meta) => meta,
-            _ => return Err(InvalidMetadataError::InvalidVersion),
+        match metadata.1 {
+            RuntimeMetadata::V14(meta) => Self::from_v14(meta, 14),
+            RuntimeMetadata::V15(meta) => Self::from_v15(meta),
+            #[cfg(feature = "legacy-metadata")]
+            RuntimeMetadata::V13(meta) => {
+                Self::from_v14(legacy::v13_to_v14(meta)?, 13)
+            }
+            #[cfg(feature = "legacy-metadata")]
+            RuntimeMetadata::V12(meta) => {
+                Self::from_v14(legacy::v12_to_v14(meta)?, 12)
+            }
+            other => {
+                Err(InvalidMetadataError::UnsupportedVersion(other.version()))
+            }
+        }
+    }
+}
+
+impl Metadata {
+    /// Convert the V15 metadata into the V14 layout, keeping the pallet docs, runtime apis and
+    /// outer enums which has no place in V14.
+    fn from_v15(
+        metadata: RuntimeMetadataV15,
+    ) -> Result<Self, InvalidMetadataError> {
+        let RuntimeMetadataV15 {
+            types,
+            pallets,
+            extrinsic,
+            ty,
+            apis,
+            outer_enums,
+            custom: _,
+        } = metadata;
+
+        let pallet_docs: HashMap<String, Vec<String>> = pallets
+            .iter()
+            .map(|pallet| (pallet.name.clone(), pallet.docs.clone()))
+            .collect();
+
+        let pallets = pallets
+            .into_iter()
+            .map(|pallet| {
+                RuntimePalletMetadata {
+                    name: pallet.name,
+                    storage: pallet.storage,
+                    calls: pallet.calls,
+                    event: pallet.event,
+                    constants: pallet.constants,
+                    error: pallet.error,
+                    index: pallet.index,
+                }
+            })
+            .collect();
+
+        // V15 no longer has the type of the whole extrinsic,
+        // the call type is used in its place
+        let extrinsic = ExtrinsicMetadata {
+            ty: extrinsic.call_ty,
+            version: extrinsic.version,
+            signed_extensions: extrinsic
+                .signed_extensions
+                .into_iter()
+                .map(|ext| {
+                    SignedExtensionMetadata {
+                        identifier: ext.identifier,
+                        ty: ext.ty,
+                        additional_signed: ext.additional_signed,
+                    }
+                })
+                .collect(),
+        };
+
+        let v14 = RuntimeMetadataV14 {
+            types,
+            pallets,
+            extrinsic,
+            ty,
         };
+        let mut metadata = Self::from_v14(v14, 15)?;
+        for (name, pallet) in metadata.pallets.iter_mut() {
+            if let Some(docs) = pallet_docs.get(name) {
+                pallet.docs = docs.clone();
+            }
+        }
+        metadata.apis = apis;
+        metadata.outer_enums = Some(outer_enums);
+        Ok(metadata)
+    }
 
+    fn from_v14(
+        metadata: RuntimeMetadataV14,
+        version: u32,
+    ) -> Result<Self, InvalidMetadataError> {
         let get_type_def_variant = |type_id: u32| {
             let ty = metadata
                 .types
@@ -393,6 +598,7 @@ impl TryFrom<RuntimeMetadataPrefixed> for Metadata {
                 let pallet_metadata = PalletMetadata {
                     index: pallet.index,
                     name: pallet.name.to_string(),
+                    docs: vec![],
                     calls,
                     storage,
                     constants,
@@ -457,6 +663,9 @@ impl TryFrom<RuntimeMetadataPrefixed> for Metadata {
             pallets,
             events,
             errors,
+            version,
+            apis: vec![],
+            outer_enums: None,
         })
     }
 }
@@ -500,6 +709,127 @@ impl Metadata {
             .get_map_prefix(storage_prefix)
     }
 
+    /// The map key of a `storage_key` of the map, the inverse of [`Metadata::storage_map_key`]
+    pub fn decode_storage_map_key<K: Decode>(
+        &self,
+        storage_prefix: &str,
+        storage_key_name: &str,
+        storage_key: &StorageKey,
+    ) -> Result<K, MetadataError> {
+        self.pallet(storage_prefix)?
+            .storage(storage_key_name)?
+            .get_map::<K>(storage_prefix)?
+            .decode_key(storage_key)
+    }
+
+    /// The keys of a `storage_key` of the double map,
+    /// the inverse of [`Metadata::storage_double_map_key`]
+    pub fn decode_storage_double_map_key<K: Decode, Q: Decode>(
+        &self,
+        storage_prefix: &str,
+        storage_key_name: &str,
+        storage_key: &StorageKey,
+    ) -> Result<(K, Q), MetadataError> {
+        self.pallet(storage_prefix)?
+            .storage(storage_key_name)?
+            .get_double_map::<K, Q>(storage_prefix)?
+            .decode_key(storage_key)
+    }
+
+    /// The keys of a `storage_key` of any map, decoded without knowing their rust types.
+    ///
+    /// The key is split into one component for each hasher of the storage entry,
+    /// which are decoded with the matching type of the key tuple.
+    pub fn decode_storage_key_dynamic(
+        &self,
+        storage_prefix: &str,
+        storage_key_name: &str,
+        storage_key: &StorageKey,
+    ) -> Result<Vec<dynamic::Value>, MetadataError> {
+        let entry = self.pallet(storage_prefix)?.storage(storage_key_name)?;
+        let (hashers, key_type_id) = match &entry.ty {
+            StorageEntryType::Map { hashers, key, .. } => (hashers, key.id()),
+            StorageEntryType::Plain(_) => {
+                return Err(MetadataError::StorageTypeError)
+            }
+        };
+        // the key of a map with several hashers is a tuple of the keys
+        let key_type_ids = if hashers.len() == 1 {
+            vec![key_type_id]
+        } else {
+            let key_type = self
+                .get_resolve_type(key_type_id)
+                .ok_or(MetadataError::TypeNotFound(key_type_id))?;
+            match key_type.type_def() {
+                TypeDef::Tuple(tuple) => {
+                    tuple.fields().iter().map(|field| field.id()).collect()
+                }
+                _ => return Err(MetadataError::StorageTypeError),
+            }
+        };
+        if key_type_ids.len() != hashers.len() {
+            return Err(MetadataError::StorageTypeError);
+        }
+
+        let mut input = storage::strip_prefix(
+            storage_key,
+            storage_prefix.as_bytes(),
+            storage_key_name.as_bytes(),
+        )?;
+        let keys = hashers
+            .iter()
+            .zip(key_type_ids)
+            .map(|(hasher, type_id)| {
+                storage::skip_key_hash(&mut input, hasher)?;
+                dynamic::decode_value(&self.metadata.types, type_id, &mut input)
+                    .map_err(MetadataError::StorageKeyDecodeError)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        storage::ensure_consumed(input)?;
+        Ok(keys)
+    }
+
+    /// The storage key of an n-map entry, `keys` is a tuple with one key per hasher
+    pub fn storage_nmap_key<K: StorageKeys>(
+        &self,
+        storage_prefix: &str,
+        storage_key_name: &str,
+        keys: &K,
+    ) -> Result<StorageKey, MetadataError> {
+        self.pallet(storage_prefix)?
+            .storage(storage_key_name)?
+            .get_nmap(storage_prefix)?
+            .key(keys)
+    }
+
+    /// The prefix of the n-map entries starting with the `keys`,
+    /// which can be fewer than the hashers
+    pub fn storage_nmap_key_prefix<K: StorageKeys>(
+        &self,
+        storage_prefix: &str,
+        storage_key_name: &str,
+        keys: &K,
+    ) -> Result<StorageKey, MetadataError> {
+        self.pallet(storage_prefix)?
+            .storage(storage_key_name)?
+            .get_nmap(storage_prefix)?
+            .prefix(keys)
+    }
+
+    /// The keys of a `storage_key` of the n-map,
+    /// the inverse of [`Metadata::storage_nmap_key`]
+    pub fn decode_storage_nmap_key<K: DecodeStorageKeys>(
+        &self,
+        storage_prefix: &str,
+        storage_key_name: &str,
+        storage_key: &StorageKey,
+    ) -> Result<K, MetadataError> {
+        self.pallet(storage_prefix)?
+            .storage(storage_key_name)?
+            .get_nmap(storage_prefix)?
+            .decode_key(storage_key)
+    }
+
     pub fn storage_double_map_key<K: Encode, Q: Encode>(
         &self,
         storage_prefix: &str,
@@ -514,3 +844,42 @@ impl Metadata {
             .key(first, second))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        test_utils::Post,
+        *,
+    };
+
+    #[test]
+    fn test_storage_default_value() {
+        let metadata = test_utils::metadata();
+        // ValueQuery entries have the default of the metadata
+        let item_counter: Option<u32> = metadata
+            .storage_default_value("ForumModule", "ItemCounter")
+            .unwrap();
+        assert_eq!(item_counter, Some(0));
+        let kids: Option<Vec<u32>> = metadata
+            .storage_default_value("ForumModule", "Kids")
+            .unwrap();
+        assert_eq!(kids, Some(vec![]));
+        // OptionQuery entries have none
+        let owner: Option<[u8; 32]> = metadata
+            .storage_default_value("ForumModule", "Owner")
+            .unwrap();
+        assert_eq!(owner, None);
+        let post: Option<Post> = metadata
+            .storage_default_value("ForumModule", "AllPosts")
+            .unwrap();
+        assert_eq!(post, None);
+    }
+
+    #[test]
+    fn test_storage_default_value_of_unknown_entry() {
+        let metadata = test_utils::metadata();
+        assert!(metadata
+            .storage_default_value::<u32>("ForumModule", "Unknown")
+            .is_err());
+    }
+}
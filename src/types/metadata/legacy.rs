@@ -0,0 +1,775 @@
+//! Convert the legacy V12 and V13 metadata into the V14 layout.
+//!
+//! Legacy metadata only has the names of the types as written in the source code,
+//! ie: `T::AccountId`, `Vec<u8>`. Each distinct name is registered into a synthetic type registry
+//! as an opaque type with no fields. The types can then be displayed, but can not be used to
+//! decode values dynamically.
+
+use super::InvalidMetadataError;
+use frame_metadata::{
+    decode_different::DecodeDifferent,
+    v12,
+    v13,
+    v14,
+};
+use scale_info::{
+    form::PortableForm,
+    interner::UntrackedSymbol,
+    Field,
+    Path,
+    PortableRegistryBuilder,
+    Type,
+    TypeDefComposite,
+    TypeDefTuple,
+    TypeDefVariant,
+    Variant,
+};
+use std::{
+    any::TypeId,
+    collections::HashMap,
+};
+
+/// A version neutral representation of the legacy module metadata
+struct LegacyModule {
+    name: String,
+    index: u8,
+    storage: Option<(String, Vec<LegacyStorageEntry>)>,
+    /// (name, arguments (name, type), docs)
+    calls: Option<Vec<(String, Vec<(String, String)>, Vec<String>)>>,
+    /// (name, argument types, docs)
+    events: Option<Vec<(String, Vec<String>, Vec<String>)>>,
+    /// (name, type, value, docs)
+    constants: Vec<(String, String, Vec<u8>, Vec<String>)>,
+    /// (name, docs)
+    errors: Vec<(String, Vec<String>)>,
+}
+
+struct LegacyStorageEntry {
+    name: String,
+    modifier: v14::StorageEntryModifier,
+    /// the key types with their hasher, empty for plain storage values
+    keys: Vec<(String, v14::StorageHasher)>,
+    value: String,
+    default: Vec<u8>,
+    docs: Vec<String>,
+}
+
+/// Registers the legacy type names into a type registry
+#[derive(Default)]
+struct LegacyTypes {
+    registry: PortableRegistryBuilder,
+    ids: HashMap<String, u32>,
+}
+
+impl LegacyTypes {
+    /// return the id of an opaque type named `name`
+    fn opaque(&mut self, name: &str) -> UntrackedSymbol<TypeId> {
+        if let Some(id) = self.ids.get(name) {
+            return UntrackedSymbol::from(*id);
+        }
+        let ty = Type::new(
+            Path::<PortableForm>::from_segments_unchecked([name.to_string()]),
+            vec![],
+            TypeDefComposite::<PortableForm>::new(vec![]),
+            vec![],
+        );
+        let id = self.registry.register_type(ty);
+        self.ids.insert(name.to_string(), id);
+        UntrackedSymbol::from(id)
+    }
+
+    /// register a tuple of the opaque types `names`
+    fn tuple(&mut self, names: &[&str]) -> UntrackedSymbol<TypeId> {
+        let fields: Vec<UntrackedSymbol<TypeId>> =
+            names.iter().map(|name| self.opaque(name)).collect();
+        let ty = Type::new(
+            Path::<PortableForm>::from_segments_unchecked(Vec::<String>::new()),
+            vec![],
+            TypeDefTuple::new_portable(fields),
+            vec![],
+        );
+        UntrackedSymbol::from(self.registry.register_type(ty))
+    }
+
+    /// register an enum named `name` with the `variants`
+    fn variant(
+        &mut self,
+        name: &str,
+        variants: Vec<Variant<PortableForm>>,
+    ) -> UntrackedSymbol<TypeId> {
+        let ty = Type::new(
+            Path::<PortableForm>::from_segments_unchecked([name.to_string()]),
+            vec![],
+            TypeDefVariant::new(variants),
+            vec![],
+        );
+        UntrackedSymbol::from(self.registry.register_type(ty))
+    }
+}
+
+fn decoded<B, O>(
+    value: DecodeDifferent<B, O>,
+) -> Result<O, InvalidMetadataError> {
+    match value {
+        DecodeDifferent::Decoded(value) => Ok(value),
+        DecodeDifferent::Encode(_) => {
+            Err(InvalidMetadataError::LegacyNotDecoded)
+        }
+    }
+}
+
+pub(super) fn v13_to_v14(
+    metadata: v13::RuntimeMetadataV13,
+) -> Result<v14::RuntimeMetadataV14, InvalidMetadataError> {
+    legacy_to_v14(
+        metadata.modules,
+        metadata.extrinsic.version,
+        metadata.extrinsic.signed_extensions,
+    )
+}
+
+pub(super) fn v12_to_v14(
+    metadata: v12::RuntimeMetadataV12,
+) -> Result<v14::RuntimeMetadataV14, InvalidMetadataError> {
+    legacy_to_v14(
+        metadata.modules,
+        metadata.extrinsic.version,
+        metadata.extrinsic.signed_extensions,
+    )
+}
+
+fn legacy_to_v14<B, M, S>(
+    modules: DecodeDifferent<B, Vec<M>>,
+    extrinsic_version: u8,
+    signed_extensions: Vec<DecodeDifferent<S, String>>,
+) -> Result<v14::RuntimeMetadataV14, InvalidMetadataError>
+where
+    M: IntoLegacyModule,
+{
+    let modules = decoded(modules)?
+        .into_iter()
+        .map(IntoLegacyModule::into_legacy_module)
+        .collect::<Result<Vec<_>, _>>()?;
+    let signed_extensions = signed_extensions
+        .into_iter()
+        .map(decoded)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(to_v14(modules, extrinsic_version, signed_extensions))
+}
+
+/// The module metadata of a legacy version
+trait IntoLegacyModule {
+    fn into_legacy_module(self) -> Result<LegacyModule, InvalidMetadataError>;
+}
+
+/// The storage hasher of a legacy version
+trait LegacyHasher {
+    fn to_v14(&self) -> v14::StorageHasher;
+}
+
+/// Implement [`IntoLegacyModule`] and [`LegacyHasher`] for the metadata `$version`.
+///
+/// The modules of V12 and V13 have the same layout but are distinct types, only their
+/// storage entry types differ: the match arms of the entry types which are only in
+/// `$version` are passed in `$extra_entry_types`, ie: `NMap` for V13.
+macro_rules! legacy_module {
+    ($version:ident { $($extra_entry_types:tt)* }) => {
+        impl LegacyHasher for $version::StorageHasher {
+            fn to_v14(&self) -> v14::StorageHasher {
+                match self {
+                    $version::StorageHasher::Blake2_128 => {
+                        v14::StorageHasher::Blake2_128
+                    }
+                    $version::StorageHasher::Blake2_256 => {
+                        v14::StorageHasher::Blake2_256
+                    }
+                    $version::StorageHasher::Blake2_128Concat => {
+                        v14::StorageHasher::Blake2_128Concat
+                    }
+                    $version::StorageHasher::Twox128 => {
+                        v14::StorageHasher::Twox128
+                    }
+                    $version::StorageHasher::Twox256 => {
+                        v14::StorageHasher::Twox256
+                    }
+                    $version::StorageHasher::Twox64Concat => {
+                        v14::StorageHasher::Twox64Concat
+                    }
+                    $version::StorageHasher::Identity => {
+                        v14::StorageHasher::Identity
+                    }
+                }
+            }
+        }
+
+        impl IntoLegacyModule for $version::ModuleMetadata {
+            fn into_legacy_module(
+                self,
+            ) -> Result<LegacyModule, InvalidMetadataError> {
+                let module = self;
+                let storage = match module.storage {
+                    Some(storage) => {
+                        let storage = decoded(storage)?;
+                        let entries = decoded(storage.entries)?
+                            .into_iter()
+                            .map(|entry| {
+                                let (keys, value) = match entry.ty {
+                                    $version::StorageEntryType::Plain(value) => {
+                                        (vec![], value)
+                                    }
+                                    $version::StorageEntryType::Map {
+                                        hasher,
+                                        key,
+                                        value,
+                                        ..
+                                    } => {
+                                        (
+                                            vec![(decoded(key)?, hasher.to_v14())],
+                                            value,
+                                        )
+                                    }
+                                    $version::StorageEntryType::DoubleMap {
+                                        hasher,
+                                        key1,
+                                        key2,
+                                        value,
+                                        key2_hasher,
+                                    } => {
+                                        (
+                                            vec![
+                                                (decoded(key1)?, hasher.to_v14()),
+                                                (
+                                                    decoded(key2)?,
+                                                    key2_hasher.to_v14(),
+                                                ),
+                                            ],
+                                            value,
+                                        )
+                                    }
+                                    $($extra_entry_types)*
+                                };
+                                Ok(LegacyStorageEntry {
+                                    name: decoded(entry.name)?,
+                                    modifier: match entry.modifier {
+                                        $version::StorageEntryModifier::Optional => {
+                                            v14::StorageEntryModifier::Optional
+                                        }
+                                        $version::StorageEntryModifier::Default => {
+                                            v14::StorageEntryModifier::Default
+                                        }
+                                    },
+                                    keys,
+                                    value: decoded(value)?,
+                                    default: decoded(entry.default)?,
+                                    docs: decoded(entry.documentation)?,
+                                })
+                            })
+                            .collect::<Result<Vec<_>, InvalidMetadataError>>()?;
+                        Some((decoded(storage.prefix)?, entries))
+                    }
+                    None => None,
+                };
+
+                let calls = match module.calls {
+                    Some(calls) => {
+                        Some(
+                            decoded(calls)?
+                                .into_iter()
+                                .map(|call| {
+                                    let args = decoded(call.arguments)?
+                                        .into_iter()
+                                        .map(|arg| {
+                                            Ok((decoded(arg.name)?, decoded(arg.ty)?))
+                                        })
+                                        .collect::<Result<
+                                            Vec<_>,
+                                            InvalidMetadataError,
+                                        >>()?;
+                                    Ok((
+                                        decoded(call.name)?,
+                                        args,
+                                        decoded(call.documentation)?,
+                                    ))
+                                })
+                                .collect::<Result<Vec<_>, InvalidMetadataError>>()?,
+                        )
+                    }
+                    None => None,
+                };
+
+                let events = match module.event {
+                    Some(events) => {
+                        Some(
+                            decoded(events)?
+                                .into_iter()
+                                .map(|event| {
+                                    Ok((
+                                        decoded(event.name)?,
+                                        decoded(event.arguments)?,
+                                        decoded(event.documentation)?,
+                                    ))
+                                })
+                                .collect::<Result<Vec<_>, InvalidMetadataError>>()?,
+                        )
+                    }
+                    None => None,
+                };
+
+                let constants = decoded(module.constants)?
+                    .into_iter()
+                    .map(|constant| {
+                        Ok((
+                            decoded(constant.name)?,
+                            decoded(constant.ty)?,
+                            decoded(constant.value)?,
+                            decoded(constant.documentation)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, InvalidMetadataError>>()?;
+
+                let errors = decoded(module.errors)?
+                    .into_iter()
+                    .map(|error| {
+                        Ok((decoded(error.name)?, decoded(error.documentation)?))
+                    })
+                    .collect::<Result<Vec<_>, InvalidMetadataError>>()?;
+
+                Ok(LegacyModule {
+                    name: decoded(module.name)?,
+                    index: module.index,
+                    storage,
+                    calls,
+                    events,
+                    constants,
+                    errors,
+                })
+            }
+        }
+    };
+}
+
+legacy_module!(v12 {});
+
+legacy_module!(v13 {
+    v13::StorageEntryType::NMap {
+        keys,
+        hashers,
+        value,
+    } => {
+        let keys = decoded(keys)?
+            .into_iter()
+            .zip(decoded(hashers)?.iter().map(LegacyHasher::to_v14))
+            .collect();
+        (keys, value)
+    }
+});
+
+/// Build the V14 metadata out of the legacy modules, registering every type name into a
+/// synthetic type registry.
+fn to_v14(
+    modules: Vec<LegacyModule>,
+    extrinsic_version: u8,
+    signed_extensions: Vec<String>,
+) -> v14::RuntimeMetadataV14 {
+    let mut types = LegacyTypes::default();
+
+    let pallets = modules
+        .into_iter()
+        .map(|module| {
+            let storage = module.storage.map(|(prefix, entries)| {
+                let entries = entries
+                    .into_iter()
+                    .map(|entry| {
+                        let value = types.opaque(&entry.value);
+                        let ty = match entry.keys.len() {
+                            0 => v14::StorageEntryType::Plain(value),
+                            1 => {
+                                v14::StorageEntryType::Map {
+                                    hashers: vec![entry.keys[0].1.clone()],
+                                    key: types.opaque(&entry.keys[0].0),
+                                    value,
+                                }
+                            }
+                            _ => {
+                                let key_names: Vec<&str> = entry
+                                    .keys
+                                    .iter()
+                                    .map(|(key, _)| key.as_str())
+                                    .collect();
+                                v14::StorageEntryType::Map {
+                                    hashers: entry
+                                        .keys
+                                        .iter()
+                                        .map(|(_, hasher)| hasher.clone())
+                                        .collect(),
+                                    key: types.tuple(&key_names),
+                                    value,
+                                }
+                            }
+                        };
+                        v14::StorageEntryMetadata {
+                            name: entry.name,
+                            modifier: entry.modifier,
+                            ty,
+                            default: entry.default,
+                            docs: entry.docs,
+                        }
+                    })
+                    .collect();
+                v14::PalletStorageMetadata { prefix, entries }
+            });
+
+            let calls = module.calls.map(|calls| {
+                let variants = calls
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, (name, args, docs))| {
+                        let fields = args
+                            .into_iter()
+                            .map(|(arg_name, arg_ty)| {
+                                Field::new(
+                                    Some(arg_name),
+                                    types.opaque(&arg_ty),
+                                    Some(arg_ty),
+                                    vec![],
+                                )
+                            })
+                            .collect();
+                        Variant::new(name, fields, index as u8, docs)
+                    })
+                    .collect();
+                v14::PalletCallMetadata {
+                    ty: types
+                        .variant(&format!("{}Call", module.name), variants),
+                }
+            });
+
+            let event = module.events.map(|events| {
+                let variants = events
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, (name, args, docs))| {
+                        let fields = args
+                            .into_iter()
+                            .map(|arg_ty| {
+                                Field::new(
+                                    None,
+                                    types.opaque(&arg_ty),
+                                    Some(arg_ty),
+                                    vec![],
+                                )
+                            })
+                            .collect();
+                        Variant::new(name, fields, index as u8, docs)
+                    })
+                    .collect();
+                v14::PalletEventMetadata {
+                    ty: types
+                        .variant(&format!("{}Event", module.name), variants),
+                }
+            });
+
+            let constants = module
+                .constants
+                .into_iter()
+                .map(|(name, ty, value, docs)| {
+                    v14::PalletConstantMetadata {
+                        name,
+                        ty: types.opaque(&ty),
+                        value,
+                        docs,
+                    }
+                })
+                .collect();
+
+            let error = if module.errors.is_empty() {
+                None
+            } else {
+                let variants = module
+                    .errors
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, (name, docs))| {
+                        Variant::new(name, vec![], index as u8, docs)
+                    })
+                    .collect();
+                Some(v14::PalletErrorMetadata {
+                    ty: types
+                        .variant(&format!("{}Error", module.name), variants),
+                })
+            };
+
+            v14::PalletMetadata {
+                name: module.name,
+                storage,
+                calls,
+                event,
+                constants,
+                error,
+                index: module.index,
+            }
+        })
+        .collect();
+
+    // the legacy signed extensions only have their names
+    let signed_extensions = signed_extensions
+        .into_iter()
+        .map(|identifier| {
+            v14::SignedExtensionMetadata {
+                ty: types.opaque(&identifier),
+                additional_signed: types
+                    .opaque(&format!("{}::AdditionalSigned", identifier)),
+                identifier,
+            }
+        })
+        .collect();
+
+    let extrinsic = v14::ExtrinsicMetadata {
+        ty: types.opaque("UncheckedExtrinsic"),
+        version: extrinsic_version,
+        signed_extensions,
+    };
+    let ty = types.opaque("Runtime");
+
+    v14::RuntimeMetadataV14 {
+        types: types.registry.finish(),
+        pallets,
+        extrinsic,
+        ty,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        metadata::{
+            Metadata,
+            MetadataError,
+        },
+        signed_extensions::SignedExtensions,
+    };
+    use codec::{
+        Decode,
+        Encode,
+    };
+    use frame_metadata::{
+        RuntimeMetadata,
+        RuntimeMetadataPrefixed,
+        META_RESERVED,
+    };
+    use std::convert::TryFrom;
+
+    /// A `Forum` module with a call, an event, a constant, an error and the storage entries
+    /// `ItemCounter` and `Kids`, plus the extra storage `$extra_entries` of `$version`
+    macro_rules! forum_module {
+        ($version:ident, [$($extra_entries:expr),*]) => {
+            $version::ModuleMetadata {
+                name: DecodeDifferent::Decoded("Forum".to_string()),
+                storage: Some(DecodeDifferent::Decoded($version::StorageMetadata {
+                    prefix: DecodeDifferent::Decoded("Forum".to_string()),
+                    entries: DecodeDifferent::Decoded(vec![
+                        $version::StorageEntryMetadata {
+                            name: DecodeDifferent::Decoded("ItemCounter".to_string()),
+                            modifier: $version::StorageEntryModifier::Default,
+                            ty: $version::StorageEntryType::Plain(
+                                DecodeDifferent::Decoded("u32".to_string()),
+                            ),
+                            default: DecodeDifferent::Decoded(0u32.encode()),
+                            documentation: DecodeDifferent::Decoded(vec![]),
+                        },
+                        $version::StorageEntryMetadata {
+                            name: DecodeDifferent::Decoded("Kids".to_string()),
+                            modifier: $version::StorageEntryModifier::Optional,
+                            ty: $version::StorageEntryType::Map {
+                                hasher: $version::StorageHasher::Twox64Concat,
+                                key: DecodeDifferent::Decoded("u32".to_string()),
+                                value: DecodeDifferent::Decoded("Vec<u32>".to_string()),
+                                unused: false,
+                            },
+                            default: DecodeDifferent::Decoded(vec![0]),
+                            documentation: DecodeDifferent::Decoded(vec![
+                                "The replies of an item".to_string(),
+                            ]),
+                        }
+                        $(, $extra_entries)*
+                    ]),
+                })),
+                calls: Some(DecodeDifferent::Decoded(vec![$version::FunctionMetadata {
+                    name: DecodeDifferent::Decoded("post_content".to_string()),
+                    arguments: DecodeDifferent::Decoded(vec![
+                        $version::FunctionArgumentMetadata {
+                            name: DecodeDifferent::Decoded("content".to_string()),
+                            ty: DecodeDifferent::Decoded("Vec<u8>".to_string()),
+                        },
+                    ]),
+                    documentation: DecodeDifferent::Decoded(vec![]),
+                }])),
+                event: Some(DecodeDifferent::Decoded(vec![$version::EventMetadata {
+                    name: DecodeDifferent::Decoded("Posted".to_string()),
+                    arguments: DecodeDifferent::Decoded(vec!["u32".to_string()]),
+                    documentation: DecodeDifferent::Decoded(vec![]),
+                }])),
+                constants: DecodeDifferent::Decoded(vec![
+                    $version::ModuleConstantMetadata {
+                        name: DecodeDifferent::Decoded("MaxComments".to_string()),
+                        ty: DecodeDifferent::Decoded("u32".to_string()),
+                        value: DecodeDifferent::Decoded(100u32.encode()),
+                        documentation: DecodeDifferent::Decoded(vec![]),
+                    },
+                ]),
+                errors: DecodeDifferent::Decoded(vec![$version::ErrorMetadata {
+                    name: DecodeDifferent::Decoded("NoSuchItem".to_string()),
+                    documentation: DecodeDifferent::Decoded(vec![]),
+                }]),
+                index: 7,
+            }
+        };
+    }
+
+    /// encode the legacy metadata and decode it back as it would be read from a node
+    fn round_trip(metadata: RuntimeMetadata) -> Metadata {
+        let bytes = RuntimeMetadataPrefixed(META_RESERVED, metadata).encode();
+        let prefixed = RuntimeMetadataPrefixed::decode(&mut bytes.as_slice())
+            .expect("must decode");
+        Metadata::try_from(prefixed).expect("must convert")
+    }
+
+    /// the parts of the `Forum` module which are the same in V12 and V13
+    fn assert_forum(metadata: &Metadata) {
+        assert!(metadata.is_legacy());
+        let forum = metadata.pallet("Forum").unwrap();
+        assert_eq!(forum.index, 7);
+        assert_eq!(forum.calls.get("post_content"), Some(&0));
+        assert_eq!(
+            metadata.pallet_call_index("Forum", "post_content").unwrap(),
+            [7, 0]
+        );
+
+        let item_counter = forum.storage("ItemCounter").unwrap();
+        assert_eq!(item_counter.modifier, v14::StorageEntryModifier::Default);
+        assert!(matches!(item_counter.ty, v14::StorageEntryType::Plain(_)));
+        let kids = forum.storage("Kids").unwrap();
+        assert_eq!(kids.docs, vec!["The replies of an item".to_string()]);
+        match &kids.ty {
+            v14::StorageEntryType::Map { hashers, .. } => {
+                assert_eq!(hashers, &vec![v14::StorageHasher::Twox64Concat])
+            }
+            v14::StorageEntryType::Plain(_) => panic!("Kids must be a map"),
+        }
+        let mut kids_key =
+            metadata.storage_map_key_prefix("Forum", "Kids").unwrap().0;
+        kids_key.extend(sp_core::twox_64(&42u32.encode()));
+        kids_key.extend(42u32.encode());
+        assert_eq!(
+            metadata.storage_map_key("Forum", "Kids", 42u32).unwrap().0,
+            kids_key
+        );
+
+        let max_comments = forum.constant("MaxComments").unwrap();
+        assert_eq!(max_comments.value, 100u32.encode());
+        assert_eq!(metadata.get_event(7, 0).unwrap().event(), "Posted");
+        assert_eq!(metadata.get_error(7, 0).unwrap().error(), "NoSuchItem");
+
+        let extensions: Vec<&str> = metadata
+            .get_runtime_metadata()
+            .extrinsic
+            .signed_extensions
+            .iter()
+            .map(|extension| extension.identifier.as_str())
+            .collect();
+        assert_eq!(extensions, vec!["CheckNonce", "ChargeTransactionPayment"]);
+    }
+
+    fn signed_extensions() -> Vec<DecodeDifferent<&'static str, String>> {
+        vec![
+            DecodeDifferent::Decoded("CheckNonce".to_string()),
+            DecodeDifferent::Decoded("ChargeTransactionPayment".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_unknown_legacy_signed_extension() {
+        let mut extensions = signed_extensions();
+        extensions.push(DecodeDifferent::Decoded("CheckVesting".to_string()));
+        let metadata =
+            round_trip(RuntimeMetadata::V12(v12::RuntimeMetadataV12 {
+                modules: DecodeDifferent::Decoded(vec![forum_module!(v12, [])]),
+                extrinsic: v12::ExtrinsicMetadata {
+                    version: 4,
+                    signed_extensions: extensions,
+                },
+            }));
+        // the placeholder type of `CheckVesting` has no fields but its payload is unknown
+        assert!(matches!(
+            SignedExtensions::default().identifiers(&metadata),
+            Err(MetadataError::UnknownSignedExtension(identifier))
+                if identifier == "CheckVesting"
+        ));
+    }
+
+    #[test]
+    fn test_v12_round_trip() {
+        let metadata =
+            round_trip(RuntimeMetadata::V12(v12::RuntimeMetadataV12 {
+                modules: DecodeDifferent::Decoded(vec![forum_module!(v12, [])]),
+                extrinsic: v12::ExtrinsicMetadata {
+                    version: 4,
+                    signed_extensions: signed_extensions(),
+                },
+            }));
+        assert_forum(&metadata);
+    }
+
+    #[test]
+    fn test_v13_round_trip() {
+        let votes = v13::StorageEntryMetadata {
+            name: DecodeDifferent::Decoded("Votes".to_string()),
+            modifier: v13::StorageEntryModifier::Optional,
+            ty: v13::StorageEntryType::NMap {
+                keys: DecodeDifferent::Decoded(vec![
+                    "u32".to_string(),
+                    "AccountId".to_string(),
+                ]),
+                hashers: DecodeDifferent::Decoded(vec![
+                    v13::StorageHasher::Twox64Concat,
+                    v13::StorageHasher::Blake2_128,
+                ]),
+                value: DecodeDifferent::Decoded("bool".to_string()),
+            },
+            default: DecodeDifferent::Decoded(vec![0]),
+            documentation: DecodeDifferent::Decoded(vec![]),
+        };
+        let metadata =
+            round_trip(RuntimeMetadata::V13(v13::RuntimeMetadataV13 {
+                modules: DecodeDifferent::Decoded(vec![forum_module!(
+                    v13,
+                    [votes]
+                )]),
+                extrinsic: v13::ExtrinsicMetadata {
+                    version: 4,
+                    signed_extensions: signed_extensions(),
+                },
+            }));
+        assert_forum(&metadata);
+
+        let votes = metadata.pallet("Forum").unwrap().storage("Votes").unwrap();
+        match &votes.ty {
+            v14::StorageEntryType::Map { hashers, key, .. } => {
+                assert_eq!(
+                    hashers,
+                    &vec![
+                        v14::StorageHasher::Twox64Concat,
+                        v14::StorageHasher::Blake2_128
+                    ]
+                );
+                let key = metadata.get_resolve_type(key.id()).unwrap();
+                assert!(matches!(
+                    key.type_def(),
+                    scale_info::TypeDef::Tuple(_)
+                ));
+            }
+            v14::StorageEntryType::Plain(_) => panic!("Votes must be a map"),
+        }
+    }
+}
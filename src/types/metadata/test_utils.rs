@@ -0,0 +1,149 @@
+//! A small V14 metadata of a forum chain, for the tests which need a [`Metadata`]
+use super::Metadata;
+use codec::{
+    Decode,
+    Encode,
+};
+use frame_metadata::{
+    v14::{
+        ExtrinsicMetadata,
+        PalletCallMetadata,
+        PalletMetadata,
+        PalletStorageMetadata,
+        RuntimeMetadataV14,
+        StorageEntryMetadata,
+        StorageEntryModifier,
+        StorageEntryType,
+        StorageHasher,
+    },
+    RuntimeMetadata,
+    RuntimeMetadataPrefixed,
+    META_RESERVED,
+};
+use scale_info::{
+    meta_type,
+    TypeInfo,
+};
+use std::convert::TryFrom;
+
+/// The pallet index of `Balances`
+pub(crate) const BALANCES: u8 = 6;
+/// The pallet index of `ForumModule`
+pub(crate) const FORUM: u8 = 8;
+
+#[allow(non_camel_case_types, dead_code)]
+#[derive(TypeInfo)]
+enum BalancesCall {
+    transfer { dest: [u8; 32], value: u128 },
+}
+
+#[allow(non_camel_case_types, dead_code)]
+#[derive(TypeInfo)]
+enum ForumCall {
+    post_content { content: Vec<u8> },
+}
+
+/// The value of `ForumModule.AllPosts`
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub(crate) struct Post {
+    pub post_id: u32,
+    pub content: Vec<u8>,
+}
+
+/// The `ForumModule` storage:
+/// - `ItemCounter`: a `ValueQuery` of `u32` which defaults to `0`
+/// - `Owner`: an `OptionQuery` of `[u8; 32]`
+/// - `AllPosts`: an `OptionQuery` map of `u32` to [`Post`], with `Twox64Concat` keys
+/// - `Kids`: a `ValueQuery` map of `u32` to `Vec<u32>`, with `Twox64Concat` keys
+fn forum_storage() -> PalletStorageMetadata {
+    let entry = |name, modifier, ty, default: Vec<u8>| {
+        StorageEntryMetadata {
+            name,
+            modifier,
+            ty,
+            default,
+            docs: vec![],
+        }
+    };
+    let map = |value| {
+        StorageEntryType::Map {
+            hashers: vec![StorageHasher::Twox64Concat],
+            key: meta_type::<u32>(),
+            value,
+        }
+    };
+    PalletStorageMetadata {
+        prefix: "ForumModule",
+        entries: vec![
+            entry(
+                "ItemCounter",
+                StorageEntryModifier::Default,
+                StorageEntryType::Plain(meta_type::<u32>()),
+                0u32.encode(),
+            ),
+            entry(
+                "Owner",
+                StorageEntryModifier::Optional,
+                StorageEntryType::Plain(meta_type::<[u8; 32]>()),
+                vec![0],
+            ),
+            entry(
+                "AllPosts",
+                StorageEntryModifier::Optional,
+                map(meta_type::<Post>()),
+                vec![0],
+            ),
+            entry(
+                "Kids",
+                StorageEntryModifier::Default,
+                map(meta_type::<Vec<u32>>()),
+                Vec::<u32>::new().encode(),
+            ),
+        ],
+    }
+}
+
+/// The runtime metadata as it is returned by `state_getMetadata`
+pub(crate) fn runtime_metadata() -> RuntimeMetadataPrefixed {
+    let pallets = vec![
+        PalletMetadata {
+            name: "Balances",
+            storage: None,
+            calls: Some(PalletCallMetadata {
+                ty: meta_type::<BalancesCall>(),
+            }),
+            event: None,
+            constants: vec![],
+            error: None,
+            index: BALANCES,
+        },
+        PalletMetadata {
+            name: "ForumModule",
+            storage: Some(forum_storage()),
+            calls: Some(PalletCallMetadata {
+                ty: meta_type::<ForumCall>(),
+            }),
+            event: None,
+            constants: vec![],
+            error: None,
+            index: FORUM,
+        },
+    ];
+    let extrinsic = ExtrinsicMetadata {
+        ty: meta_type::<()>(),
+        version: 4,
+        signed_extensions: vec![],
+    };
+    RuntimeMetadataPrefixed(
+        META_RESERVED,
+        RuntimeMetadata::V14(RuntimeMetadataV14::new(
+            pallets,
+            extrinsic,
+            meta_type::<()>(),
+        )),
+    )
+}
+
+pub(crate) fn metadata() -> Metadata {
+    Metadata::try_from(runtime_metadata()).expect("must be a valid metadata")
+}
@@ -0,0 +1,382 @@
+//! Encode the signed extra and additional signed bytes of a transaction
+//! following the signed extensions listed in the chain metadata.
+//!
+//! Each signed extension in `metadata.extrinsic.signed_extensions` is looked up by its identifier
+//! in the [`SignedExtensions`] registry. Chains with custom extensions can register their own
+//! [`ExtensionEncoder`].
+
+use crate::types::metadata::{
+    Encoded,
+    Metadata,
+    MetadataError,
+};
+use codec::{
+    Compact,
+    Encode,
+};
+use scale_info::{
+    PortableRegistry,
+    TypeDef,
+};
+use sp_core::H256;
+use sp_runtime::generic::Era;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+};
+
+/// The values which are used by the signed extensions to encode
+/// their signed extra and additional signed bytes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtensionParams {
+    pub spec_version: u32,
+    pub transaction_version: u32,
+    pub genesis_hash: H256,
+    /// the block hash at which the era of a mortal transaction starts,
+    /// this is the genesis hash for immortal transactions
+    pub mortality_checkpoint: H256,
+    pub era: Era,
+    pub nonce: u32,
+    pub tip: u128,
+    /// the asset in which the tip is paid, `None` for the native token
+    pub tip_asset: Option<u32>,
+    /// the hash of the metadata when `CheckMetadataHash` is enabled
+    pub metadata_hash: Option<[u8; 32]>,
+}
+
+/// Encodes the signed extra and the additional signed of a signed extension.
+/// Implement this for the custom signed extension of a chain
+/// and register it with [`SignedExtensions::register`].
+pub trait ExtensionEncoder: Send + Sync {
+    /// encode the extra which is sent along with the transaction
+    fn encode_extra_to(&self, _params: &ExtensionParams, _extra: &mut Vec<u8>) {
+    }
+
+    /// encode the additional data which is not sent along with the transaction
+    /// but is included in the signed payload
+    fn encode_additional_signed_to(
+        &self,
+        _params: &ExtensionParams,
+        _additional: &mut Vec<u8>,
+    ) {
+    }
+}
+
+/// `frame_system::CheckNonZeroSender`, `frame_system::CheckWeight`
+/// and other extensions which encodes to nothing
+pub struct NoopExtension;
+
+impl ExtensionEncoder for NoopExtension {}
+
+/// `frame_system::CheckSpecVersion`
+pub struct CheckSpecVersion;
+
+impl ExtensionEncoder for CheckSpecVersion {
+    fn encode_additional_signed_to(
+        &self,
+        params: &ExtensionParams,
+        additional: &mut Vec<u8>,
+    ) {
+        params.spec_version.encode_to(additional);
+    }
+}
+
+/// `frame_system::CheckTxVersion`
+pub struct CheckTxVersion;
+
+impl ExtensionEncoder for CheckTxVersion {
+    fn encode_additional_signed_to(
+        &self,
+        params: &ExtensionParams,
+        additional: &mut Vec<u8>,
+    ) {
+        params.transaction_version.encode_to(additional);
+    }
+}
+
+/// `frame_system::CheckGenesis`
+pub struct CheckGenesis;
+
+impl ExtensionEncoder for CheckGenesis {
+    fn encode_additional_signed_to(
+        &self,
+        params: &ExtensionParams,
+        additional: &mut Vec<u8>,
+    ) {
+        params.genesis_hash.encode_to(additional);
+    }
+}
+
+/// `frame_system::CheckMortality`, formerly `frame_system::CheckEra`
+pub struct CheckMortality;
+
+impl ExtensionEncoder for CheckMortality {
+    fn encode_extra_to(&self, params: &ExtensionParams, extra: &mut Vec<u8>) {
+        params.era.encode_to(extra);
+    }
+
+    fn encode_additional_signed_to(
+        &self,
+        params: &ExtensionParams,
+        additional: &mut Vec<u8>,
+    ) {
+        params.mortality_checkpoint.encode_to(additional);
+    }
+}
+
+/// `frame_system::CheckNonce`
+pub struct CheckNonce;
+
+impl ExtensionEncoder for CheckNonce {
+    fn encode_extra_to(&self, params: &ExtensionParams, extra: &mut Vec<u8>) {
+        Compact(params.nonce).encode_to(extra);
+    }
+}
+
+/// `pallet_transaction_payment::ChargeTransactionPayment`
+pub struct ChargeTransactionPayment;
+
+impl ExtensionEncoder for ChargeTransactionPayment {
+    fn encode_extra_to(&self, params: &ExtensionParams, extra: &mut Vec<u8>) {
+        Compact(params.tip).encode_to(extra);
+    }
+}
+
+/// `pallet_asset_tx_payment::ChargeAssetTxPayment`
+pub struct ChargeAssetTxPayment;
+
+impl ExtensionEncoder for ChargeAssetTxPayment {
+    fn encode_extra_to(&self, params: &ExtensionParams, extra: &mut Vec<u8>) {
+        (Compact(params.tip), params.tip_asset).encode_to(extra);
+    }
+}
+
+/// `frame_metadata_hash_extension::CheckMetadataHash`
+pub struct CheckMetadataHash;
+
+impl ExtensionEncoder for CheckMetadataHash {
+    fn encode_extra_to(&self, params: &ExtensionParams, extra: &mut Vec<u8>) {
+        // the mode is encoded as an enum: 0 = Disabled, 1 = Enabled
+        let mode: u8 = if params.metadata_hash.is_some() { 1 } else { 0 };
+        mode.encode_to(extra);
+    }
+
+    fn encode_additional_signed_to(
+        &self,
+        params: &ExtensionParams,
+        additional: &mut Vec<u8>,
+    ) {
+        params.metadata_hash.encode_to(additional);
+    }
+}
+
+/// The encoders of the signed extensions, keyed by their identifier in the metadata
+#[derive(Clone)]
+pub struct SignedExtensions {
+    encoders: HashMap<String, Arc<dyn ExtensionEncoder>>,
+}
+
+impl Default for SignedExtensions {
+    /// A registry with the signed extensions from frame
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("CheckNonZeroSender", NoopExtension);
+        registry.register("CheckSpecVersion", CheckSpecVersion);
+        registry.register("CheckTxVersion", CheckTxVersion);
+        registry.register("CheckGenesis", CheckGenesis);
+        registry.register("CheckMortality", CheckMortality);
+        registry.register("CheckEra", CheckMortality);
+        registry.register("CheckNonce", CheckNonce);
+        registry.register("CheckWeight", NoopExtension);
+        registry.register("ChargeTransactionPayment", ChargeTransactionPayment);
+        registry.register("ChargeAssetTxPayment", ChargeAssetTxPayment);
+        registry.register("CheckMetadataHash", CheckMetadataHash);
+        registry
+    }
+}
+
+impl SignedExtensions {
+    /// A registry without any signed extension encoder
+    pub fn empty() -> Self {
+        Self {
+            encoders: HashMap::new(),
+        }
+    }
+
+    /// Register the `encoder` for the signed extension `identifier`,
+    /// replacing the previous encoder of the same identifier.
+    pub fn register<E>(&mut self, identifier: &str, encoder: E)
+    where
+        E: ExtensionEncoder + 'static,
+    {
+        self.encoders
+            .insert(identifier.to_string(), Arc::new(encoder));
+    }
+
+    /// Encode the signed extra and the additional signed of the signed extensions listed in the
+    /// metadata, in the same order as they are declared.
+    ///
+    /// Signed extensions which has no encoder are skipped when both their extra and additional
+    /// signed types encode to nothing, otherwise this is an error.
+    pub fn encode(
+        &self,
+        metadata: &Metadata,
+        params: &ExtensionParams,
+    ) -> Result<(Encoded, Encoded), MetadataError> {
+        let identifiers = self.identifiers(metadata)?;
+        self.encode_identifiers(&identifiers, params)
+    }
+
+    /// The identifiers of the signed extensions listed in the metadata which are encoded
+    /// by this registry, skipping the unknown ones which encodes to nothing.
+    ///
+    /// The types of legacy metadata are placeholders without fields, so an unknown signed
+    /// extension of a legacy chain is always an error.
+    pub fn identifiers(
+        &self,
+        metadata: &Metadata,
+    ) -> Result<Vec<String>, MetadataError> {
+        let types = &metadata.metadata.types;
+        let mut identifiers = vec![];
+        for ext in metadata.metadata.extrinsic.signed_extensions.iter() {
+            if self.encoders.contains_key(&ext.identifier) {
+                identifiers.push(ext.identifier.clone());
+            } else if metadata.is_legacy()
+                || !is_empty_type(types, ext.ty.id(), 0)
+                || !is_empty_type(types, ext.additional_signed.id(), 0)
+            {
+                return Err(MetadataError::UnknownSignedExtension(
+                    ext.identifier.clone(),
+                ));
+            }
+        }
+        Ok(identifiers)
+    }
+
+    /// Encode the signed extra and the additional signed of the signed extensions
+    /// `identifiers`, in the given order. This doesn't need the metadata, which is useful
+    /// when signing offline.
+    ///
+    /// A tip in an asset is an error when there is no `ChargeAssetTxPayment` to pay it,
+    /// rather than paying the same amount in the native token.
+    pub fn encode_identifiers(
+        &self,
+        identifiers: &[String],
+        params: &ExtensionParams,
+    ) -> Result<(Encoded, Encoded), MetadataError> {
+        if let Some(tip_asset) = params.tip_asset {
+            if !identifiers.iter().any(|id| id == "ChargeAssetTxPayment") {
+                return Err(MetadataError::AssetTipNotSupported(tip_asset));
+            }
+        }
+        let mut extra = vec![];
+        let mut additional = vec![];
+        for identifier in identifiers {
+            let encoder = self.encoders.get(identifier).ok_or_else(|| {
+                MetadataError::UnknownSignedExtension(identifier.clone())
+            })?;
+            encoder.encode_extra_to(params, &mut extra);
+            encoder.encode_additional_signed_to(params, &mut additional);
+        }
+        Ok((Encoded(extra), Encoded(additional)))
+    }
+}
+
+/// Returns true if the type `id` encodes to nothing, ie: `()`, `PhantomData<T>`
+/// or a struct with only such fields
+pub(crate) fn is_empty_type(
+    registry: &PortableRegistry,
+    id: u32,
+    depth: usize,
+) -> bool {
+    // guard against self-referencing types
+    if depth > 32 {
+        return false;
+    }
+    match registry.resolve(id).map(|ty| ty.type_def()) {
+        Some(TypeDef::Tuple(tuple)) => {
+            tuple
+                .fields()
+                .iter()
+                .all(|field| is_empty_type(registry, field.id(), depth + 1))
+        }
+        Some(TypeDef::Composite(composite)) => {
+            composite.fields().iter().all(|field| {
+                is_empty_type(registry, field.ty().id(), depth + 1)
+            })
+        }
+        Some(TypeDef::Array(array)) => {
+            array.len() == 0
+                || is_empty_type(registry, array.type_param().id(), depth + 1)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale_info::{
+        meta_type,
+        Registry,
+    };
+    use std::marker::PhantomData;
+
+    #[test]
+    fn test_empty_types() {
+        let mut registry = Registry::new();
+        let unit = registry.register_type(&meta_type::<()>()).id();
+        let phantom = registry
+            .register_type(&meta_type::<PhantomData<u32>>())
+            .id();
+        let nested = registry.register_type(&meta_type::<((), [u8; 0])>()).id();
+        let non_empty = registry.register_type(&meta_type::<(u32, ())>()).id();
+        let registry = PortableRegistry::from(registry);
+        assert!(is_empty_type(&registry, unit, 0));
+        assert!(is_empty_type(&registry, phantom, 0));
+        assert!(is_empty_type(&registry, nested, 0));
+        assert!(!is_empty_type(&registry, non_empty, 0));
+    }
+
+    fn params() -> ExtensionParams {
+        ExtensionParams {
+            spec_version: 100,
+            transaction_version: 1,
+            genesis_hash: H256::zero(),
+            mortality_checkpoint: H256::zero(),
+            era: Era::immortal(),
+            nonce: 3,
+            tip: 5,
+            tip_asset: Some(7),
+            metadata_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_charge_asset_tx_payment() {
+        let mut extra = vec![];
+        ChargeAssetTxPayment.encode_extra_to(&params(), &mut extra);
+        assert_eq!(extra, (Compact(5u128), Some(7u32)).encode());
+    }
+
+    #[test]
+    fn test_asset_tip_without_charge_asset_tx_payment() {
+        let registry = SignedExtensions::default();
+        let identifiers = vec![
+            "CheckNonce".to_string(),
+            "ChargeTransactionPayment".to_string(),
+        ];
+        assert!(matches!(
+            registry.encode_identifiers(&identifiers, &params()),
+            Err(MetadataError::AssetTipNotSupported(7))
+        ));
+        let native_tip = ExtensionParams {
+            tip_asset: None,
+            ..params()
+        };
+        let (extra, _additional) = registry
+            .encode_identifiers(&identifiers, &native_tip)
+            .unwrap();
+        assert_eq!(extra.0, (Compact(3u32), Compact(5u128)).encode());
+    }
+}
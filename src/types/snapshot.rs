@@ -0,0 +1,455 @@
+//! A portable snapshot of the storage at one block, for reproducible bug reports and
+//! offline tests.
+//!
+//! The snapshot file is the SCALE encoding of [`StorageSnapshot`]: the raw key/value pairs,
+//! the runtime metadata and the header of the block.
+
+use crate::{
+    api::StorageBackend,
+    error::Error,
+    types::storage::{
+        DecodeStorageKeys,
+        StorageKeys,
+    },
+    Metadata,
+};
+use async_trait::async_trait;
+use codec::{
+    Decode,
+    Encode,
+};
+use frame_metadata::RuntimeMetadataPrefixed;
+use sp_core::{
+    storage::StorageKey,
+    H256,
+};
+use sp_runtime::{
+    generic,
+    traits::{
+        BlakeTwo256,
+        Header as _,
+    },
+};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    ops::Bound,
+};
+
+/// The version of the snapshot format, bumped on incompatible changes
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+pub type Header = generic::Header<u64, BlakeTwo256>;
+
+/// The storage entries of some pallets, or of the whole state, at one block
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct StorageSnapshot {
+    pub version: u32,
+    pub block_hash: H256,
+    pub header: Header,
+    /// the SCALE encoded `RuntimeMetadataPrefixed` of the runtime at the block
+    pub metadata: Vec<u8>,
+    /// the key prefixes which were exported, a single empty prefix for the whole state
+    pub prefixes: Vec<Vec<u8>>,
+    /// the raw key/value pairs
+    pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl StorageSnapshot {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let snapshot = Self::decode(&mut &*bytes)?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(Error::InvalidSnapshot(format!(
+                "unsupported version {}, expecting {}",
+                snapshot.version, SNAPSHOT_VERSION
+            )));
+        }
+        Ok(snapshot)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        Ok(std::fs::write(path, self.to_bytes())?)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+/// Serves the storage reads of [`StorageBackend`] from a [`StorageSnapshot`] instead of a node,
+/// all the reads are at the block of the snapshot.
+pub struct SnapshotStorage {
+    block_hash: H256,
+    header: Header,
+    metadata: Metadata,
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl SnapshotStorage {
+    /// Check the header against the block hash and decode the metadata of the `snapshot`
+    pub fn new(snapshot: StorageSnapshot) -> Result<Self, Error> {
+        if snapshot.header.hash() != snapshot.block_hash {
+            return Err(Error::InvalidSnapshot(format!(
+                "the header does not hash to {:?}",
+                snapshot.block_hash
+            )));
+        }
+        let metadata =
+            RuntimeMetadataPrefixed::decode(&mut snapshot.metadata.as_slice())?;
+        Ok(Self {
+            block_hash: snapshot.block_hash,
+            header: snapshot.header,
+            metadata: Metadata::try_from(metadata)?,
+            entries: snapshot.entries.into_iter().collect(),
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        Self::new(StorageSnapshot::load(path)?)
+    }
+
+    pub fn block_hash(&self) -> H256 {
+        self.block_hash
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// `count` storage keys which starts with `prefix`, after the `start_key`
+    fn storage_keys_paged(
+        &self,
+        prefix: &StorageKey,
+        count: u32,
+        start_key: Option<StorageKey>,
+    ) -> Vec<StorageKey> {
+        // a start key before the prefix starts the page at the first key of the prefix
+        let start = match start_key {
+            Some(start_key) if start_key.0 >= prefix.0 => {
+                Bound::Excluded(start_key.0)
+            }
+            _ => Bound::Included(prefix.0.clone()),
+        };
+        self.entries
+            .range((start, Bound::Unbounded))
+            .map(|(key, _value)| key)
+            .take_while(|key| key.starts_with(&prefix.0))
+            .take(count as usize)
+            .map(|key| StorageKey(key.clone()))
+            .collect()
+    }
+
+    /// All the entries of the storage map `module.storage_name` in the snapshot
+    pub fn iter_storage_map<K, V>(
+        &self,
+        module: &str,
+        storage_name: &str,
+    ) -> Result<Vec<(K, V)>, Error>
+    where
+        K: Decode,
+        V: Decode,
+    {
+        let prefix =
+            self.metadata.storage_map_key_prefix(module, storage_name)?;
+        self.entries_with_prefix(&prefix)
+            .map(|(key, value)| {
+                let key = self.metadata.decode_storage_map_key(
+                    module,
+                    storage_name,
+                    &StorageKey(key.clone()),
+                )?;
+                Ok((key, V::decode(&mut value.as_slice())?))
+            })
+            .collect()
+    }
+
+    /// The entries of the n-map `module.storage_name` starting with the `partial_keys`
+    pub fn iter_storage_nmap<P, K, V>(
+        &self,
+        module: &str,
+        storage_name: &str,
+        partial_keys: P,
+    ) -> Result<Vec<(K, V)>, Error>
+    where
+        P: StorageKeys,
+        K: DecodeStorageKeys,
+        V: Decode,
+    {
+        let prefix = self.metadata.storage_nmap_key_prefix(
+            module,
+            storage_name,
+            &partial_keys,
+        )?;
+        self.entries_with_prefix(&prefix)
+            .map(|(key, value)| {
+                let key = self.metadata.decode_storage_nmap_key(
+                    module,
+                    storage_name,
+                    &StorageKey(key.clone()),
+                )?;
+                Ok((key, V::decode(&mut value.as_slice())?))
+            })
+            .collect()
+    }
+
+    fn entries_with_prefix<'a>(
+        &'a self,
+        prefix: &'a StorageKey,
+    ) -> impl Iterator<Item = (&'a Vec<u8>, &'a Vec<u8>)> + 'a {
+        self.entries
+            .range(prefix.0.clone()..)
+            .take_while(move |(key, _value)| key.starts_with(&prefix.0))
+    }
+}
+
+/// Reads at the block of the snapshot, the storage keys are computed with its metadata
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl StorageBackend for SnapshotStorage {
+    fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    async fn fetch_opaque_storage_by_key_hash(
+        &self,
+        storage_key: StorageKey,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.entries.get(&storage_key.0).cloned())
+    }
+
+    async fn fetch_opaque_storage_many(
+        &self,
+        storage_keys: &[StorageKey],
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        Ok(storage_keys
+            .iter()
+            .map(|storage_key| self.entries.get(&storage_key.0).cloned())
+            .collect())
+    }
+
+    async fn fetch_storage_keys_paged_by_prefix(
+        &self,
+        prefix: StorageKey,
+        count: u32,
+        start_key: Option<StorageKey>,
+    ) -> Result<Option<Vec<StorageKey>>, Error> {
+        Ok(Some(self.storage_keys_paged(&prefix, count, start_key)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::metadata::{
+        test_utils::{
+            self,
+            Post,
+        },
+        MetadataError,
+    };
+
+    fn snapshot(version: u32) -> StorageSnapshot {
+        let header = Header::new(
+            7,
+            H256::repeat_byte(1),
+            H256::repeat_byte(2),
+            H256::repeat_byte(3),
+            Default::default(),
+        );
+        StorageSnapshot {
+            version,
+            block_hash: header.hash(),
+            header,
+            metadata: vec![],
+            prefixes: vec![vec![]],
+            entries: vec![(vec![1, 2], vec![3]), (vec![1, 3], vec![])],
+        }
+    }
+
+    #[test]
+    fn snapshot_roundtrip() {
+        let snapshot = snapshot(SNAPSHOT_VERSION);
+        let decoded = StorageSnapshot::from_bytes(&snapshot.to_bytes());
+        assert_eq!(decoded.unwrap(), snapshot);
+    }
+
+    #[test]
+    fn reject_other_versions() {
+        let snapshot = snapshot(SNAPSHOT_VERSION + 1);
+        assert!(matches!(
+            StorageSnapshot::from_bytes(&snapshot.to_bytes()),
+            Err(Error::InvalidSnapshot(_))
+        ));
+    }
+
+    fn post(post_id: u32) -> Post {
+        Post {
+            post_id,
+            content: format!("post {}", post_id).into_bytes(),
+        }
+    }
+
+    /// a snapshot of the forum with the posts 1 and 2, and the replies to post 1
+    fn forum_snapshot() -> StorageSnapshot {
+        let metadata = test_utils::metadata();
+        let entry =
+            |storage_key: StorageKey, value: Vec<u8>| (storage_key.0, value);
+        let header = Header::new(
+            7,
+            H256::repeat_byte(1),
+            H256::repeat_byte(2),
+            H256::repeat_byte(3),
+            Default::default(),
+        );
+        StorageSnapshot {
+            version: SNAPSHOT_VERSION,
+            block_hash: header.hash(),
+            header,
+            metadata: test_utils::runtime_metadata().encode(),
+            prefixes: vec![sp_core::twox_128(b"ForumModule").to_vec()],
+            entries: vec![
+                entry(
+                    metadata
+                        .storage_value_key("ForumModule", "ItemCounter")
+                        .unwrap(),
+                    3u32.encode(),
+                ),
+                entry(
+                    metadata
+                        .storage_map_key("ForumModule", "AllPosts", 1u32)
+                        .unwrap(),
+                    post(1).encode(),
+                ),
+                entry(
+                    metadata
+                        .storage_map_key("ForumModule", "AllPosts", 2u32)
+                        .unwrap(),
+                    post(2).encode(),
+                ),
+                entry(
+                    metadata
+                        .storage_map_key("ForumModule", "Kids", 1u32)
+                        .unwrap(),
+                    vec![3u32].encode(),
+                ),
+            ],
+        }
+    }
+
+    fn all_posts_keys(storage: &SnapshotStorage) -> Vec<StorageKey> {
+        let mut keys: Vec<StorageKey> = [1u32, 2]
+            .iter()
+            .map(|post_id| {
+                storage
+                    .metadata()
+                    .storage_map_key("ForumModule", "AllPosts", post_id)
+                    .unwrap()
+            })
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    #[tokio::test]
+    async fn read_the_snapshot_with_its_metadata() {
+        let storage = SnapshotStorage::new(forum_snapshot()).unwrap();
+        assert_eq!(storage.header().number, 7);
+        let item_counter: Option<u32> = storage
+            .fetch_storage_value("ForumModule", "ItemCounter")
+            .await
+            .unwrap();
+        assert_eq!(item_counter, Some(3));
+        let first_post: Option<Post> = storage
+            .fetch_storage_map("ForumModule", "AllPosts", 1u32)
+            .await
+            .unwrap();
+        assert_eq!(first_post, Some(post(1)));
+        // the fetch methods return the stored value, the defaults are explicit
+        let kids: Option<Vec<u32>> = storage
+            .fetch_storage_map("ForumModule", "Kids", 2u32)
+            .await
+            .unwrap();
+        assert_eq!(kids, None);
+        let kids_key = storage
+            .metadata()
+            .storage_map_key("ForumModule", "Kids", 2u32)
+            .unwrap();
+        let kids: Option<Vec<u32>> = storage
+            .fetch_or_default("ForumModule", "Kids", kids_key)
+            .await
+            .unwrap();
+        assert_eq!(kids, Some(vec![]));
+        // the key of another entry is not read with the default of `Kids`
+        let post_key = storage
+            .metadata()
+            .storage_map_key("ForumModule", "AllPosts", 9u32)
+            .unwrap();
+        let kids: Result<Option<Vec<u32>>, _> = storage
+            .fetch_or_default("ForumModule", "Kids", post_key)
+            .await;
+        assert!(matches!(
+            kids,
+            Err(Error::MetadataError(MetadataError::InvalidStorageKey(_)))
+        ));
+    }
+
+    #[test]
+    fn reject_a_header_of_another_block() {
+        let mut snapshot = forum_snapshot();
+        snapshot.block_hash = H256::repeat_byte(9);
+        assert!(matches!(
+            SnapshotStorage::new(snapshot),
+            Err(Error::InvalidSnapshot(_))
+        ));
+    }
+
+    #[test]
+    fn keys_paged_stay_within_the_prefix() {
+        let storage = SnapshotStorage::new(forum_snapshot()).unwrap();
+        let prefix = storage
+            .metadata()
+            .storage_map_key_prefix("ForumModule", "AllPosts")
+            .unwrap();
+        let keys = all_posts_keys(&storage);
+
+        // the keys of ItemCounter and Kids are not included
+        assert_eq!(storage.storage_keys_paged(&prefix, 10, None), keys);
+        assert_eq!(storage.storage_keys_paged(&prefix, 1, None), keys[..1]);
+        assert_eq!(
+            storage.storage_keys_paged(&prefix, 10, Some(keys[0].clone())),
+            keys[1..]
+        );
+        assert!(storage
+            .storage_keys_paged(&prefix, 10, Some(keys[1].clone()))
+            .is_empty());
+        // a start key before the prefix starts at the first key of the prefix
+        assert_eq!(
+            storage.storage_keys_paged(&prefix, 10, Some(StorageKey(vec![]))),
+            keys
+        );
+        assert!(storage.storage_keys_paged(&prefix, 0, None).is_empty());
+    }
+
+    #[test]
+    fn iter_storage_map_decodes_the_keys() {
+        let storage = SnapshotStorage::new(forum_snapshot()).unwrap();
+        let mut all_posts: Vec<(u32, Post)> =
+            storage.iter_storage_map("ForumModule", "AllPosts").unwrap();
+        all_posts.sort_by_key(|(post_id, _post)| *post_id);
+        assert_eq!(all_posts, vec![(1, post(1)), (2, post(2))]);
+        let all_kids: Vec<(u32, Vec<u32>)> =
+            storage.iter_storage_map("ForumModule", "Kids").unwrap();
+        assert_eq!(all_kids, vec![(1, vec![3])]);
+    }
+}
@@ -16,14 +16,20 @@
 //! This file is **not** from subxt.
 
 use crate::types::metadata::MetadataError;
-use codec::Encode;
-use frame_metadata::{
+use codec::{
+    Decode,
+    Encode,
+};
+use frame_metadata::v14::{
     StorageEntryMetadata,
     StorageEntryType,
     StorageHasher,
 };
 use scale_info::form::PortableForm;
-use sp_core::storage::StorageKey;
+use sp_core::{
+    storage::StorageKey,
+    H256,
+};
 use std::marker::PhantomData;
 
 #[derive(Clone, Debug)]
@@ -57,6 +63,23 @@ impl<K: Encode> StorageMap<K> {
     }
 }
 
+impl<K: Decode> StorageMap<K> {
+    /// The map key of a `storage_key` of this map, the inverse of [`StorageMap::key`]
+    pub fn decode_key(
+        &self,
+        storage_key: &StorageKey,
+    ) -> Result<K, MetadataError> {
+        let mut input = strip_prefix(
+            storage_key,
+            &self.module_prefix,
+            &self.storage_prefix,
+        )?;
+        let key = key_unhash(&mut input, &self.hasher)?;
+        ensure_consumed(input)?;
+        Ok(key)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct StorageDoubleMap<K, Q> {
     _marker: PhantomData<K>,
@@ -77,13 +100,147 @@ impl<K: Encode, Q: Encode> StorageDoubleMap<K, Q> {
     }
 }
 
+impl<K: Decode, Q: Decode> StorageDoubleMap<K, Q> {
+    /// The keys of a `storage_key` of this double map,
+    /// the inverse of [`StorageDoubleMap::key`]
+    pub fn decode_key(
+        &self,
+        storage_key: &StorageKey,
+    ) -> Result<(K, Q), MetadataError> {
+        let mut input = strip_prefix(
+            storage_key,
+            &self.module_prefix,
+            &self.storage_prefix,
+        )?;
+        let key1 = key_unhash(&mut input, &self.hasher)?;
+        let key2 = key_unhash(&mut input, &self.key2_hasher)?;
+        ensure_consumed(input)?;
+        Ok((key1, key2))
+    }
+}
+
+/// A map with any number of keys, each key has its own hasher
+#[derive(Clone, Debug)]
+pub struct StorageNMap {
+    module_prefix: Vec<u8>,
+    storage_prefix: Vec<u8>,
+    hashers: Vec<StorageHasher>,
+}
+
+impl StorageNMap {
+    /// The storage key of the entry at `keys`, there must be one key for each hasher
+    pub fn key<K: StorageKeys>(
+        &self,
+        keys: &K,
+    ) -> Result<StorageKey, MetadataError> {
+        let encoded_keys = keys.encoded_keys();
+        check_key_count(self.hashers.len(), encoded_keys.len())?;
+        Ok(self.hash_keys(&encoded_keys))
+    }
+
+    /// The prefix of all the entries starting with the `keys`,
+    /// there can be fewer keys than hashers
+    pub fn prefix<K: StorageKeys>(
+        &self,
+        keys: &K,
+    ) -> Result<StorageKey, MetadataError> {
+        let encoded_keys = keys.encoded_keys();
+        if encoded_keys.len() > self.hashers.len() {
+            return Err(MetadataError::StorageKeyCountMismatch {
+                expected: self.hashers.len(),
+                got: encoded_keys.len(),
+            });
+        }
+        Ok(self.hash_keys(&encoded_keys))
+    }
+
+    /// The keys of a `storage_key` of this map, the inverse of [`StorageNMap::key`]
+    pub fn decode_key<K: DecodeStorageKeys>(
+        &self,
+        storage_key: &StorageKey,
+    ) -> Result<K, MetadataError> {
+        check_key_count(self.hashers.len(), K::count())?;
+        let mut input = strip_prefix(
+            storage_key,
+            &self.module_prefix,
+            &self.storage_prefix,
+        )?;
+        let keys = K::decode_keys(&mut input, &self.hashers)?;
+        ensure_consumed(input)?;
+        Ok(keys)
+    }
+
+    fn hash_keys(&self, encoded_keys: &[Vec<u8>]) -> StorageKey {
+        let mut bytes = sp_core::twox_128(&self.module_prefix).to_vec();
+        bytes.extend(&sp_core::twox_128(&self.storage_prefix)[..]);
+        for (encoded_key, hasher) in encoded_keys.iter().zip(&self.hashers) {
+            bytes.extend(encoded_key_hash(encoded_key, hasher));
+        }
+        StorageKey(bytes)
+    }
+}
+
+/// The keys of a [`StorageNMap`] entry, a tuple of keys which are encoded separately
+/// since each one is hashed with its own hasher
+pub trait StorageKeys {
+    fn encoded_keys(&self) -> Vec<Vec<u8>>;
+}
+
+/// Decode the keys of a [`StorageNMap`] entry
+pub trait DecodeStorageKeys: Sized {
+    /// the number of keys
+    fn count() -> usize;
+    /// decode the keys from `input`, unhashing each one with its hasher
+    fn decode_keys(
+        input: &mut &[u8],
+        hashers: &[StorageHasher],
+    ) -> Result<Self, MetadataError>;
+}
+
+/// no keys, for the prefix of the whole map
+impl StorageKeys for () {
+    fn encoded_keys(&self) -> Vec<Vec<u8>> {
+        vec![]
+    }
+}
+
+macro_rules! impl_storage_keys {
+    ($($key:ident $index:tt),+) => {
+        impl<$($key: Encode),+> StorageKeys for ($($key,)+) {
+            fn encoded_keys(&self) -> Vec<Vec<u8>> {
+                vec![$(self.$index.encode()),+]
+            }
+        }
+
+        impl<$($key: Decode),+> DecodeStorageKeys for ($($key,)+) {
+            fn count() -> usize {
+                [$($index),+].len()
+            }
+
+            fn decode_keys(
+                input: &mut &[u8],
+                hashers: &[StorageHasher],
+            ) -> Result<Self, MetadataError> {
+                Ok(($(key_unhash::<$key>(input, &hashers[$index])?,)+))
+            }
+        }
+    };
+}
+
+impl_storage_keys!(A 0);
+impl_storage_keys!(A 0, B 1);
+impl_storage_keys!(A 0, B 1, C 2);
+impl_storage_keys!(A 0, B 1, C 2, D 3);
+impl_storage_keys!(A 0, B 1, C 2, D 3, E 4);
+impl_storage_keys!(A 0, B 1, C 2, D 3, E 4, F 5);
+
 /// trait to extract the storage based on the [`StorageEntryMetadata`].
 pub trait GetStorage {
-    fn get_double_map<K: Encode, Q: Encode>(
+    fn get_double_map<K, Q>(
         &self,
         pallet_prefix: &str,
     ) -> Result<StorageDoubleMap<K, Q>, MetadataError>;
-    fn get_map<K: Encode>(
+    fn get_map<K>(
         &self,
         pallet_prefix: &str,
     ) -> Result<StorageMap<K>, MetadataError>;
@@ -91,6 +248,10 @@ pub trait GetStorage {
         &self,
         pallet_prefix: &str,
     ) -> Result<StorageKey, MetadataError>;
+    fn get_nmap(
+        &self,
+        pallet_prefix: &str,
+    ) -> Result<StorageNMap, MetadataError>;
     fn get_value(
         &self,
         pallet_prefix: &str,
@@ -98,12 +259,13 @@ pub trait GetStorage {
 }
 
 impl GetStorage for StorageEntryMetadata<PortableForm> {
-    fn get_double_map<K: Encode, Q: Encode>(
+    fn get_double_map<K, Q>(
         &self,
         pallet_prefix: &str,
     ) -> Result<StorageDoubleMap<K, Q>, MetadataError> {
         match &self.ty {
             StorageEntryType::Map { hashers, .. } => {
+                check_key_count(hashers.len(), 2)?;
                 let module_prefix = pallet_prefix.as_bytes().to_vec();
                 let storage_prefix = self.name.as_bytes().to_vec();
                 let hasher1 =
@@ -132,12 +294,13 @@ impl GetStorage for StorageEntryMetadata<PortableForm> {
         }
     }
 
-    fn get_map<K: Encode>(
+    fn get_map<K>(
         &self,
         pallet_prefix: &str,
     ) -> Result<StorageMap<K>, MetadataError> {
         match &self.ty {
             StorageEntryType::Map { hashers, .. } => {
+                check_key_count(hashers.len(), 1)?;
                 let hasher = hashers
                     .get(0)
                     .ok_or(MetadataError::StorageTypeError)?
@@ -178,6 +341,22 @@ impl GetStorage for StorageEntryMetadata<PortableForm> {
         }
     }
 
+    fn get_nmap(
+        &self,
+        pallet_prefix: &str,
+    ) -> Result<StorageNMap, MetadataError> {
+        match &self.ty {
+            StorageEntryType::Map { hashers, .. } => {
+                Ok(StorageNMap {
+                    module_prefix: pallet_prefix.as_bytes().to_vec(),
+                    storage_prefix: self.name.as_bytes().to_vec(),
+                    hashers: hashers.clone(),
+                })
+            }
+            _ => Err(MetadataError::StorageTypeError),
+        }
+    }
+
     fn get_value(
         &self,
         pallet_prefix: &str,
@@ -196,30 +375,242 @@ impl GetStorage for StorageEntryMetadata<PortableForm> {
     }
 }
 
+/// A change of the value at `key` in block `block_hash`,
+/// the value is `None` when the storage item was removed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageChange<V> {
+    pub block_hash: H256,
+    pub key: StorageKey,
+    pub value: Option<V>,
+}
+
+impl<V> StorageChange<V> {
+    /// Convert the value, ie: decode the raw bytes
+    pub fn try_map<T, E, F>(self, f: F) -> Result<StorageChange<T>, E>
+    where
+        F: FnOnce(V) -> Result<T, E>,
+    {
+        Ok(StorageChange {
+            block_hash: self.block_hash,
+            key: self.key,
+            value: self.value.map(f).transpose()?,
+        })
+    }
+}
+
+/// The changes of the storage values in one block
+pub type StorageChangeSet<V> = Vec<StorageChange<V>>;
+
 /// generates the key's hash depending on the StorageHasher selected
 fn key_hash<K: Encode>(key: &K, hasher: &StorageHasher) -> Vec<u8> {
-    let encoded_key = key.encode();
+    encoded_key_hash(&key.encode(), hasher)
+}
+
+/// the hash of an already encoded key
+fn encoded_key_hash(encoded_key: &[u8], hasher: &StorageHasher) -> Vec<u8> {
     match hasher {
         StorageHasher::Identity => encoded_key.to_vec(),
-        StorageHasher::Blake2_128 => sp_core::blake2_128(&encoded_key).to_vec(),
+        StorageHasher::Blake2_128 => sp_core::blake2_128(encoded_key).to_vec(),
         StorageHasher::Blake2_128Concat => {
             // copied from substrate Blake2_128Concat::hash since StorageHasher is not public
-            let x: &[u8] = encoded_key.as_slice();
+            let x: &[u8] = encoded_key;
             sp_core::blake2_128(x)
                 .iter()
                 .chain(x.iter())
                 .cloned()
                 .collect::<Vec<_>>()
         }
-        StorageHasher::Blake2_256 => sp_core::blake2_256(&encoded_key).to_vec(),
-        StorageHasher::Twox128 => sp_core::twox_128(&encoded_key).to_vec(),
-        StorageHasher::Twox256 => sp_core::twox_256(&encoded_key).to_vec(),
+        StorageHasher::Blake2_256 => sp_core::blake2_256(encoded_key).to_vec(),
+        StorageHasher::Twox128 => sp_core::twox_128(encoded_key).to_vec(),
+        StorageHasher::Twox256 => sp_core::twox_256(encoded_key).to_vec(),
         StorageHasher::Twox64Concat => {
-            sp_core::twox_64(&encoded_key)
+            sp_core::twox_64(encoded_key)
                 .iter()
-                .chain(&encoded_key)
+                .chain(encoded_key)
                 .cloned()
                 .collect()
         }
     }
 }
+
+/// the bytes of the `storage_key` after the pallet and storage prefixes
+pub(crate) fn strip_prefix<'a>(
+    storage_key: &'a StorageKey,
+    module_prefix: &[u8],
+    storage_prefix: &[u8],
+) -> Result<&'a [u8], MetadataError> {
+    let mut prefix = sp_core::twox_128(module_prefix).to_vec();
+    prefix.extend(&sp_core::twox_128(storage_prefix)[..]);
+    storage_key
+        .0
+        .strip_prefix(prefix.as_slice())
+        .ok_or_else(|| {
+            MetadataError::InvalidStorageKey(format!(
+                "0x{} is not a key of {}.{}",
+                hex::encode(&storage_key.0),
+                String::from_utf8_lossy(module_prefix),
+                String::from_utf8_lossy(storage_prefix),
+            ))
+        })
+}
+
+/// The inverse of [`key_hash`], reads the key of a `Twox64Concat`, `Blake2_128Concat`
+/// or `Identity` hasher from the `input`, leaving the bytes of the next keys.
+/// The other hashers only keep the hash of the key, which can not be reversed.
+pub fn key_unhash<K: Decode>(
+    input: &mut &[u8],
+    hasher: &StorageHasher,
+) -> Result<K, MetadataError> {
+    skip_key_hash(input, hasher)?;
+    K::decode(input).map_err(MetadataError::StorageKeyDecodeError)
+}
+
+/// skip the hash in front of the encoded key in `input`
+pub(crate) fn skip_key_hash(
+    input: &mut &[u8],
+    hasher: &StorageHasher,
+) -> Result<(), MetadataError> {
+    let hash_len = match hasher {
+        StorageHasher::Identity => 0,
+        StorageHasher::Twox64Concat => 8,
+        StorageHasher::Blake2_128Concat => 16,
+        _ => return Err(MetadataError::NonReversibleHasher(hasher.clone())),
+    };
+    if input.len() < hash_len {
+        return Err(MetadataError::InvalidStorageKey(
+            "the storage key is shorter than the key hash".to_string(),
+        ));
+    }
+    *input = &input[hash_len..];
+    Ok(())
+}
+
+/// error out if the map has a different number of hashers than the keys
+fn check_key_count(hashers: usize, keys: usize) -> Result<(), MetadataError> {
+    if hashers == keys {
+        Ok(())
+    } else {
+        Err(MetadataError::StorageKeyCountMismatch {
+            expected: hashers,
+            got: keys,
+        })
+    }
+}
+
+/// error out if there are bytes left after the last key
+pub(crate) fn ensure_consumed(input: &[u8]) -> Result<(), MetadataError> {
+    if input.is_empty() {
+        Ok(())
+    } else {
+        Err(MetadataError::InvalidStorageKey(format!(
+            "{} trailing bytes after the last key",
+            input.len()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map<K>(hasher: StorageHasher) -> StorageMap<K> {
+        StorageMap {
+            _marker: PhantomData,
+            module_prefix: b"ForumModule".to_vec(),
+            storage_prefix: b"Kids".to_vec(),
+            hasher,
+        }
+    }
+
+    #[test]
+    fn decode_map_keys() {
+        for hasher in [
+            StorageHasher::Identity,
+            StorageHasher::Twox64Concat,
+            StorageHasher::Blake2_128Concat,
+        ] {
+            let map = map::<u32>(hasher);
+            assert_eq!(map.decode_key(&map.key(42)).unwrap(), 42);
+        }
+    }
+
+    #[test]
+    fn decode_double_map_keys() {
+        let double_map = StorageDoubleMap::<u32, (u8, String)> {
+            _marker: PhantomData,
+            _marker2: PhantomData,
+            module_prefix: b"ForumModule".to_vec(),
+            storage_prefix: b"Votes".to_vec(),
+            hasher: StorageHasher::Twox64Concat,
+            key2_hasher: StorageHasher::Blake2_128Concat,
+        };
+        let key2 = (7, "alice".to_string());
+        let storage_key = double_map.key(3, key2.clone());
+        assert_eq!(double_map.decode_key(&storage_key).unwrap(), (3, key2));
+    }
+
+    #[test]
+    fn nmap_keys_and_prefixes() {
+        let nmap = StorageNMap {
+            module_prefix: b"Staking".to_vec(),
+            storage_prefix: b"ErasStakersPaged".to_vec(),
+            hashers: vec![
+                StorageHasher::Twox64Concat,
+                StorageHasher::Twox64Concat,
+                StorageHasher::Blake2_128Concat,
+            ],
+        };
+        let keys = (7u32, [1u8; 32], 2u32);
+        let storage_key = nmap.key(&keys).unwrap();
+        assert_eq!(
+            nmap.decode_key::<(u32, [u8; 32], u32)>(&storage_key)
+                .unwrap(),
+            keys
+        );
+
+        let prefix = nmap.prefix(&(7u32, [1u8; 32])).unwrap();
+        assert!(storage_key.0.starts_with(&prefix.0));
+        assert_eq!(nmap.prefix(&()).unwrap().0.len(), 32);
+
+        assert!(matches!(
+            nmap.key(&(7u32, [1u8; 32])),
+            Err(MetadataError::StorageKeyCountMismatch {
+                expected: 3,
+                got: 2
+            })
+        ));
+        assert!(matches!(
+            nmap.prefix(&(7u32, [1u8; 32], 2u32, 0u8)),
+            Err(MetadataError::StorageKeyCountMismatch {
+                expected: 3,
+                got: 4
+            })
+        ));
+        assert!(matches!(
+            nmap.decode_key::<(u32, [u8; 32])>(&storage_key),
+            Err(MetadataError::StorageKeyCountMismatch {
+                expected: 3,
+                got: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn reject_non_reversible_and_foreign_keys() {
+        let hashed = map::<u32>(StorageHasher::Blake2_128);
+        assert!(matches!(
+            hashed.decode_key(&hashed.key(42)),
+            Err(MetadataError::NonReversibleHasher(
+                StorageHasher::Blake2_128
+            ))
+        ));
+
+        let mut other = map::<u32>(StorageHasher::Twox64Concat);
+        other.storage_prefix = b"AllPosts".to_vec();
+        let kids = map::<u32>(StorageHasher::Twox64Concat);
+        assert!(matches!(
+            kids.decode_key(&other.key(42)),
+            Err(MetadataError::InvalidStorageKey(_))
+        ));
+    }
+}
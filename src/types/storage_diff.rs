@@ -0,0 +1,422 @@
+//! The changes of the storage of a pallet between two blocks.
+
+use crate::{
+    types::dynamic,
+    Metadata,
+};
+use frame_metadata::v14::StorageEntryType;
+use serde_json::json;
+use sp_core::{
+    storage::StorageKey,
+    H256,
+};
+use std::{
+    collections::BTreeMap,
+    fmt,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl DiffKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DiffKind::Added => "added",
+            DiffKind::Removed => "removed",
+            DiffKind::Modified => "modified",
+        }
+    }
+}
+
+/// A storage key whose value differs between the two blocks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    pub kind: DiffKind,
+    pub key: StorageKey,
+    /// the name of the storage item the key belongs to
+    pub storage_name: Option<String>,
+    /// the map keys, when their hashers can be reversed
+    pub map_keys: Option<Vec<dynamic::Value>>,
+    pub before: Option<Vec<u8>>,
+    pub after: Option<Vec<u8>>,
+    /// the values decoded through the metadata of their block
+    pub before_decoded: Option<dynamic::Value>,
+    pub after_decoded: Option<dynamic::Value>,
+}
+
+/// The added, removed and modified keys of a pallet from block `from` to block `to`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageDiff {
+    pub pallet: String,
+    pub from: H256,
+    pub to: H256,
+    /// ordered by key
+    pub entries: Vec<DiffEntry>,
+}
+
+impl StorageDiff {
+    /// Compare the `before` entries at block `from` with the `after` entries at block `to`,
+    /// the keys and values are decoded with the metadata of their block where possible.
+    pub fn new(
+        pallet: &str,
+        from: (H256, &Metadata, BTreeMap<Vec<u8>, Vec<u8>>),
+        to: (H256, &Metadata, BTreeMap<Vec<u8>, Vec<u8>>),
+    ) -> Self {
+        let (from, from_metadata, before) = from;
+        let (to, to_metadata, after) = to;
+        let storage_names = |metadata: &Metadata| -> BTreeMap<Vec<u8>, String> {
+            metadata
+                .pallet(pallet)
+                .map(|pallet_metadata| {
+                    pallet_metadata
+                        .storage
+                        .keys()
+                        .map(|name| {
+                            let mut prefix =
+                                sp_core::twox_128(pallet.as_bytes()).to_vec();
+                            prefix.extend(sp_core::twox_128(name.as_bytes()));
+                            (prefix, name.clone())
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let from_names = storage_names(from_metadata);
+        let to_names = storage_names(to_metadata);
+
+        let entries = diff_entries(&before, &after)
+            .into_iter()
+            .map(|(key, before, after)| {
+                let kind = match (&before, &after) {
+                    (None, Some(_)) => DiffKind::Added,
+                    (Some(_), None) => DiffKind::Removed,
+                    _ => DiffKind::Modified,
+                };
+                // describe the key with the metadata of the block which has it
+                let (metadata, names) = if after.is_some() {
+                    (to_metadata, &to_names)
+                } else {
+                    (from_metadata, &from_names)
+                };
+                let storage_name =
+                    key.get(..32).and_then(|prefix| names.get(prefix)).cloned();
+                let key = StorageKey(key);
+                let map_keys = storage_name.as_ref().and_then(|name| {
+                    let entry =
+                        metadata.pallet(pallet).ok()?.storage(name).ok()?;
+                    match &entry.ty {
+                        // a plain storage value has no map keys
+                        StorageEntryType::Plain(_) => Some(vec![]),
+                        StorageEntryType::Map { .. } => {
+                            metadata
+                                .decode_storage_key_dynamic(pallet, name, &key)
+                                .ok()
+                        }
+                    }
+                });
+                let decode = |metadata: &Metadata, bytes: &Option<Vec<u8>>| {
+                    let name = storage_name.as_ref()?;
+                    let type_id =
+                        metadata.storage_value_type_id(pallet, name).ok()?;
+                    metadata.decode_dynamic(type_id, bytes.as_ref()?).ok()
+                };
+                DiffEntry {
+                    kind,
+                    before_decoded: decode(from_metadata, &before),
+                    after_decoded: decode(to_metadata, &after),
+                    key,
+                    storage_name,
+                    map_keys,
+                    before,
+                    after,
+                }
+            })
+            .collect();
+        StorageDiff {
+            pallet: pallet.to_string(),
+            from,
+            to,
+            entries,
+        }
+    }
+
+    pub fn added(&self) -> impl Iterator<Item = &DiffEntry> {
+        self.of_kind(DiffKind::Added)
+    }
+
+    pub fn removed(&self) -> impl Iterator<Item = &DiffEntry> {
+        self.of_kind(DiffKind::Removed)
+    }
+
+    pub fn modified(&self) -> impl Iterator<Item = &DiffEntry> {
+        self.of_kind(DiffKind::Modified)
+    }
+
+    fn of_kind(&self, kind: DiffKind) -> impl Iterator<Item = &DiffEntry> {
+        self.entries.iter().filter(move |entry| entry.kind == kind)
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let entries: Vec<serde_json::Value> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let map_keys = entry.map_keys.as_ref().map(|keys| {
+                    keys.iter().map(|key| key.to_json()).collect::<Vec<_>>()
+                });
+                json!({
+                    "kind": entry.kind.as_str(),
+                    "key": format!("0x{}", hex::encode(&entry.key.0)),
+                    "storage": entry.storage_name,
+                    "map_keys": map_keys,
+                    "before": entry.before.as_deref().map(to_hex),
+                    "after": entry.after.as_deref().map(to_hex),
+                    "before_decoded": entry.before_decoded.as_ref().map(dynamic::Value::to_json),
+                    "after_decoded": entry.after_decoded.as_ref().map(dynamic::Value::to_json),
+                })
+            })
+            .collect();
+        json!({
+            "pallet": self.pallet,
+            "from": self.from,
+            "to": self.to,
+            "entries": entries,
+        })
+    }
+}
+
+/// A readable report, one line per key with the before and after values
+impl fmt::Display for StorageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} storage from {:?} to {:?}: {} added, {} removed, {} modified",
+            self.pallet,
+            self.from,
+            self.to,
+            self.added().count(),
+            self.removed().count(),
+            self.modified().count()
+        )?;
+        for entry in self.entries.iter() {
+            let name = entry.storage_name.as_deref().unwrap_or("?");
+            let keys = match &entry.map_keys {
+                Some(keys) if !keys.is_empty() => {
+                    let keys: Vec<String> = keys
+                        .iter()
+                        .map(|key| key.to_json().to_string())
+                        .collect();
+                    format!("({})", keys.join(", "))
+                }
+                Some(_) => String::new(),
+                None => format!("[0x{}]", hex::encode(&entry.key.0)),
+            };
+            let describe =
+                |decoded: &Option<dynamic::Value>, bytes: &Option<Vec<u8>>| {
+                    match (decoded, bytes) {
+                        (Some(value), _) => value.to_json().to_string(),
+                        (None, Some(bytes)) => to_hex(bytes),
+                        (None, None) => "-".to_string(),
+                    }
+                };
+            writeln!(
+                f,
+                "  {:<8} {}{}: {} -> {}",
+                entry.kind.as_str(),
+                name,
+                keys,
+                describe(&entry.before_decoded, &entry.before),
+                describe(&entry.after_decoded, &entry.after)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// A raw key with its value before and after, `None` where the key is absent
+type ChangedEntry = (Vec<u8>, Option<Vec<u8>>, Option<Vec<u8>>);
+
+/// The keys whose values differ, with the value before and after, ordered by key
+fn diff_entries(
+    before: &BTreeMap<Vec<u8>, Vec<u8>>,
+    after: &BTreeMap<Vec<u8>, Vec<u8>>,
+) -> Vec<ChangedEntry> {
+    let mut keys: Vec<&Vec<u8>> = before.keys().chain(after.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter_map(|key| {
+            let old = before.get(key);
+            let new = after.get(key);
+            if old == new {
+                None
+            } else {
+                Some((key.clone(), old.cloned(), new.cloned()))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::metadata::test_utils::{
+        self,
+        Post,
+    };
+    use codec::Encode;
+
+    #[test]
+    fn diff_added_removed_modified() {
+        let before: BTreeMap<Vec<u8>, Vec<u8>> = [
+            (vec![1], vec![10]),
+            (vec![2], vec![20]),
+            (vec![3], vec![30]),
+        ]
+        .into_iter()
+        .collect();
+        let after: BTreeMap<Vec<u8>, Vec<u8>> = [
+            (vec![2], vec![21]),
+            (vec![3], vec![30]),
+            (vec![4], vec![40]),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            diff_entries(&before, &after),
+            vec![
+                (vec![1], Some(vec![10]), None),
+                (vec![2], Some(vec![20]), Some(vec![21])),
+                (vec![4], None, Some(vec![40])),
+            ]
+        );
+    }
+
+    /// the forum storage at two blocks: `ItemCounter` goes from 1 to 2,
+    /// the post 1 is removed, the post 2 is added and the replies of post 1 are unchanged
+    fn forum_diff() -> StorageDiff {
+        let metadata = test_utils::metadata();
+        let item_counter = metadata
+            .storage_value_key("ForumModule", "ItemCounter")
+            .unwrap();
+        let post_key = |post_id: u32| {
+            metadata
+                .storage_map_key("ForumModule", "AllPosts", post_id)
+                .unwrap()
+        };
+        let kids = metadata
+            .storage_map_key("ForumModule", "Kids", 1u32)
+            .unwrap();
+        let post = |post_id: u32| {
+            Post {
+                post_id,
+                content: b"hi".to_vec(),
+            }
+            .encode()
+        };
+        let before: BTreeMap<Vec<u8>, Vec<u8>> = [
+            (item_counter.0.clone(), 1u32.encode()),
+            (post_key(1).0, post(1)),
+            (kids.0.clone(), vec![2u32].encode()),
+        ]
+        .into_iter()
+        .collect();
+        let after: BTreeMap<Vec<u8>, Vec<u8>> = [
+            (item_counter.0, 2u32.encode()),
+            (post_key(2).0, post(2)),
+            (kids.0, vec![2u32].encode()),
+        ]
+        .into_iter()
+        .collect();
+        StorageDiff::new(
+            "ForumModule",
+            (H256::repeat_byte(1), &metadata, before),
+            (H256::repeat_byte(2), &metadata, after),
+        )
+    }
+
+    #[test]
+    fn new_describes_the_keys_with_the_metadata() {
+        let diff = forum_diff();
+        assert_eq!(diff.entries.len(), 3);
+        assert_eq!(diff.added().count(), 1);
+        assert_eq!(diff.removed().count(), 1);
+        assert_eq!(diff.modified().count(), 1);
+
+        let modified = diff.modified().next().unwrap();
+        assert_eq!(modified.storage_name.as_deref(), Some("ItemCounter"));
+        assert_eq!(modified.map_keys, Some(vec![]));
+        assert_eq!(modified.before_decoded, Some(dynamic::Value::U128(1)));
+        assert_eq!(modified.after_decoded, Some(dynamic::Value::U128(2)));
+
+        let added = diff.added().next().unwrap();
+        assert_eq!(added.storage_name.as_deref(), Some("AllPosts"));
+        assert_eq!(added.map_keys, Some(vec![dynamic::Value::U128(2)]));
+        assert_eq!(added.before, None);
+        assert!(added.after_decoded.is_some());
+
+        let removed = diff.removed().next().unwrap();
+        assert_eq!(removed.map_keys, Some(vec![dynamic::Value::U128(1)]));
+        assert_eq!(removed.after, None);
+    }
+
+    #[test]
+    fn to_json_has_every_entry() {
+        let json = forum_diff().to_json();
+        assert_eq!(json["pallet"], "ForumModule");
+        assert_eq!(
+            json["from"],
+            serde_json::to_value(H256::repeat_byte(1)).unwrap()
+        );
+        let entries = json["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 3);
+        let item_counter = entries
+            .iter()
+            .find(|entry| entry["storage"] == "ItemCounter")
+            .unwrap();
+        assert_eq!(item_counter["kind"], "modified");
+        assert_eq!(item_counter["before"], "0x01000000");
+        assert_eq!(item_counter["after"], "0x02000000");
+        assert_eq!(item_counter["before_decoded"], 1);
+        assert_eq!(item_counter["after_decoded"], 2);
+        let added = entries
+            .iter()
+            .find(|entry| entry["kind"] == "added")
+            .unwrap();
+        assert_eq!(added["storage"], "AllPosts");
+        assert_eq!(added["map_keys"], json!([2]));
+        assert_eq!(added["before"], serde_json::Value::Null);
+        assert_eq!(added["after_decoded"]["content"], "0x6869");
+    }
+
+    #[test]
+    fn display_one_line_per_key() {
+        let report = forum_diff().to_string();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            format!(
+                "ForumModule storage from {:?} to {:?}: 1 added, 1 removed, 1 modified",
+                H256::repeat_byte(1),
+                H256::repeat_byte(2)
+            )
+        );
+        assert!(lines.contains(&"  modified ItemCounter: 1 -> 2"));
+        assert!(lines.iter().any(|line| {
+            line.starts_with("  added    AllPosts(2): - -> {")
+        }));
+        assert!(lines.iter().any(|line| {
+            line.starts_with("  removed  AllPosts(1): {")
+                && line.ends_with("} -> -")
+        }));
+    }
+}
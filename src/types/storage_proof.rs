@@ -0,0 +1,104 @@
+//! Verify the storage read proofs returned by `state_getReadProof`,
+//! so the values read from an untrusted node can be checked against the `state_root`
+//! of a block header.
+
+use crate::error::Error;
+use sp_core::{
+    storage::StorageKey,
+    H256,
+};
+use sp_runtime::traits::BlakeTwo256;
+use sp_trie::{
+    LayoutV1,
+    StorageProof,
+};
+
+/// Read the values of the `keys` from the trie nodes of the `proof`.
+///
+/// Every node on the path of each key must be in the proof and hash up to the `state_root`,
+/// otherwise an error is returned. A key which is proven to be absent has a `None` value.
+///
+/// The trie layout only matters when building a trie, the nodes of both the V0 and V1 state
+/// versions are read the same way.
+pub fn verify_read_proof(
+    state_root: H256,
+    proof: Vec<Vec<u8>>,
+    keys: &[StorageKey],
+) -> Result<Vec<Option<Vec<u8>>>, Error> {
+    let db = StorageProof::new(proof).into_memory_db::<BlakeTwo256>();
+    keys.iter()
+        .map(|key| {
+            sp_trie::read_trie_value::<LayoutV1<BlakeTwo256>, _>(
+                &db,
+                &state_root,
+                &key.0,
+            )
+            .map_err(|e| {
+                Error::InvalidStorageProof(format!(
+                    "0x{}: {:?}",
+                    hex::encode(&key.0),
+                    e
+                ))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_runtime::traits::Hash;
+    use sp_trie::{
+        MemoryDB,
+        TrieDBMut,
+        TrieMut,
+    };
+
+    /// build a trie with the `entries`, returning the root and all of its nodes
+    fn build_trie(entries: &[(&[u8], &[u8])]) -> (H256, Vec<Vec<u8>>) {
+        let mut db = MemoryDB::<BlakeTwo256>::default();
+        let mut root = H256::default();
+        {
+            let mut trie =
+                TrieDBMut::<LayoutV1<BlakeTwo256>>::new(&mut db, &mut root);
+            for (key, value) in entries {
+                trie.insert(key, value).expect("must insert");
+            }
+        }
+        let nodes = db.drain().into_values().map(|(node, _rc)| node).collect();
+        (root, nodes)
+    }
+
+    #[test]
+    fn verify_values_and_absence() {
+        let (root, proof) =
+            build_trie(&[(b"alice", b"100"), (b"bob", &[7; 64])]);
+        let keys = [
+            StorageKey(b"alice".to_vec()),
+            StorageKey(b"bob".to_vec()),
+            StorageKey(b"charlie".to_vec()),
+        ];
+        let values = verify_read_proof(root, proof, &keys).unwrap();
+        assert_eq!(
+            values,
+            vec![Some(b"100".to_vec()), Some(vec![7; 64]), None]
+        );
+    }
+
+    #[test]
+    fn reject_wrong_root() {
+        let (_root, proof) = build_trie(&[(b"alice", b"100")]);
+        let keys = [StorageKey(b"alice".to_vec())];
+        assert!(verify_read_proof(H256::repeat_byte(1), proof, &keys).is_err());
+    }
+
+    #[test]
+    fn reject_incomplete_proof() {
+        let (root, mut proof) =
+            build_trie(&[(b"alice", b"100"), (b"bob", b"200")]);
+        // keep only the root node
+        proof.retain(|node| BlakeTwo256::hash(node) == root);
+        let keys = [StorageKey(b"alice".to_vec())];
+        assert!(verify_read_proof(root, proof, &keys).is_err());
+    }
+}
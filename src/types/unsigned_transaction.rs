@@ -0,0 +1,529 @@
+//! A portable document of a transaction to be signed on another machine.
+//!
+//! The online machine creates an [`UnsignedTransaction`] with `Api::unsigned_transaction`,
+//! it is then carried as json or in its compact binary form to an offline machine which
+//! verifies and signs it into a [`SignedTransaction`]. The signed document is brought back
+//! and submitted with `Api::submit_signed_document`.
+//!
+//! The payload is re-encoded from the fields of the document when signing, so what is
+//! signed is exactly what can be reviewed in the document.
+
+use crate::{
+    error::Error,
+    signer::{
+        Signer,
+        SignerPayload,
+    },
+    types::{
+        explorer::FieldInfo,
+        extrinsic_params::SignedPayload,
+        metadata::{
+            Encoded,
+            Metadata,
+            MetadataError,
+        },
+        signed_extensions::{
+            ExtensionParams,
+            SignedExtensions,
+        },
+    },
+};
+use codec::{
+    Decode,
+    Encode,
+    Input,
+    Output,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sp_core::{
+    crypto::AccountId32,
+    H256,
+};
+use sp_runtime::{
+    generic::Era,
+    MultiSignature,
+};
+
+/// The version of the document format
+pub const DOCUMENT_VERSION: u8 = 1;
+
+/// The pallet, name and arguments of the call, so it can be reviewed before signing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallDescription {
+    pub pallet: String,
+    pub call: String,
+    pub args: Vec<FieldInfo>,
+    pub docs: Vec<String>,
+}
+
+impl CallDescription {
+    /// Describe the encoded `call` using the metadata,
+    /// the first 2 bytes of the call are the pallet index and the call index.
+    pub fn from_call(
+        metadata: &Metadata,
+        call: &[u8],
+    ) -> Result<Self, MetadataError> {
+        let (pallet_index, call_index) = match call {
+            [pallet_index, call_index, ..] => (*pallet_index, *call_index),
+            _ => return Err(MetadataError::CallNotFound(hex::encode(call))),
+        };
+        let pallet = metadata
+            .pallets
+            .values()
+            .find(|pallet| pallet.index == pallet_index)
+            .ok_or(MetadataError::PalletIndexNotFound(pallet_index))?;
+        let pallet_info = metadata.explorer().pallet(&pallet.name)?;
+        let call_info = pallet_info
+            .calls
+            .into_iter()
+            .find(|call| call.index == call_index)
+            .ok_or_else(|| {
+                MetadataError::CallNotFound(format!(
+                    "{}[{}]",
+                    pallet.name, call_index
+                ))
+            })?;
+        Ok(Self {
+            pallet: pallet_info.name,
+            call: call_info.name,
+            args: call_info.args,
+            docs: call_info.docs,
+        })
+    }
+}
+
+/// The arguments are encoded as `(name, type)` pairs in the binary form of the document
+impl Encode for CallDescription {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        let args: Vec<(&Option<String>, &String)> =
+            self.args.iter().map(|arg| (&arg.name, &arg.ty)).collect();
+        self.pallet.encode_to(dest);
+        self.call.encode_to(dest);
+        args.encode_to(dest);
+        self.docs.encode_to(dest);
+    }
+}
+
+impl Decode for CallDescription {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        let pallet = String::decode(input)?;
+        let call = String::decode(input)?;
+        let args = Vec::<(Option<String>, String)>::decode(input)?
+            .into_iter()
+            .map(|(name, ty)| FieldInfo { name, ty })
+            .collect();
+        let docs = Vec::<String>::decode(input)?;
+        Ok(Self {
+            pallet,
+            call,
+            args,
+            docs,
+        })
+    }
+}
+
+/// A transaction waiting to be signed
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsignedTransaction {
+    /// the version of the document format
+    pub version: u8,
+    /// the encoded call
+    #[serde(with = "hex_bytes")]
+    pub call: Vec<u8>,
+    pub call_description: CallDescription,
+    #[serde(with = "ss58")]
+    pub signer: AccountId32,
+    pub nonce: u32,
+    #[serde(with = "era_hex")]
+    pub era: Era,
+    /// the block hash at which the era of a mortal transaction starts,
+    /// this is the genesis hash for immortal transactions
+    pub mortality_checkpoint: H256,
+    #[serde(with = "u128_string")]
+    #[codec(compact)]
+    pub tip: u128,
+    pub tip_asset: Option<u32>,
+    pub spec_version: u32,
+    pub transaction_version: u32,
+    pub genesis_hash: H256,
+    /// the identifiers of the signed extensions which are encoded in the payload
+    pub signed_extensions: Vec<String>,
+}
+
+/// A transaction document along with the signature of the signer
+#[derive(
+    Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedTransaction {
+    pub transaction: UnsignedTransaction,
+    #[serde(with = "signature_hex")]
+    pub signature: MultiSignature,
+}
+
+impl UnsignedTransaction {
+    /// The values used by the signed extensions of this transaction
+    pub fn extension_params(&self) -> ExtensionParams {
+        ExtensionParams {
+            spec_version: self.spec_version,
+            transaction_version: self.transaction_version,
+            genesis_hash: self.genesis_hash,
+            mortality_checkpoint: self.mortality_checkpoint,
+            era: self.era,
+            nonce: self.nonce,
+            tip: self.tip,
+            tip_asset: self.tip_asset,
+            metadata_hash: None,
+        }
+    }
+
+    /// Encode the signed extra and additional signed of this transaction
+    pub fn encode_extensions(
+        &self,
+        registry: &SignedExtensions,
+    ) -> Result<(Encoded, Encoded), Error> {
+        Ok(registry.encode_identifiers(
+            &self.signed_extensions,
+            &self.extension_params(),
+        )?)
+    }
+
+    /// The payload to be signed, which is hashed when it is longer than 256 bytes
+    pub fn signing_payload(
+        &self,
+        registry: &SignedExtensions,
+    ) -> Result<Vec<u8>, Error> {
+        let (extra, additional_signed) = self.encode_extensions(registry)?;
+        let payload = SignedPayload::from_raw(
+            Encoded(self.call.clone()),
+            extra,
+            additional_signed,
+        );
+        Ok(payload.using_encoded(|payload| payload.to_vec()))
+    }
+
+    /// Check that the document can be signed and that its call description matches the
+    /// encoded call, using the `metadata` of the runtime the document was created for.
+    /// The offline machine must have a copy of that metadata, since the call description
+    /// is what the user reviews before signing.
+    pub fn verify(&self, metadata: &Metadata) -> Result<(), Error> {
+        if self.version != DOCUMENT_VERSION {
+            return Err(Error::InvalidTransactionDocument(format!(
+                "unsupported document version {}",
+                self.version
+            )));
+        }
+        let description = CallDescription::from_call(metadata, &self.call)?;
+        if description != self.call_description {
+            return Err(Error::InvalidTransactionDocument(format!(
+                "the call is {}.{} but the document describes it as {}.{}",
+                description.pallet,
+                description.call,
+                self.call_description.pallet,
+                self.call_description.call
+            )));
+        }
+        Ok(())
+    }
+
+    /// Verify and sign this transaction, the `signer` must be the signer of the document
+    pub async fn sign(
+        &self,
+        signer: &dyn Signer,
+        registry: &SignedExtensions,
+        metadata: &Metadata,
+    ) -> Result<SignedTransaction, Error> {
+        self.verify(metadata)?;
+        if signer.account_id() != self.signer {
+            return Err(Error::InvalidTransactionDocument(format!(
+                "the document must be signed by {}",
+                ss58::to_string(&self.signer)
+            )));
+        }
+        let signer_payload = SignerPayload {
+            address: self.signer.clone(),
+            method: self.call.clone(),
+            params: self.extension_params(),
+            block_number: 0,
+            signed_extensions: self.signed_extensions.clone(),
+            version: 4,
+            encoded: self.signing_payload(registry)?,
+        };
+        let signature = signer.sign_payload(&signer_payload).await?;
+        Ok(SignedTransaction {
+            transaction: self.clone(),
+            signature,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// The compact binary form of this document
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::decode(&mut &bytes[..])?)
+    }
+}
+
+impl SignedTransaction {
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// The compact binary form of this document
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::decode(&mut &bytes[..])?)
+    }
+}
+
+mod hex_bytes {
+    use crate::utils::FromHexStr;
+    use serde::{
+        de::Error,
+        Deserialize,
+        Deserializer,
+        Serializer,
+    };
+
+    pub fn serialize<S: Serializer>(
+        bytes: &[u8],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", hex::encode(bytes)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Vec::from_hex(&hex).map_err(D::Error::custom)
+    }
+}
+
+mod era_hex {
+    use codec::{
+        Decode,
+        Encode,
+    };
+    use serde::{
+        de::Error,
+        Deserializer,
+        Serializer,
+    };
+    use sp_runtime::generic::Era;
+
+    pub fn serialize<S: Serializer>(
+        era: &Era,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        super::hex_bytes::serialize(&era.encode(), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Era, D::Error> {
+        let bytes = super::hex_bytes::deserialize(deserializer)?;
+        Era::decode(&mut &bytes[..]).map_err(D::Error::custom)
+    }
+}
+
+mod signature_hex {
+    use codec::{
+        Decode,
+        Encode,
+    };
+    use serde::{
+        de::Error,
+        Deserializer,
+        Serializer,
+    };
+    use sp_runtime::MultiSignature;
+
+    pub fn serialize<S: Serializer>(
+        signature: &MultiSignature,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        super::hex_bytes::serialize(&signature.encode(), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MultiSignature, D::Error> {
+        let bytes = super::hex_bytes::deserialize(deserializer)?;
+        MultiSignature::decode(&mut &bytes[..]).map_err(D::Error::custom)
+    }
+}
+
+mod ss58 {
+    use serde::{
+        de::Error,
+        Deserialize,
+        Deserializer,
+        Serializer,
+    };
+    use sp_core::crypto::{
+        AccountId32,
+        Ss58Codec,
+    };
+
+    pub fn to_string(account: &AccountId32) -> String {
+        account.to_ss58check()
+    }
+
+    pub fn serialize<S: Serializer>(
+        account: &AccountId32,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_string(account))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<AccountId32, D::Error> {
+        let address = String::deserialize(deserializer)?;
+        AccountId32::from_ss58check(&address)
+            .map_err(|e| D::Error::custom(format!("{:?}", e)))
+    }
+}
+
+/// amounts are written as strings since they don't fit in the numbers of javascript
+mod u128_string {
+    use serde::{
+        de::Error,
+        Deserialize,
+        Deserializer,
+        Serializer,
+    };
+
+    pub fn serialize<S: Serializer>(
+        value: &u128,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<u128, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::metadata::test_utils;
+    use sp_core::{
+        sr25519,
+        Pair,
+    };
+    use sp_runtime::traits::Verify;
+
+    /// `Balances.transfer` of 42 to the account `[1; 32]`
+    fn transfer_call() -> Vec<u8> {
+        ([test_utils::BALANCES, 0], [1u8; 32], 42u128).encode()
+    }
+
+    fn transaction(signer: AccountId32) -> UnsignedTransaction {
+        let call = transfer_call();
+        UnsignedTransaction {
+            version: DOCUMENT_VERSION,
+            call_description: CallDescription::from_call(
+                &test_utils::metadata(),
+                &call,
+            )
+            .unwrap(),
+            call,
+            signer,
+            nonce: 3,
+            era: Era::mortal(64, 100),
+            mortality_checkpoint: H256::repeat_byte(1),
+            tip: u128::MAX,
+            tip_asset: None,
+            spec_version: 100,
+            transaction_version: 1,
+            genesis_hash: H256::repeat_byte(2),
+            signed_extensions: vec![
+                "CheckSpecVersion".to_string(),
+                "CheckGenesis".to_string(),
+                "CheckMortality".to_string(),
+                "CheckNonce".to_string(),
+                "ChargeTransactionPayment".to_string(),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_json_and_bytes_roundtrip() {
+        let (pair, _) = sr25519::Pair::generate();
+        let tx = transaction(AccountId32::from(pair.public()));
+        let json = tx.to_json().unwrap();
+        assert_eq!(UnsignedTransaction::from_json(&json).unwrap(), tx);
+        assert_eq!(
+            UnsignedTransaction::from_bytes(&tx.to_bytes()).unwrap(),
+            tx
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_sign_offline() {
+        let (pair, _) = sr25519::Pair::generate();
+        let tx = transaction(AccountId32::from(pair.public()));
+        let registry = SignedExtensions::default();
+        let metadata = test_utils::metadata();
+        let signed = tx.sign(&pair, &registry, &metadata).await.unwrap();
+        let signed =
+            SignedTransaction::from_json(&signed.to_json().unwrap()).unwrap();
+        let payload = tx.signing_payload(&registry).unwrap();
+        assert!(signed
+            .signature
+            .verify(&payload[..], &AccountId32::from(pair.public())));
+
+        let (other, _) = sr25519::Pair::generate();
+        assert!(tx.sign(&other, &registry, &metadata).await.is_err());
+    }
+
+    #[test]
+    fn test_verify_call_description() {
+        let (pair, _) = sr25519::Pair::generate();
+        let metadata = test_utils::metadata();
+        let tx = transaction(AccountId32::from(pair.public()));
+        assert_eq!(tx.call_description.pallet, "Balances");
+        assert_eq!(tx.call_description.call, "transfer");
+        assert!(tx.verify(&metadata).is_ok());
+
+        // a document describing a harmless call while carrying a transfer
+        let mut forged = tx;
+        forged.call_description.pallet = "ForumModule".to_string();
+        forged.call_description.call = "post_content".to_string();
+        assert!(matches!(
+            forged.verify(&metadata),
+            Err(Error::InvalidTransactionDocument(_))
+        ));
+    }
+}
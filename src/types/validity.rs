@@ -0,0 +1,89 @@
+//! The outcome of validating or dry running an extrinsic before submitting it
+use crate::types::metadata::Metadata;
+use sp_runtime::{
+    transaction_validity::TransactionValidityError,
+    ApplyExtrinsicResult,
+    DispatchError,
+};
+
+/// The outcome of applying an extrinsic on top of a block, without committing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DryRunResult {
+    /// The extrinsic is included and the call succeeds
+    Success,
+    /// The extrinsic is included, paying its fee, but the call fails
+    Failed(DispatchFailure),
+    /// The extrinsic is rejected by the transaction pool, ie: bad nonce or not enough balance
+    /// to pay the fee
+    Invalid(TransactionValidityError),
+}
+
+/// The reason a call failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DispatchFailure {
+    /// An error declared by a pallet, resolved through the metadata
+    Pallet(PalletError),
+    /// Any other dispatch error, or a pallet error which is not in the metadata
+    Other(DispatchError),
+}
+
+/// An error declared in the `#[pallet::error]` of a pallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PalletError {
+    pub pallet: String,
+    pub error: String,
+    pub docs: Vec<String>,
+}
+
+impl DryRunResult {
+    /// Resolve the pallet errors of the `result` using the metadata
+    pub fn from_apply_result(
+        result: ApplyExtrinsicResult,
+        metadata: &Metadata,
+    ) -> Self {
+        match result {
+            Ok(Ok(())) => DryRunResult::Success,
+            Ok(Err(dispatch_error)) => {
+                DryRunResult::Failed(DispatchFailure::from_dispatch_error(
+                    dispatch_error,
+                    metadata,
+                ))
+            }
+            Err(validity_error) => DryRunResult::Invalid(validity_error),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self, DryRunResult::Success)
+    }
+
+    /// The pallet error of a failed call
+    pub fn pallet_error(&self) -> Option<&PalletError> {
+        match self {
+            DryRunResult::Failed(DispatchFailure::Pallet(error)) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl DispatchFailure {
+    pub fn from_dispatch_error(
+        dispatch_error: DispatchError,
+        metadata: &Metadata,
+    ) -> Self {
+        if let DispatchError::Module(module_error) = &dispatch_error {
+            // the first byte is the index of the error variant,
+            // the rest is reserved for the fields of the variant
+            if let Ok(error) =
+                metadata.get_error(module_error.index, module_error.error[0])
+            {
+                return DispatchFailure::Pallet(PalletError {
+                    pallet: error.pallet().to_string(),
+                    error: error.error().to_string(),
+                    docs: error.description().to_vec(),
+                });
+            }
+        }
+        DispatchFailure::Other(dispatch_error)
+    }
+}
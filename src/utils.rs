@@ -22,6 +22,7 @@ use alloc::vec::Vec;
 
 use hex::FromHexError;
 use sp_core::H256;
+use std::time::Duration;
 
 pub trait FromHexStr {
     fn from_hex(hex: &str) -> Result<Self, hex::FromHexError>
@@ -48,6 +49,34 @@ impl FromHexStr for H256 {
     }
 }
 
+/// Wait for `duration`, using the browser timers in wasm
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+/// Wait for `duration`, using the browser timers in wasm
+#[cfg(target_arch = "wasm32")]
+pub async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await
+}
+
+/// The time elapsed since the unix epoch,
+/// `std::time` can not read the clock in wasm so the browser clock is used
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now() -> Duration {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// The time elapsed since the unix epoch,
+/// `std::time` can not read the clock in wasm so the browser clock is used
+#[cfg(target_arch = "wasm32")]
+pub fn now() -> Duration {
+    Duration::from_millis(js_sys::Date::now() as u64)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
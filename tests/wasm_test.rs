@@ -3,7 +3,21 @@
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_test::*;
 wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
-use mycelium::BaseApi;
+use mycelium::{
+    signer::{
+        JsSigner,
+        KeyType,
+        SignerPayload,
+    },
+    sp_core::crypto::AccountId32,
+    sp_runtime::{
+        generic::Era,
+        MultiSignature,
+    },
+    types::signed_extensions::ExtensionParams,
+    BaseApi,
+    Signer,
+};
 
 #[wasm_bindgen]
 extern "C" {
@@ -15,6 +29,78 @@ extern "C" {
     fn alert(s: &str);
 }
 
+#[wasm_bindgen(inline_js = "
+export function stub_signer() {
+    const signer = {
+        signPayload: async (payload) => {
+            signer.lastPayload = payload;
+            return { id: 1, signature: '0x01' + '11'.repeat(64) };
+        },
+        signRaw: (raw) => {
+            signer.lastRaw = raw;
+            return { id: 2, signature: '0x' + '22'.repeat(64) };
+        },
+    };
+    return signer;
+}")]
+extern "C" {
+    fn stub_signer() -> JsValue;
+}
+
+// the ss58 address of the account id [1; 32]
+const ADDRESS: &str = "5C62Ck4UrFPiBtoCmeSrgF7x9yv9mn38446dhCpsi2mLHiFT";
+
+#[wasm_bindgen_test]
+async fn js_signer_sign_payload() {
+    let stub = stub_signer();
+    let signer = JsSigner::new(ADDRESS, KeyType::Sr25519, stub.clone())
+        .expect("must be a valid address");
+    assert_eq!(signer.account_id(), AccountId32::new([1; 32]));
+    let payload = SignerPayload {
+        address: AccountId32::new([1; 32]),
+        method: vec![8, 0],
+        params: ExtensionParams {
+            spec_version: 100,
+            transaction_version: 1,
+            genesis_hash: Default::default(),
+            mortality_checkpoint: Default::default(),
+            era: Era::immortal(),
+            nonce: 3,
+            tip: 0,
+            tip_asset: None,
+            metadata_hash: None,
+        },
+        block_number: 0,
+        signed_extensions: vec!["CheckNonce".to_string()],
+        version: 4,
+        encoded: vec![8, 0, 0],
+    };
+    let signature = signer
+        .sign_payload(&payload)
+        .await
+        .expect("must sign the payload");
+    assert!(matches!(signature, MultiSignature::Sr25519(_)));
+
+    let last_payload =
+        js_sys::Reflect::get(&stub, &JsValue::from_str("lastPayload")).unwrap();
+    let get = |field: &str| {
+        js_sys::Reflect::get(&last_payload, &JsValue::from_str(field))
+            .unwrap()
+            .as_string()
+    };
+    assert_eq!(get("address").as_deref(), Some(ADDRESS));
+    assert_eq!(get("method").as_deref(), Some("0x0800"));
+    assert_eq!(get("nonce").as_deref(), Some("0x00000003"));
+}
+
+#[wasm_bindgen_test]
+async fn js_signer_sign_raw() {
+    let signer = JsSigner::new(ADDRESS, KeyType::Ed25519, stub_signer())
+        .expect("must be a valid address");
+    let signature = signer.sign(b"hello").await.expect("must sign the bytes");
+    assert!(matches!(signature, MultiSignature::Ed25519(_)));
+}
+
 #[wasm_bindgen_test]
 async fn wasm_test1() {
     log("hello!");